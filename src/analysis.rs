@@ -0,0 +1,127 @@
+//! Signal analysis routines that locate features within interleaved sample buffers
+
+/// RMS window length used by silence detection in milliseconds
+pub const SILENCE_WINDOW_MS: f64 = 10.0;
+
+/// Find runs of silence
+///
+/// # Parameters
+/// * `data` - interleaved samples
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `threshold` - linear RMS level below which audio counts as silent
+/// * `min_frames` - minimum length of a silent run in frames
+///
+/// # Returns
+/// `Vec<(usize, usize)>` - (start_frame, end_frame) of each silent run, end exclusive
+///
+/// # Notes
+/// Each frame is judged by the RMS of a short window centred on it (across all
+/// channels), so an isolated quiet sample does not start a run and an isolated
+/// loud sample does not split one.
+pub fn detect_silence(data: &[f32], channels: usize, sample_rate: u32, threshold: f64, min_frames: usize) -> Vec<(usize, usize)>
+{
+    let frames = data.len() / channels;
+    let window = ((SILENCE_WINDOW_MS * sample_rate as f64 / 1000.0) as usize).max(1);
+    let half = window / 2;
+
+    // prefix sums of per-frame energy for O(1) window queries
+    let mut energy = Vec::with_capacity(frames + 1);
+    energy.push(0.0f64);
+    for frame in data.chunks_exact(channels)
+    {
+        let frame_energy: f64 = frame.iter().map(|&s| (s as f64) * (s as f64)).sum();
+        energy.push(energy.last().unwrap() + frame_energy);
+    }
+
+    let threshold_sq = threshold * threshold;
+    let mut runs = Vec::new();
+    let mut run_start: Option<usize> = None;
+
+    for n in 0..=frames
+    {
+        let silent = if n < frames
+        {
+            let lo = n.saturating_sub(half);
+            let hi = (n + half + 1).min(frames);
+            let mean_sq = (energy[hi] - energy[lo]) / ((hi - lo) * channels) as f64;
+            mean_sq < threshold_sq
+        }
+        else
+        {
+            false
+        };
+
+        match (silent, run_start)
+        {
+            (true, None) => run_start = Some(n),
+            (false, Some(start)) =>
+            {
+                if n - start >= min_frames.max(1)
+                {
+                    runs.push((start, n));
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    runs
+}
+
+/// Find runs of clipped samples
+///
+/// # Parameters
+/// * `data` - interleaved samples
+/// * `channels` - number of interleaved channels
+/// * `min_run` - minimum number of consecutive clipped samples on a channel to report
+///
+/// # Returns
+/// `Vec<(usize, usize)>` - (start_frame, end_frame) of each clipped span, end exclusive, sorted and merged across channels
+///
+/// # Notes
+/// A sample is clipped when its magnitude is at or above full scale (1.0), which
+/// also catches floating point overs
+pub fn detect_clipping(data: &[f32], channels: usize, min_run: usize) -> Vec<(usize, usize)>
+{
+    let frames = data.len() / channels;
+    let min_run = min_run.max(1);
+    let mut spans = Vec::new();
+
+    for ch in 0..channels
+    {
+        let mut run_start: Option<usize> = None;
+        for n in 0..=frames
+        {
+            let clipped = n < frames && data[n * channels + ch].abs() >= 1.0;
+            match (clipped, run_start)
+            {
+                (true, None) => run_start = Some(n),
+                (false, Some(start)) =>
+                {
+                    if n - start >= min_run
+                    {
+                        spans.push((start, n));
+                    }
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // merge overlapping spans from different channels
+    spans.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans
+    {
+        match merged.last_mut()
+        {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
@@ -1,22 +1,276 @@
 use symphonia::core::audio::{AudioBufferRef, Signal};
 use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
 use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey, Value};
 use symphonia::core::probe::Hint;
+use rayon::prelude::*;
+use std::collections::HashMap;
 use std::fs::File;
 use std::path::Path;
 use std::io::Write;
-use crate::playback::AudioPlayback;
+use crate::analysis;
+use crate::denoise::NoiseProfile;
+use crate::effects;
+use crate::filters::{self, BiquadCoefficients};
+use crate::flac::{CueSheet, FlacOptions};
+use crate::generators;
+use crate::pcm::{self, RawFormat};
+use crate::playback::{self, AudioPlayback, PositionProbe};
+use crate::project;
+use crate::resample::{self, ResampleQuality};
+use crate::samples::{MappedSampleWriter, SampleStore};
+use crate::waveform;
+
+/// File extensions that can be imported, as decoded by symphonia
+pub const IMPORT_FORMATS: &[&str] = &[
+    "wav", "aif", "aiff", "flac", "mp3", "mp2", "mp1", "ogg", "oga",
+    "m4a", "mp4", "aac", "caf", "mka", "mkv", "webm",
+];
+
+/// File extensions that can be exported
+pub const EXPORT_FORMATS: &[&str] = &["wav", "flac", "mp3", "ogg"];
+
+/// Number of interleaved samples written between export progress updates
+const EXPORT_PROGRESS_CHUNK: usize = 65536;
+
+/// Tolerance in frames when converting a time to a frame, absorbing floating point
+/// error so a time computed from frame n maps back to n rather than n - 1
+const FRAME_EPSILON: f64 = 1e-6;
+
+/// PADDING block length of exported FLAC files, left free for later tag edits
+pub const DEFAULT_FLAC_PADDING_BYTES: usize = 4096;
+
+/// Metadata tags written into exported files
+#[derive(Clone, Default)]
+pub struct ExportTags
+{
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub year: Option<i32>,
+}
+
+impl ExportTags
+{
+    /// Build tags from a key/value map
+    ///
+    /// # Parameters
+    /// * `map` - tag values keyed by 'title', 'artist', 'album', or 'year'
+    ///
+    /// # Returns
+    /// `Result<ExportTags, String>` - Ok with the tags, Err on an unknown key or non-numeric year
+    pub fn from_map(map: &HashMap<String, String>) -> Result<Self, String>
+    {
+        let mut tags = ExportTags::default();
+        for (key, value) in map
+        {
+            match key.to_lowercase().as_str()
+            {
+                "title" => tags.title = Some(value.clone()),
+                "artist" => tags.artist = Some(value.clone()),
+                "album" => tags.album = Some(value.clone()),
+                "year" =>
+                {
+                    tags.year = Some(value.trim().parse().map_err(|_| format!("Invalid year: {}", value))?);
+                }
+                _ => return Err(format!("Unknown tag: {} (expected 'title', 'artist', 'album', or 'year')", key)),
+            }
+        }
+        Ok(tags)
+    }
+
+    /// Tags as Vorbis comment fields
+    ///
+    /// # Returns
+    /// `Vec<(String, String)>` - (field name, value) pairs for the tags that are set
+    pub fn vorbis_comments(&self) -> Vec<(String, String)>
+    {
+        let mut comments = Vec::new();
+        if let Some(ref title) = self.title
+        {
+            comments.push(("TITLE".to_string(), title.clone()));
+        }
+        if let Some(ref artist) = self.artist
+        {
+            comments.push(("ARTIST".to_string(), artist.clone()));
+        }
+        if let Some(ref album) = self.album
+        {
+            comments.push(("ALBUM".to_string(), album.clone()));
+        }
+        if let Some(year) = self.year
+        {
+            comments.push(("DATE".to_string(), year.to_string()));
+        }
+        comments
+    }
+}
+
+/// Encoder settings for `write_export`
+///
+/// # Notes
+/// Each setting only applies to the formats named; None selects the format default
+#[derive(Clone, Default)]
+pub struct ExportOptions
+{
+    pub compression_level: Option<u8>,  // FLAC compression level 0-8
+    pub bitrate_kbps: Option<u32>,  // MP3 bitrate
+    pub quality: Option<f32>,  // Ogg Vorbis VBR quality -0.2 to 1.0
+    pub bit_depth: Option<u16>,  // WAV bit depth 16, 24, or 32 (float); FLAC bit depth 16 or 24
+    pub flac_block_size: Option<usize>,  // FLAC samples per channel in each frame, 16-65535
+    pub cue_sheet: Option<CueSheet>,  // FLAC CUESHEET block marking tracks within the file
+    pub flac_padding: Option<usize>,  // FLAC PADDING block length in bytes, 0 to omit it
+    pub flac_dither: bool,  // add TPDF dither when quantizing FLAC samples
+    pub tags: ExportTags,  // MP3 ID3v2 and FLAC Vorbis comment tags
+}
+
+/// Brickwall limiter settings for the export mix bus
+///
+/// # Notes
+/// Used in place of clamping the summed mix, so an over-level mix is turned
+/// down smoothly instead of clipping.
+#[derive(Clone, Copy)]
+pub struct BusLimiter
+{
+    pub ceiling_db: f64,  // maximum output level in dBFS
+    pub release_ms: f64,  // gain recovery time in milliseconds
+}
+
+impl BusLimiter
+{
+    /// Create limiter settings
+    ///
+    /// # Parameters
+    /// * `ceiling_db` - maximum output level in dBFS, at most 0
+    /// * `release_ms` - gain recovery time in milliseconds
+    ///
+    /// # Returns
+    /// `Result<BusLimiter, String>` - Ok with the settings, Err if either is out of range
+    pub fn new(ceiling_db: f64, release_ms: f64) -> Result<Self, String>
+    {
+        if ceiling_db > 0.0 || !ceiling_db.is_finite()
+        {
+            return Err(format!("Invalid limiter ceiling: {}", ceiling_db));
+        }
+        if release_ms < 0.0 || !release_ms.is_finite()
+        {
+            return Err(format!("Invalid release time: {}", release_ms));
+        }
+        Ok(BusLimiter { ceiling_db, release_ms })
+    }
+}
+
+/// Weights for folding stereo tracks down in the "mono" export mode
+///
+/// # Notes
+/// The default averages left and right at unity gain. Equal weights of about 0.707
+/// (-3 dB each) keep the loudness of uncorrelated material instead.
+#[derive(Clone, Copy)]
+pub struct MonoFold
+{
+    pub left: f32,  // linear weight of the left channel
+    pub right: f32,  // linear weight of the right channel
+    pub gain: f32,  // linear gain applied to the whole mono mix
+}
+
+impl Default for MonoFold
+{
+    fn default() -> Self
+    {
+        MonoFold
+        {
+            left: 0.5,
+            right: 0.5,
+            gain: 1.0,
+        }
+    }
+}
+
+/// Mixed audio ready to write, as (audio data, sample rate, channels, file suffix)
+pub type ExportItem = (Vec<f32>, u32, usize, String);
+
+/// Output path of a region and its mixed audio, as (path, `render_export` result)
+pub type RenderedRegion = (String, Vec<(Vec<f32>, u32, usize, String)>);
+
+/// Track information with its source format, as (name, sample_rate, channels, duration, start_offset, bits_per_sample, codec)
+pub type TrackDetails = (String, u32, usize, f64, f64, Option<u32>, Option<String>);
+
+/// How the current playback was started, kept so it can be restarted on another output device
+#[derive(Clone)]
+enum PlaybackMode
+{
+    Mix { end: Option<f64> },
+    Track { track_index: usize, end: Option<f64> },
+    Reverse { start: f64 },
+    Loop { start: f64, end: f64 },
+    Queue { regions: Vec<(f64, f64)>, crossfade_ms: f64, first_index: usize },  // regions before first_index were dropped when playback was restarted
+}
 
 /// Represents a single audio track
+#[derive(Clone)]
 pub struct AudioTrack
 {
-    pub audio_data: Vec<f32>,
+    pub audio_data: SampleStore,
     pub sample_rate: u32,
     pub channels: usize,
     pub name: String,
     pub start_offset: f64,  // time offset in seconds for when the track starts
+    pub gain_envelope: Vec<(f64, f64)>,  // (time in seconds from track start, gain in dB) breakpoints
+    pub metadata: HashMap<String, String>,  // tags read from the source file
+    pub source_format: SourceFormat,  // codec and bit depth of the source file
+}
+
+/// Codec details of the file a track was decoded from
+///
+/// # Notes
+/// Both fields are None for generated tracks, and bit depth is None for lossy
+/// codecs such as MP3 that have no fixed sample size
+#[derive(Clone, Default)]
+pub struct SourceFormat
+{
+    pub codec: Option<String>,  // symphonia's short codec name, e.g. 'flac' or 'pcm_s16le'
+    pub bits_per_sample: Option<u32>,
+}
+
+impl AudioTrack
+{
+    /// Get the envelope gain at a point in the track
+    ///
+    /// # Parameters
+    /// * `local_time` - time in seconds from the start of the track's audio
+    ///
+    /// # Returns
+    /// `f32` - linear gain factor (1.0 when the envelope is empty)
+    ///
+    /// # Notes
+    /// Breakpoints are converted to linear gain and interpolated linearly between them,
+    /// so a point at -inf dB fades smoothly to silence. The first and last values are
+    /// held before and after the envelope.
+    pub fn gain_at(&self, local_time: f64) -> f32
+    {
+        let (first, last) = match (self.gain_envelope.first(), self.gain_envelope.last())
+        {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return 1.0,
+        };
+
+        if local_time <= first.0
+        {
+            return effects::db_to_gain(first.1) as f32;
+        }
+        if local_time >= last.0
+        {
+            return effects::db_to_gain(last.1) as f32;
+        }
+
+        let next = self.gain_envelope.partition_point(|&(time, _)| time <= local_time);
+        let (t0, db0) = self.gain_envelope[next - 1];
+        let (t1, db1) = self.gain_envelope[next];
+        let position = (local_time - t0) / (t1 - t0);
+        let gain = effects::db_to_gain(db0) + (effects::db_to_gain(db1) - effects::db_to_gain(db0)) * position;
+        gain as f32
+    }
 }
 
 /// Core audio engine for loading, processing, and exporting audio
@@ -25,6 +279,16 @@ pub struct AudioEngine
     tracks: Vec<AudioTrack>,
     playback: Option<AudioPlayback>,
     playback_sample_rate: Option<u32>,
+    playback_channels: Option<usize>,
+    playback_speed: f64,
+    playback_fade_ms: f64,
+    playback_seek_crossfade_ms: f64,
+    playback_balance: f32,
+    output_device: Option<String>,
+    noise_profile: Option<NoiseProfile>,  // learned by learn_noise_profile for apply_noise_reduction
+    ab_loop: Option<(f64, f64)>,  // A/B loop markers in seconds, kept across calls to play
+    resample_quality: ResampleQuality,  // used by every sample rate conversion
+    playback_mode: Option<PlaybackMode>,  // how the last playback was started
 }
 
 impl AudioEngine
@@ -40,6 +304,16 @@ impl AudioEngine
             tracks: Vec::new(),
             playback: None,
             playback_sample_rate: None,
+            playback_channels: None,
+            playback_speed: 1.0,
+            playback_fade_ms: playback::DEFAULT_FADE_MS,
+            playback_seek_crossfade_ms: playback::DEFAULT_SEEK_CROSSFADE_MS,
+            playback_balance: 0.0,
+            output_device: None,
+            noise_profile: None,
+            ab_loop: None,
+            resample_quality: ResampleQuality::default(),
+            playback_mode: None,
         }
     }
 
@@ -47,6 +321,7 @@ impl AudioEngine
     ///
     /// # Parameters
     /// * `path` - filesystem path to audio file
+    /// * `resample_to_project` - resample the track to the first track's sample rate if they differ
     ///
     /// # Returns
     /// `Result<(u32, usize, Option<u32>), String>` - Ok with (sample_rate, channels, mismatched_rate) if successful
@@ -54,25 +329,246 @@ impl AudioEngine
     /// # Notes
     /// Preserves original channel configuration (mono or stereo).
     /// Returns the previous sample rate if there's a mismatch with existing tracks.
-    pub fn load_file(&mut self, path: &str) -> Result<(u32, usize, Option<u32>), String>
+    /// When resampling, the returned sample rate is the project rate and no mismatch is reported.
+    pub fn load_file(&mut self, path: &str, resample_to_project: bool) -> Result<(u32, usize, Option<u32>), String>
+    {
+        let mut audio_data = Vec::new();
+        let (mut sample_rate, channels, metadata, source_format) = Self::decode_file(path, &mut |samples|
+        {
+            audio_data.extend_from_slice(samples);
+            Ok(())
+        })?;
+
+        let mut mismatched_rate = self.mismatched_rate(sample_rate);
+
+        if let Some(project_rate) = mismatched_rate
+        {
+            if resample_to_project
+            {
+                audio_data = resample::resample(&audio_data, channels, sample_rate, project_rate, self.resample_quality);
+                sample_rate = project_rate;
+                mismatched_rate = None;
+            }
+        }
+
+        self.push_loaded_track(path, audio_data.into(), sample_rate, channels, metadata, source_format);
+
+        Ok((sample_rate, channels, mismatched_rate))
+    }
+
+    /// Load and decode several audio files as new tracks
+    ///
+    /// # Parameters
+    /// * `paths` - filesystem paths to audio files
+    /// * `resample_to_project` - resample each track to the project sample rate if they differ
+    ///
+    /// # Returns
+    /// `Result<Vec<(u32, usize, Option<u32>)>, String>` - Ok with (sample_rate, channels, mismatched_rate) per file, in path order
+    ///
+    /// # Notes
+    /// Files are decoded in parallel, then added in path order exactly as repeated
+    /// `load_file` calls would add them, so the first file of the batch sets the
+    /// project rate when there are no tracks yet. If any file fails to decode, no
+    /// tracks are added.
+    pub fn load_files(&mut self, paths: &[String], resample_to_project: bool) -> Result<Vec<(u32, usize, Option<u32>)>, String>
+    {
+        let decoded = paths
+            .par_iter()
+            .map(|path|
+            {
+                let mut audio_data = Vec::new();
+                let (sample_rate, channels, metadata, source_format) = Self::decode_file(path, &mut |samples|
+                {
+                    audio_data.extend_from_slice(samples);
+                    Ok(())
+                })
+                .map_err(|e| format!("{}: {}", path, e))?;
+                Ok((audio_data, sample_rate, channels, metadata, source_format))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let mut results = Vec::with_capacity(decoded.len());
+        for (path, (mut audio_data, mut sample_rate, channels, metadata, source_format)) in paths.iter().zip(decoded)
+        {
+            let mut mismatched_rate = self.mismatched_rate(sample_rate);
+
+            if let Some(project_rate) = mismatched_rate
+            {
+                if resample_to_project
+                {
+                    audio_data = resample::resample(&audio_data, channels, sample_rate, project_rate, self.resample_quality);
+                    sample_rate = project_rate;
+                    mismatched_rate = None;
+                }
+            }
+
+            self.push_loaded_track(path, audio_data.into(), sample_rate, channels, metadata, source_format);
+            results.push((sample_rate, channels, mismatched_rate));
+        }
+
+        Ok(results)
+    }
+
+    /// Load an audio file as a new track backed by a memory-mapped sample store
+    ///
+    /// # Parameters
+    /// * `path` - filesystem path to audio file
+    ///
+    /// # Returns
+    /// `Result<(u32, usize, Option<u32>), String>` - Ok with (sample_rate, channels, mismatched_rate) if successful
+    ///
+    /// # Notes
+    /// Decoded samples are streamed to a temporary file instead of memory, so only
+    /// the ranges being drawn, played, or exported are paged in. The first edit to
+    /// the track copies it into memory. Resampling on load is not offered since it
+    /// would need the whole track in memory anyway.
+    pub fn load_file_streaming(&mut self, path: &str) -> Result<(u32, usize, Option<u32>), String>
+    {
+        let mut store = MappedSampleWriter::new()?;
+        let (sample_rate, channels, metadata, source_format) = Self::decode_file(path, &mut |samples| store.write(samples))?;
+
+        let mismatched_rate = self.mismatched_rate(sample_rate);
+        self.push_loaded_track(path, store.finish()?, sample_rate, channels, metadata, source_format);
+
+        Ok((sample_rate, channels, mismatched_rate))
+    }
+
+    /// Decode audio held in memory as a new track
+    ///
+    /// # Parameters
+    /// * `data` - encoded file contents, e.g. a download or a zip entry
+    /// * `hint_ext` - optional file extension hinting at the format, such as "wav" or "mp3"
+    ///
+    /// # Returns
+    /// `Result<(u32, usize, Option<u32>), String>` - Ok with (sample_rate, channels, mismatched_rate) if successful
+    ///
+    /// # Notes
+    /// Decodes exactly as `load_file` does without going through a temporary file.
+    /// The track is named "Untitled", or "Untitled.<ext>" when an extension is given.
+    pub fn load_bytes(&mut self, data: &[u8], hint_ext: Option<&str>) -> Result<(u32, usize, Option<u32>), String>
+    {
+        let hint_ext = hint_ext.map(|ext| ext.trim_start_matches('.'));
+        let source = std::io::Cursor::new(data.to_vec());
+
+        let mut audio_data = Vec::new();
+        let (sample_rate, channels, metadata, source_format) = Self::decode_source(Box::new(source), hint_ext, &mut |samples|
+        {
+            audio_data.extend_from_slice(samples);
+            Ok(())
+        })?;
+
+        let mismatched_rate = self.mismatched_rate(sample_rate);
+        let name = match hint_ext
+        {
+            Some(ext) => format!("Untitled.{}", ext),
+            None => "Untitled".to_string(),
+        };
+        self.push_loaded_track(&name, audio_data.into(), sample_rate, channels, metadata, source_format);
+
+        Ok((sample_rate, channels, mismatched_rate))
+    }
+
+    /// Decode an audio file and append it to the end of an existing track
+    ///
+    /// # Parameters
+    /// * `path` - filesystem path to audio file
+    /// * `dest_index` - index of the track to extend
+    ///
+    /// # Returns
+    /// `Result<f64, String>` - Ok with the duration appended in seconds, Err if the index
+    /// is invalid, the file cannot be decoded, or its channels cannot be converted
+    ///
+    /// # Notes
+    /// For recordings split across several files, such as a field recorder's
+    /// size-limited WAVs. The file is resampled and channel-converted to match the
+    /// track, and its samples follow the track's last sample with no gap.
+    pub fn load_file_append(&mut self, path: &str, dest_index: usize) -> Result<f64, String>
+    {
+        if dest_index >= self.tracks.len()
+        {
+            return Err(format!("Invalid track index: {}", dest_index));
+        }
+
+        let mut audio_data = Vec::new();
+        let (sample_rate, channels, _, _) = Self::decode_file(path, &mut |samples|
+        {
+            audio_data.extend_from_slice(samples);
+            Ok(())
+        })?;
+        Self::check_sample_layout(audio_data.len(), sample_rate, channels)?;
+
+        let quality = self.resample_quality;
+        let dest = &mut self.tracks[dest_index];
+        let appended = Self::conform_samples(&audio_data, sample_rate, channels, dest.sample_rate, dest.channels, quality)?;
+        let old_len = dest.audio_data.len();
+        dest.audio_data.edit_from(old_len).extend_from_slice(&appended);
+        dest.audio_data.refresh_mipmap(dest.channels);
+
+        Ok((appended.len() / dest.channels) as f64 / dest.sample_rate as f64)
+    }
+
+    /// Decode an audio file packet by packet
+    ///
+    /// # Parameters
+    /// * `path` - filesystem path to audio file
+    /// * `sink` - receives the interleaved samples of each decoded packet in order
+    ///
+    /// # Returns
+    /// `Result<(u32, usize, HashMap<String, String>, SourceFormat), String>` - Ok with (sample_rate, channels, tags, source format)
+    ///
+    /// # Notes
+    /// Packets that fail to decode are skipped
+    fn decode_file(path: &str, sink: &mut dyn FnMut(&[f32]) -> Result<(), String>) -> Result<(u32, usize, HashMap<String, String>, SourceFormat), String>
     {
         let file = File::open(path).map_err(|e| e.to_string())?;
-        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        let extension = Path::new(path).extension().and_then(|ext| ext.to_str());
+        Self::decode_source(Box::new(file), extension, sink)
+    }
+
+    /// Decode audio from any media source packet by packet
+    ///
+    /// # Parameters
+    /// * `source` - file, in-memory buffer, or other seekable source
+    /// * `extension` - optional file extension hinting at the format
+    /// * `sink` - receives the interleaved samples of each decoded packet in order
+    ///
+    /// # Returns
+    /// `Result<(u32, usize, HashMap<String, String>, SourceFormat), String>` - Ok with (sample_rate, channels, tags, source format)
+    ///
+    /// # Notes
+    /// Packets that fail to decode are skipped
+    fn decode_source(source: Box<dyn MediaSource>, extension: Option<&str>,
+                     sink: &mut dyn FnMut(&[f32]) -> Result<(), String>) -> Result<(u32, usize, HashMap<String, String>, SourceFormat), String>
+    {
+        let mss = MediaSourceStream::new(source, Default::default());
 
         let mut hint = Hint::new();
-        if let Some(ext) = Path::new(path).extension()
+        if let Some(ext) = extension
         {
-            hint.with_extension(ext.to_str().unwrap_or(""));
+            hint.with_extension(ext);
         }
 
         let meta_opts: MetadataOptions = Default::default();
         let fmt_opts: FormatOptions = Default::default();
 
-        let probed = symphonia::default::get_probe()
+        let mut probed = symphonia::default::get_probe()
             .format(&hint, mss, &fmt_opts, &meta_opts)
             .map_err(|e| format!("Probe error: {}", e))?;
 
+        // tags ahead of the container (e.g. ID3v2) first, then the container's own,
+        // so container tags win when both are present
+        let mut metadata = HashMap::new();
+        if let Some(revision) = probed.metadata.get().as_mut().and_then(|m| m.skip_to_latest().cloned())
+        {
+            Self::collect_tags(&revision, &mut metadata);
+        }
+
         let mut format = probed.format;
+        if let Some(revision) = format.metadata().skip_to_latest()
+        {
+            Self::collect_tags(revision, &mut metadata);
+        }
+
         let track = format
             .tracks()
             .iter()
@@ -86,7 +582,14 @@ impl AudioEngine
 
         let sample_rate = track.codec_params.sample_rate.unwrap_or(44100);
         let channels = track.codec_params.channels.unwrap_or_default().count();
-        let mut audio_data = Vec::new();
+        let source_format = SourceFormat
+        {
+            codec: symphonia::default::get_codecs()
+                .get_codec(track.codec_params.codec)
+                .map(|descriptor| descriptor.short_name.to_string()),
+            bits_per_sample: track.codec_params.bits_per_sample,
+        };
+        let mut packet_data = Vec::new();
 
         loop
         {
@@ -100,91 +603,407 @@ impl AudioEngine
             {
                 Ok(audio_buf) =>
                 {
-                    Self::append_audio_buffer(&mut audio_data, audio_buf, channels);
+                    packet_data.clear();
+                    Self::append_audio_buffer(&mut packet_data, audio_buf, channels);
+                    sink(&packet_data)?;
                 }
                 Err(_) => continue,
             }
         }
 
+        Ok((sample_rate, channels, metadata, source_format))
+    }
+
+    /// Find the project rate if it differs from a new track's rate
+    ///
+    /// # Parameters
+    /// * `sample_rate` - sample rate of the new track
+    ///
+    /// # Returns
+    /// `Option<u32>` - first track's sample rate if it differs, None if it matches or there are no tracks
+    fn mismatched_rate(&self, sample_rate: u32) -> Option<u32>
+    {
+        self.tracks
+            .first()
+            .map(|track| track.sample_rate)
+            .filter(|&existing_rate| existing_rate != sample_rate)
+    }
+
+    /// Add a decoded file as a new track named after the file
+    ///
+    /// # Parameters
+    /// * `path` - path the audio was loaded from
+    /// * `audio_data` - interleaved samples
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels
+    /// * `metadata` - tags read from the file
+    /// * `source_format` - codec and bit depth of the file
+    fn push_loaded_track(&mut self, path: &str, audio_data: SampleStore, sample_rate: u32, channels: usize,
+                         metadata: HashMap<String, String>, source_format: SourceFormat)
+    {
         let track_name = Path::new(path)
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("Unknown")
             .to_string();
 
-        let mismatched_rate = if !self.tracks.is_empty()
-        {
-            let existing_rate = self.tracks[0].sample_rate;
-            if existing_rate != sample_rate
-            {
-                Some(existing_rate)
-            }
-            else
-            {
-                None
-            }
-        }
-        else
-        {
-            None
-        };
-
-        let new_track = AudioTrack
+        let mut new_track = AudioTrack
         {
             audio_data,
             sample_rate,
             channels,
             name: track_name,
             start_offset: 0.0,
+            gain_envelope: Vec::new(),
+            metadata,
+            source_format,
         };
+        new_track.audio_data.refresh_mipmap(channels);
 
         self.tracks.push(new_track);
-
-        Ok((sample_rate, channels, mismatched_rate))
     }
 
-    /// Append decoded audio buffer to storage
+    /// Copy the text tags of a metadata revision into a map
     ///
     /// # Parameters
-    /// * `audio_data` - vector to append to
-    /// * `audio_buf` - decoded audio buffer from symphonia
-    /// * `channels` - number of channels
+    /// * `revision` - metadata revision from symphonia
+    /// * `into` - map to insert into, keyed by lowercase tag name
     ///
     /// # Notes
-    /// Handles F32, S32, and S16 sample formats, converting to F32
-    fn append_audio_buffer(audio_data: &mut Vec<f32>, audio_buf: AudioBufferRef, channels: usize)
+    /// Common tags use fixed names ('title', 'artist', 'album', 'date', ...) whatever
+    /// the container calls them; others keep their raw key. Binary values such as
+    /// embedded artwork are skipped.
+    fn collect_tags(revision: &MetadataRevision, into: &mut HashMap<String, String>)
     {
-        match audio_buf
+        for tag in revision.tags()
         {
-            AudioBufferRef::F32(buf) =>
-            {
-                // pass through f32 samples as is
-                for frame in 0..buf.frames()
-                {
-                    for ch in 0..channels.min(buf.spec().channels.count())
-                    {
-                        audio_data.push(buf.chan(ch)[frame]);
-                    }
-                }
-            }
-            AudioBufferRef::S32(buf) =>
+            if let Value::Binary(_) = tag.value
             {
-                // convert signed 32-bit integer samples to f32
-                for frame in 0..buf.frames()
-                {
-                    for ch in 0..channels.min(buf.spec().channels.count())
-                    {
-                        audio_data.push(buf.chan(ch)[frame] as f32 / i32::MAX as f32);
-                    }
-                }
+                continue;
             }
-            AudioBufferRef::S16(buf) =>
+
+            let key = match tag.std_key
             {
-                // convert signed 16-bit integer samples to f32
-                for frame in 0..buf.frames()
-                {
-                    for ch in 0..channels.min(buf.spec().channels.count())
-                    {
+                Some(StandardTagKey::TrackTitle) => "title".to_string(),
+                Some(StandardTagKey::Artist) => "artist".to_string(),
+                Some(StandardTagKey::Album) => "album".to_string(),
+                Some(StandardTagKey::AlbumArtist) => "album_artist".to_string(),
+                Some(StandardTagKey::Date) | Some(StandardTagKey::ReleaseDate) => "date".to_string(),
+                Some(StandardTagKey::Genre) => "genre".to_string(),
+                Some(StandardTagKey::TrackNumber) => "track_number".to_string(),
+                Some(StandardTagKey::Composer) => "composer".to_string(),
+                Some(StandardTagKey::Comment) => "comment".to_string(),
+                _ => tag.key.to_lowercase(),
+            };
+            into.insert(key, tag.value.to_string());
+        }
+    }
+
+    /// Synthesize a noise track
+    ///
+    /// # Parameters
+    /// * `duration` - length in seconds
+    /// * `kind` - noise colour ('white' or 'pink')
+    /// * `seed` - random seed (the same seed always produces the same noise)
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels
+    ///
+    /// # Returns
+    /// `Result<usize, String>` - Ok with index of the new track, Err if parameters invalid
+    pub fn add_noise_track(&mut self, duration: f64, kind: &str, seed: u64, sample_rate: u32, channels: usize) -> Result<usize, String>
+    {
+        let frames = Self::generated_frame_count(duration, sample_rate, channels)?;
+        let (audio_data, name) = match kind
+        {
+            "white" => (generators::white_noise(frames, channels, seed), "White Noise"),
+            "pink" => (generators::pink_noise(frames, channels, seed), "Pink Noise"),
+            _ => return Err(format!("Unknown noise type '{}'. Use 'white' or 'pink'", kind)),
+        };
+
+        Ok(self.push_generated_track(audio_data, sample_rate, channels, name.to_string()))
+    }
+
+    /// Synthesize a sine tone track
+    ///
+    /// # Parameters
+    /// * `frequency` - tone frequency in Hz
+    /// * `duration` - length in seconds
+    /// * `amplitude` - peak amplitude (0.0-1.0)
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels
+    ///
+    /// # Returns
+    /// `Result<usize, String>` - Ok with index of the new track, Err if parameters invalid
+    pub fn add_tone_track(&mut self, frequency: f64, duration: f64, amplitude: f64, sample_rate: u32, channels: usize) -> Result<usize, String>
+    {
+        let frames = Self::generated_frame_count(duration, sample_rate, channels)?;
+        if frequency <= 0.0 || !frequency.is_finite()
+        {
+            return Err(format!("Invalid frequency: {}", frequency));
+        }
+
+        let audio_data = generators::sine_tone(frequency, frames, amplitude.clamp(0.0, 1.0), sample_rate, channels);
+        Ok(self.push_generated_track(audio_data, sample_rate, channels, format!("Tone {} Hz", frequency)))
+    }
+
+    /// Synthesize a logarithmic sine sweep track
+    ///
+    /// # Parameters
+    /// * `start_hz` - starting frequency in Hz
+    /// * `end_hz` - ending frequency in Hz
+    /// * `duration` - length in seconds
+    /// * `amplitude` - peak amplitude (0.0-1.0)
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels
+    ///
+    /// # Returns
+    /// `Result<usize, String>` - Ok with index of the new track, Err if parameters invalid
+    pub fn add_sweep_track(&mut self, start_hz: f64, end_hz: f64, duration: f64, amplitude: f64,
+                           sample_rate: u32, channels: usize) -> Result<usize, String>
+    {
+        let frames = Self::generated_frame_count(duration, sample_rate, channels)?;
+        if start_hz <= 0.0 || end_hz <= 0.0 || !start_hz.is_finite() || !end_hz.is_finite()
+        {
+            return Err(format!("Invalid sweep range: {} to {} Hz", start_hz, end_hz));
+        }
+
+        let audio_data = generators::log_sweep(start_hz, end_hz, frames, amplitude.clamp(0.0, 1.0), sample_rate, channels);
+        Ok(self.push_generated_track(audio_data, sample_rate, channels, format!("Sweep {}-{} Hz", start_hz, end_hz)))
+    }
+
+    /// Add a track from samples already in memory
+    ///
+    /// # Parameters
+    /// * `audio_data` - interleaved samples
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of interleaved channels
+    /// * `name` - track name
+    ///
+    /// # Returns
+    /// `Result<usize, String>` - Ok with index of the new track, Err if the layout is invalid
+    pub fn add_track_from_samples(&mut self, audio_data: Vec<f32>, sample_rate: u32, channels: usize, name: &str) -> Result<usize, String>
+    {
+        Self::check_sample_layout(audio_data.len(), sample_rate, channels)?;
+        Ok(self.push_generated_track(audio_data, sample_rate, channels, name.to_string()))
+    }
+
+    /// Check that caller-supplied samples describe whole frames at a usable rate
+    ///
+    /// # Parameters
+    /// * `sample_count` - number of interleaved samples
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of interleaved channels
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if the layout is valid
+    fn check_sample_layout(sample_count: usize, sample_rate: u32, channels: usize) -> Result<(), String>
+    {
+        if sample_rate == 0
+        {
+            return Err("Sample rate must be greater than zero".to_string());
+        }
+        if channels == 0
+        {
+            return Err("Channel count must be greater than zero".to_string());
+        }
+        if !sample_count.is_multiple_of(channels)
+        {
+            return Err(format!("Sample count {} is not divisible by channel count {}", sample_count, channels));
+        }
+        Ok(())
+    }
+
+    /// Convert samples to another rate and channel count
+    ///
+    /// # Parameters
+    /// * `audio_data` - interleaved samples
+    /// * `sample_rate` - sample rate of `audio_data` in Hz
+    /// * `channels` - channel count of `audio_data`
+    /// * `target_rate` - sample rate to convert to
+    /// * `target_channels` - channel count to convert to
+    /// * `quality` - sample rate conversion quality
+    ///
+    /// # Returns
+    /// `Result<Vec<f32>, String>` - Ok with converted samples, Err if there is no channel mapping
+    ///
+    /// # Notes
+    /// Channels are folded to mono or stereo with `fold_to_mono` and `fold_to_stereo`,
+    /// and mono is copied to every channel. Other channel count changes are not supported.
+    fn conform_samples(audio_data: &[f32], sample_rate: u32, channels: usize, target_rate: u32, target_channels: usize,
+                       quality: ResampleQuality) -> Result<Vec<f32>, String>
+    {
+        let frames = audio_data.chunks_exact(channels);
+        let converted: Vec<f32> = if channels == target_channels
+        {
+            audio_data.to_vec()
+        }
+        else if target_channels == 1
+        {
+            frames.map(Self::fold_to_mono).collect()
+        }
+        else if target_channels == 2
+        {
+            frames.flat_map(|frame|
+            {
+                let (left, right) = Self::fold_to_stereo(frame);
+                [left, right]
+            }).collect()
+        }
+        else if channels == 1
+        {
+            audio_data.iter().flat_map(|&sample| std::iter::repeat_n(sample, target_channels)).collect()
+        }
+        else
+        {
+            return Err(format!("Cannot convert {} channels to {}", channels, target_channels));
+        };
+
+        if sample_rate == target_rate
+        {
+            Ok(converted)
+        }
+        else
+        {
+            Ok(resample::resample(&converted, target_channels, sample_rate, target_rate, quality))
+        }
+    }
+
+    /// Import a headerless PCM file as a new track
+    ///
+    /// # Parameters
+    /// * `path` - input file path
+    /// * `sample_rate` - sample rate in Hz of the stored audio
+    /// * `channels` - number of interleaved channels in the stored audio
+    /// * `sample_format` - sample encoding ('s16', 's24', 's32', or 'f32')
+    /// * `endianness` - byte order ('le' or 'be')
+    ///
+    /// # Returns
+    /// `Result<usize, String>` - Ok with index of the new track, Err if the file or parameters are invalid
+    ///
+    /// # Notes
+    /// Raw files carry no header, so the layout must be supplied by the caller.
+    /// Trailing bytes that do not make up a whole frame are dropped.
+    pub fn import_raw(&mut self, path: &str, sample_rate: u32, channels: usize, sample_format: &str, endianness: &str) -> Result<usize, String>
+    {
+        let format = RawFormat::parse(sample_format, endianness)?;
+        if sample_rate == 0
+        {
+            return Err("Sample rate must be greater than zero".to_string());
+        }
+        if channels == 0
+        {
+            return Err("Channel count must be greater than zero".to_string());
+        }
+
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        let mut audio_data = pcm::decode(&bytes, format);
+        audio_data.truncate(audio_data.len() / channels * channels);
+
+        let track_name = Path::new(path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("Unknown")
+            .to_string();
+
+        Ok(self.push_generated_track(audio_data, sample_rate, channels, track_name))
+    }
+
+    /// Validate generator parameters and calculate the frame count
+    ///
+    /// # Parameters
+    /// * `duration` - length in seconds
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels
+    ///
+    /// # Returns
+    /// `Result<usize, String>` - Ok with number of frames, Err if any parameter is invalid
+    fn generated_frame_count(duration: f64, sample_rate: u32, channels: usize) -> Result<usize, String>
+    {
+        if duration < 0.0 || !duration.is_finite()
+        {
+            return Err(format!("Invalid duration: {}", duration));
+        }
+        if sample_rate == 0
+        {
+            return Err("Sample rate must be greater than zero".to_string());
+        }
+        if channels == 0
+        {
+            return Err("Channel count must be greater than zero".to_string());
+        }
+        Ok((duration * sample_rate as f64).round() as usize)
+    }
+
+    /// Add a synthesized or in-memory track to the track list
+    ///
+    /// # Parameters
+    /// * `audio_data` - interleaved samples
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels
+    /// * `name` - track name
+    ///
+    /// # Returns
+    /// `usize` - index of the new track
+    fn push_generated_track(&mut self, audio_data: Vec<f32>, sample_rate: u32, channels: usize, name: String) -> usize
+    {
+        let mut audio_data = SampleStore::from(audio_data);
+        audio_data.refresh_mipmap(channels);
+        self.tracks.push(AudioTrack
+        {
+            audio_data,
+            sample_rate,
+            channels,
+            name,
+            start_offset: 0.0,
+            gain_envelope: Vec::new(),
+            metadata: HashMap::new(),
+            source_format: SourceFormat::default(),
+        });
+        self.tracks.len() - 1
+    }
+
+    /// Append decoded audio buffer to storage
+    ///
+    /// # Parameters
+    /// * `audio_data` - vector to append to
+    /// * `audio_buf` - decoded audio buffer from symphonia
+    /// * `channels` - number of channels
+    ///
+    /// # Notes
+    /// Handles F32, S32, and S16 sample formats, converting to F32
+    fn append_audio_buffer(audio_data: &mut Vec<f32>, audio_buf: AudioBufferRef, channels: usize)
+    {
+        match audio_buf
+        {
+            AudioBufferRef::F32(buf) =>
+            {
+                // pass through f32 samples as is
+                for frame in 0..buf.frames()
+                {
+                    for ch in 0..channels.min(buf.spec().channels.count())
+                    {
+                        audio_data.push(buf.chan(ch)[frame]);
+                    }
+                }
+            }
+            AudioBufferRef::S32(buf) =>
+            {
+                // convert signed 32-bit integer samples to f32
+                for frame in 0..buf.frames()
+                {
+                    for ch in 0..channels.min(buf.spec().channels.count())
+                    {
+                        audio_data.push(buf.chan(ch)[frame] as f32 / i32::MAX as f32);
+                    }
+                }
+            }
+            AudioBufferRef::S16(buf) =>
+            {
+                // convert signed 16-bit integer samples to f32
+                for frame in 0..buf.frames()
+                {
+                    for ch in 0..channels.min(buf.spec().channels.count())
+                    {
                         audio_data.push(buf.chan(ch)[frame] as f32 / i16::MAX as f32);
                     }
                 }
@@ -260,870 +1079,5195 @@ impl AudioEngine
         }).collect()
     }
 
-    /// Clear all loaded tracks
-    pub fn clear_tracks(&mut self)
+    /// Get information about one track, including the format of its source file
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    ///
+    /// # Returns
+    /// `Result<TrackDetails, String>` - Ok with (name, sample_rate, channels, duration,
+    /// start_offset, bits_per_sample, codec), Err if track index invalid
+    ///
+    /// # Notes
+    /// The first five fields match `get_track_info`. Bit depth and codec describe
+    /// the file the track was loaded from, not the 32-bit float samples held in memory.
+    pub fn get_track_details(&self, track_index: usize) -> Result<TrackDetails, String>
     {
-        self.tracks.clear();
-        self.playback = None;
-        self.playback_sample_rate = None;
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+        let duration = (track.audio_data.len() / track.channels) as f64 / track.sample_rate as f64;
+
+        Ok((
+            track.name.clone(),
+            track.sample_rate,
+            track.channels,
+            duration,
+            track.start_offset,
+            track.source_format.bits_per_sample,
+            track.source_format.codec.clone(),
+        ))
     }
 
-    /// Set the start offset for a track
+    /// Get the exact number of frames in a track
     ///
     /// # Parameters
-    /// * `track_index` - index of the track to modify
-    /// * `offset` - new start offset in seconds
+    /// * `track_index` - index of the track
     ///
     /// # Returns
-    /// `Result<(), String>` - Ok if successful, Err if track index invalid
-    pub fn set_track_offset(&mut self, track_index: usize, offset: f64) -> Result<(), String>
+    /// `Result<usize, String>` - Ok with samples per channel, Err if track index invalid
+    pub fn get_track_frame_count(&self, track_index: usize) -> Result<usize, String>
     {
-        if track_index >= self.tracks.len()
-        {
-            return Err(format!("Invalid track index: {}", track_index));
-        }
-        self.tracks[track_index].start_offset = offset.max(0.0);
-        Ok(())
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+        Ok(track.audio_data.len() / track.channels)
     }
 
-    /// Get waveform data for a specific time range for all tracks
+    /// Convert a timeline position to a frame within a track
     ///
     /// # Parameters
-    /// * `start_time` - start of range in seconds
-    /// * `end_time` - end of range in seconds
-    /// * `num_pixels` - desired number of display pixels
+    /// * `track_index` - index of the track
+    /// * `time` - timeline position in seconds
     ///
     /// # Returns
-    /// `Vec<Vec<(f32, f32, f32, f32)>>` - waveform data per track as (min_l, max_l, min_r, max_r) tuples
+    /// `Result<usize, String>` - Ok with the frame, clamped to 0 through the frame count,
+    /// Err if track index invalid or time not finite
     ///
     /// # Notes
-    /// Returns separate waveform data for each track. For mono audio, left and right
-    /// values are identical.
-    pub fn get_waveform_for_range(&self, start_time: f64, end_time: f64, num_pixels: usize) -> Vec<Vec<(f32, f32, f32, f32)>>
+    /// Uses the same conversion as region edits, so a region passed to an edit starts
+    /// and ends exactly on the frames returned here
+    pub fn time_to_frame(&self, track_index: usize, time: f64) -> Result<usize, String>
     {
-        if self.tracks.is_empty() || num_pixels == 0
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+        if !time.is_finite()
         {
-            return Vec::new();
+            return Err(format!("Invalid time: {}", time));
         }
-
-        self.tracks.iter().map(|track|
-        {
-            Self::get_track_waveform(track, start_time, end_time, num_pixels)
-        }).collect()
+        Ok(Self::frame_at_time(track, time).min(track.audio_data.len() / track.channels))
     }
 
-    /// Get waveform data for a single track
+    /// Convert a frame within a track to a timeline position
     ///
     /// # Parameters
-    /// * `track` - audio track to analyze
-    /// * `start_time` - start of range in seconds
-    /// * `end_time` - end of range in seconds
-    /// * `num_pixels` - desired number of display pixels
+    /// * `track_index` - index of the track
+    /// * `frame` - frame index within the track, may be the frame count for the track end
     ///
     /// # Returns
-    /// `Vec<(f32, f32, f32, f32)>` - waveform data as (min_l, max_l, min_r, max_r) tuples
-    fn get_track_waveform(track: &AudioTrack, start_time: f64, end_time: f64, num_pixels: usize) -> Vec<(f32, f32, f32, f32)>
+    /// `Result<f64, String>` - Ok with the timeline position of the frame's start in seconds,
+    /// Err if track index invalid
+    ///
+    /// # Notes
+    /// `time_to_frame` maps the result back to `frame` for any frame within the track
+    pub fn frame_to_time(&self, track_index: usize, frame: usize) -> Result<f64, String>
     {
-        if track.audio_data.is_empty() || num_pixels == 0
-        {
-            return vec![(0.0, 0.0, 0.0, 0.0); num_pixels];
-        }
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+        Ok(track.start_offset + frame as f64 / track.sample_rate as f64)
+    }
 
-        let track_audio_duration = (track.audio_data.len() / track.channels) as f64 / track.sample_rate as f64;
-        let track_end_time = track.start_offset + track_audio_duration;
+    /// Clear all loaded tracks
+    pub fn clear_tracks(&mut self)
+    {
+        self.tracks.clear();
+        self.playback = None;
+        self.playback_sample_rate = None;
+        self.playback_channels = None;
+    }
 
-        // if the view range doesn't overlap with this track, return silence
-        if end_time <= track.start_offset || start_time >= track_end_time
-        {
-            return vec![(0.0, 0.0, 0.0, 0.0); num_pixels];
-        }
+    /// Save the session to a project file
+    ///
+    /// # Parameters
+    /// * `path` - output file path
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    ///
+    /// # Notes
+    /// Stores each track's name, format, offset, gain envelope, and edited samples
+    pub fn save_project(&self, path: &str) -> Result<(), String>
+    {
+        project::save(path, &self.tracks)
+    }
 
-        // calculate times relative to track's audio data
-        let relative_start = (start_time - track.start_offset).max(0.0);
-        let relative_end = (end_time - track.start_offset).min(track_audio_duration);
-
-        let start_frame = ((relative_start * track.sample_rate as f64) as usize).min(track.audio_data.len() / track.channels);
-        let end_frame = ((relative_end * track.sample_rate as f64) as usize).min(track.audio_data.len() / track.channels);
+    /// Replace the session with the contents of a project file
+    ///
+    /// # Parameters
+    /// * `path` - project file path
+    ///
+    /// # Returns
+    /// `Result<usize, String>` - Ok with the number of tracks loaded, Err if the file is unreadable or invalid
+    ///
+    /// # Notes
+    /// The current tracks are kept if loading fails
+    pub fn load_project(&mut self, path: &str) -> Result<usize, String>
+    {
+        let tracks = project::load(path)?;
+        self.clear_tracks();
+        self.tracks = tracks;
+        Ok(self.tracks.len())
+    }
 
-        if start_frame >= end_frame
+    /// Remove a single track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to remove
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if track index invalid
+    ///
+    /// # Notes
+    /// Stops playback, since the playing buffer may contain the removed track.
+    /// Releases the playback stream when the last track is removed.
+    pub fn remove_track(&mut self, track_index: usize) -> Result<(), String>
+    {
+        if track_index >= self.tracks.len()
         {
-            return vec![(0.0, 0.0, 0.0, 0.0); num_pixels];
+            return Err(format!("Invalid track index: {}", track_index));
         }
 
-        // calculate how many pixels correspond to the actual audio portion
-        let view_duration = end_time - start_time;
-        let audio_start_in_view = (track.start_offset - start_time).max(0.0);
-        let audio_end_in_view = (track_end_time - start_time).min(view_duration);
+        self.stop();
+        self.tracks.remove(track_index);
 
-        let start_pixel = ((audio_start_in_view / view_duration) * num_pixels as f64) as usize;
-        let end_pixel = ((audio_end_in_view / view_duration) * num_pixels as f64).ceil() as usize;
-        let audio_pixels = end_pixel.saturating_sub(start_pixel).max(1);
+        if self.tracks.is_empty()
+        {
+            self.playback = None;
+            self.playback_sample_rate = None;
+            self.playback_channels = None;
+        }
+        Ok(())
+    }
 
-        let frame_count = end_frame - start_frame;
-        let samples_per_pixel = (frame_count as f64) / (audio_pixels as f64);
+    /// Get the tags read from a track's source file
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    ///
+    /// # Returns
+    /// `Result<HashMap<String, String>, String>` - Ok with the tags (empty if the file had none), Err if index invalid
+    pub fn get_track_metadata(&self, track_index: usize) -> Result<HashMap<String, String>, String>
+    {
+        self.tracks
+            .get(track_index)
+            .map(|track| track.metadata.clone())
+            .ok_or(format!("Invalid track index: {}", track_index))
+    }
 
-        // build result with silence before and after as needed
-        let mut waveform = vec![(0.0, 0.0, 0.0, 0.0); num_pixels];
+    /// Rename a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    /// * `name` - new track name
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if track index invalid
+    pub fn rename_track(&mut self, track_index: usize, name: &str) -> Result<(), String>
+    {
+        if track_index >= self.tracks.len()
+        {
+            return Err(format!("Invalid track index: {}", track_index));
+        }
+        self.tracks[track_index].name = name.to_string();
+        Ok(())
+    }
 
-        if samples_per_pixel < 1.0
+    /// Set the start offset for a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    /// * `offset` - new start offset in seconds
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if track index or offset invalid
+    ///
+    /// # Notes
+    /// A negative offset starts the track before the beginning of the timeline, so
+    /// its head is not heard in playback or export
+    pub fn set_track_offset(&mut self, track_index: usize, offset: f64) -> Result<(), String>
+    {
+        if track_index >= self.tracks.len()
         {
-            // we're zoomed in far enough to see individual samples
-            // return one entry per actual sample in their correct pixel positions
-            let pixels_per_sample = audio_pixels as f64 / frame_count as f64;
+            return Err(format!("Invalid track index: {}", track_index));
+        }
+        if !offset.is_finite()
+        {
+            return Err(format!("Invalid track offset: {}", offset));
+        }
+        self.tracks[track_index].start_offset = offset;
+        Ok(())
+    }
 
-            for (i, frame) in (start_frame..end_frame).enumerate()
-            {
-                let pixel_idx = start_pixel + (i as f64 * pixels_per_sample) as usize;
-                if pixel_idx >= num_pixels
-                {
-                    break;
-                }
+    /// Set the gain automation envelope for a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    /// * `points` - (time, gain_db) breakpoints, with time in seconds from the start of the track
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if track index or a breakpoint is invalid
+    ///
+    /// # Notes
+    /// Points are sorted by time. Gains are interpolated linearly in amplitude rather than
+    /// in dB, and an empty envelope means unity gain. Times are relative to the track so
+    /// the envelope moves with it when its offset changes.
+    pub fn set_gain_envelope(&mut self, track_index: usize, points: Vec<(f64, f64)>) -> Result<(), String>
+    {
+        if track_index >= self.tracks.len()
+        {
+            return Err(format!("Invalid track index: {}", track_index));
+        }
+        if let Some(&(time, gain_db)) = points.iter().find(|&&(time, gain_db)| !time.is_finite() || gain_db.is_nan() || gain_db == f64::INFINITY)
+        {
+            return Err(format!("Invalid envelope point: ({}, {})", time, gain_db));
+        }
 
-                if track.channels == 2
-                {
-                    let idx = frame * 2;
-                    if idx + 1 < track.audio_data.len()
-                    {
-                        let left = track.audio_data[idx];
-                        let right = track.audio_data[idx + 1];
-                        waveform[pixel_idx] = (0.0, left, 0.0, right);
-                    }
-                }
-                else if track.channels == 1
-                {
-                    if frame < track.audio_data.len()
-                    {
-                        let sample = track.audio_data[frame];
-                        waveform[pixel_idx] = (0.0, sample, 0.0, sample);
-                    }
-                }
-                else
-                {
-                    let idx = frame * track.channels;
-                    if idx < track.audio_data.len()
-                    {
-                        let sample = track.audio_data[idx];
-                        waveform[pixel_idx] = (0.0, sample, 0.0, sample);
-                    }
-                }
-            }
+        let mut points = points;
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self.tracks[track_index].gain_envelope = points;
+        Ok(())
+    }
 
-            // early return to bypass max/min rendering
-            return waveform;
+    /// Resample a track to a new sample rate
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    /// * `target_rate` - new sample rate in Hz
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if track index or rate invalid
+    ///
+    /// # Notes
+    /// Uses a band-limited resampler, so downsampling does not alias.
+    /// Channel count and interleaving are preserved.
+    pub fn resample_track(&mut self, track_index: usize, target_rate: u32) -> Result<(), String>
+    {
+        if track_index >= self.tracks.len()
+        {
+            return Err(format!("Invalid track index: {}", track_index));
+        }
+        if target_rate == 0
+        {
+            return Err("Sample rate must be greater than zero".to_string());
         }
 
-        // normal case: aggregate samples per pixel
-        for i in 0..audio_pixels
+        let quality = self.resample_quality;
+        let track = &mut self.tracks[track_index];
+        track.audio_data = resample::resample(&track.audio_data, track.channels, track.sample_rate, target_rate, quality).into();
+        track.sample_rate = target_rate;
+        Ok(())
+    }
+
+    /// Convert a track to mono by averaging its channels
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if track index invalid
+    ///
+    /// # Notes
+    /// Does nothing if the track is already mono
+    pub fn track_to_mono(&mut self, track_index: usize) -> Result<(), String>
+    {
+        if track_index >= self.tracks.len()
         {
-            let pixel_idx = start_pixel + i;
-            if pixel_idx >= num_pixels
-            {
-                break;
-            }
+            return Err(format!("Invalid track index: {}", track_index));
+        }
+
+        let track = &mut self.tracks[track_index];
+        if track.channels <= 1
+        {
+            return Ok(());
+        }
 
-            let pixel_start_frame = start_frame + (i as f64 * samples_per_pixel) as usize;
-            let pixel_end_frame = (start_frame + ((i + 1) as f64 * samples_per_pixel) as usize).min(end_frame);
+        let channels = track.channels;
+        track.audio_data = track.audio_data
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+        track.channels = 1;
+        Ok(())
+    }
 
-            if pixel_start_frame >= pixel_end_frame
+    /// Convert a track to stereo
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if track index invalid
+    ///
+    /// # Notes
+    /// Mono is duplicated to both channels and tracks with more than two channels
+    /// are folded down as in mixing. Does nothing if the track is already stereo
+    pub fn track_to_stereo(&mut self, track_index: usize) -> Result<(), String>
+    {
+        if track_index >= self.tracks.len()
+        {
+            return Err(format!("Invalid track index: {}", track_index));
+        }
+
+        let track = &mut self.tracks[track_index];
+        match track.channels
+        {
+            1 =>
             {
-                continue;
+                track.audio_data = track.audio_data
+                    .iter()
+                    .flat_map(|&sample| [sample, sample])
+                    .collect();
+                track.channels = 2;
+                Ok(())
             }
-
-            if track.channels == 2
+            2 => Ok(()),
+            channels =>
             {
-                let mut min_l = 0.0f32;
-                let mut max_l = 0.0f32;
-                let mut min_r = 0.0f32;
-                let mut max_r = 0.0f32;
-
-                for frame in pixel_start_frame..pixel_end_frame
-                {
-                    let idx = frame * 2;
-                    if idx + 1 < track.audio_data.len()
+                track.audio_data = track.audio_data
+                    .chunks_exact(channels)
+                    .flat_map(|frame|
                     {
-                        let left = track.audio_data[idx];
-                        let right = track.audio_data[idx + 1];
+                        let (left, right) = Self::fold_to_stereo(frame);
+                        [left, right]
+                    })
+                    .collect();
+                track.channels = 2;
+                Ok(())
+            }
+        }
+    }
 
-                        min_l = min_l.min(left);
-                        max_l = max_l.max(left);
-                        min_r = min_r.min(right);
-                        max_r = max_r.max(right);
-                    }
-                }
+    /// Split a track into two tracks at a point in time
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to split
+    /// * `time` - split point in seconds on the timeline
+    ///
+    /// # Returns
+    /// `Result<(usize, usize), String>` - Ok with indices of the two resulting tracks
+    ///
+    /// # Notes
+    /// The split lands on a frame boundary. The second piece is inserted directly after
+    /// the first and keeps its position on the timeline. Splitting at or outside the
+    /// track's start or end is an error.
+    pub fn split_track(&mut self, track_index: usize, time: f64) -> Result<(usize, usize), String>
+    {
+        if track_index >= self.tracks.len()
+        {
+            return Err(format!("Invalid track index: {}", track_index));
+        }
 
-                waveform[pixel_idx] = (min_l, max_l, min_r, max_r);
-            }
-            else if track.channels == 1
-            {
-                let mut min_val = 0.0f32;
-                let mut max_val = 0.0f32;
+        let track = &mut self.tracks[track_index];
+        let total_frames = track.audio_data.len() / track.channels;
+        let local_time = time - track.start_offset;
+        let split_frame = (local_time * track.sample_rate as f64).round();
 
-                for frame in pixel_start_frame..pixel_end_frame
-                {
-                    if frame < track.audio_data.len()
-                    {
-                        let sample = track.audio_data[frame];
-                        min_val = min_val.min(sample);
-                        max_val = max_val.max(sample);
-                    }
-                }
+        if split_frame <= 0.0 || split_frame as usize >= total_frames
+        {
+            return Err(format!("Split point {:.3}s is outside the track", time));
+        }
 
-                waveform[pixel_idx] = (min_val, max_val, min_val, max_val);
-            }
-            else
-            {
-                let mut min_val = 0.0f32;
-                let mut max_val = 0.0f32;
+        let split_frame = split_frame as usize;
+        let tail = track.audio_data.edit_from(split_frame * track.channels).split_off(split_frame * track.channels);
+        track.audio_data.refresh_mipmap(track.channels);
+        let base_name = track.name.clone();
+        track.name = format!("{} (1)", base_name);
 
-                for frame in pixel_start_frame..pixel_end_frame
-                {
-                    let idx = frame * track.channels;
-                    if idx < track.audio_data.len()
-                    {
-                        let sample = track.audio_data[idx];
-                        min_val = min_val.min(sample);
-                        max_val = max_val.max(sample);
-                    }
-                }
+        let mut second = AudioTrack
+        {
+            audio_data: tail.into(),
+            sample_rate: track.sample_rate,
+            channels: track.channels,
+            name: format!("{} (2)", base_name),
+            start_offset: track.start_offset + split_frame as f64 / track.sample_rate as f64,
+            gain_envelope: Self::shift_envelope(&track.gain_envelope, split_frame as f64 / track.sample_rate as f64),
+            metadata: track.metadata.clone(),
+            source_format: track.source_format.clone(),
+        };
+        second.audio_data.refresh_mipmap(second.channels);
 
-                waveform[pixel_idx] = (min_val, max_val, min_val, max_val);
-            }
+        self.tracks.insert(track_index + 1, second);
+        Ok((track_index, track_index + 1))
+    }
+
+    /// Duplicate a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to copy
+    ///
+    /// # Returns
+    /// `Result<usize, String>` - Ok with index of the new track, Err if track index invalid
+    ///
+    /// # Notes
+    /// The copy is inserted directly after the original and named "<name> (copy)"
+    pub fn duplicate_track(&mut self, track_index: usize) -> Result<usize, String>
+    {
+        if track_index >= self.tracks.len()
+        {
+            return Err(format!("Invalid track index: {}", track_index));
         }
 
-        waveform
+        let mut copy = self.tracks[track_index].clone();
+        copy.name = format!("{} (copy)", copy.name);
+        self.tracks.insert(track_index + 1, copy);
+        Ok(track_index + 1)
     }
 
-    /// Mix all tracks together for playback
+    /// Append one track's audio to the end of another
     ///
     /// # Parameters
-    /// * `start_time` - start time in seconds
-    /// * `end_time` - end time in seconds
+    /// * `dest_index` - index of the track to extend
+    /// * `src_index` - index of the track whose audio is appended
+    /// * `remove_source` - remove the source track afterwards
     ///
     /// # Returns
-    /// `(Vec<f32>, u32, usize)` - mixed audio data, sample rate, and channel count
+    /// `Result<usize, String>` - Ok with the destination's index (which shifts down if an earlier source was removed),
+    /// Err if either index is invalid, they are the same track, or the channels cannot be converted
     ///
     /// # Notes
-    /// Preserves mono if all tracks are mono, otherwise converts to stereo.
-    /// Uses the sample rate of the first track. Accounts for track start offsets.
-    fn mix_tracks_for_playback(&self, start_time: f64, end_time: f64) -> (Vec<f32>, u32, usize)
+    /// The source is resampled and channel-converted to match the destination. Its raw
+    /// samples are used, so its gain envelope and start offset do not carry over.
+    pub fn append_track(&mut self, dest_index: usize, src_index: usize, remove_source: bool) -> Result<usize, String>
+    {
+        for index in [dest_index, src_index]
+        {
+            if index >= self.tracks.len()
+            {
+                return Err(format!("Invalid track index: {}", index));
+            }
+        }
+        if dest_index == src_index
+        {
+            return Err("Cannot append a track to itself".to_string());
+        }
+
+        let src = &self.tracks[src_index];
+        let dest = &self.tracks[dest_index];
+        let appended = Self::conform_samples(&src.audio_data, src.sample_rate, src.channels, dest.sample_rate, dest.channels, self.resample_quality)?;
+        let dest = &mut self.tracks[dest_index];
+        let old_len = dest.audio_data.len();
+        dest.audio_data.edit_from(old_len).extend_from_slice(&appended);
+        dest.audio_data.refresh_mipmap(dest.channels);
+
+        if remove_source
+        {
+            self.remove_track(src_index)?;
+            if src_index < dest_index
+            {
+                return Ok(dest_index - 1);
+            }
+        }
+        Ok(dest_index)
+    }
+
+    /// Get the samples of a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    ///
+    /// # Returns
+    /// `Result<&[f32], String>` - Ok with interleaved samples, Err if track index invalid
+    pub fn get_track_samples(&self, track_index: usize) -> Result<&[f32], String>
+    {
+        self.tracks
+            .get(track_index)
+            .map(|track| track.audio_data.as_slice())
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))
+    }
+
+    /// Get the MD5 checksum of a track's audio
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `bits_per_sample` - integer width the samples are quantized to before hashing, 16 or 24
+    ///   (None for the track's source bit depth, or 16 if that is neither)
+    ///
+    /// # Returns
+    /// `Result<String, String>` - Ok with the lowercase hex digest, Err if track index or bit depth invalid
+    ///
+    /// # Notes
+    /// The digest covers the quantized samples as little-endian bytes, the same as the
+    /// MD5 in FLAC STREAMINFO, so a re-imported export can be checked against its source
+    pub fn get_track_md5(&self, track_index: usize, bits_per_sample: Option<u8>) -> Result<String, String>
+    {
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+
+        let bits_per_sample = bits_per_sample.unwrap_or(match track.source_format.bits_per_sample
+        {
+            Some(24) => 24,
+            _ => 16,
+        });
+        let digest = crate::flac::audio_md5(&track.audio_data, bits_per_sample).map_err(|e| e.to_string())?;
+        Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    /// Get the samples of a track within a timeline region
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `start_time` - start of region in seconds
+    /// * `end_time` - end of region in seconds
+    ///
+    /// # Returns
+    /// `Result<&[f32], String>` - Ok with interleaved samples (clamped to the track), Err if track index invalid
+    pub fn get_region_samples(&self, track_index: usize, start_time: f64, end_time: f64) -> Result<&[f32], String>
+    {
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+
+        let (start, end) = Self::region_sample_range(track, start_time, end_time);
+        Ok(&track.audio_data[start..end])
+    }
+
+    /// Get peak and RMS levels over a whole track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to measure
+    ///
+    /// # Returns
+    /// `Result<(f32, f32), String>` - Ok with (peak, rms) linear amplitudes, Err if track index invalid
+    ///
+    /// # Notes
+    /// Both values are taken across all channels
+    pub fn get_track_levels(&self, track_index: usize) -> Result<(f32, f32), String>
+    {
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+        Ok(Self::measure_levels(&track.audio_data))
+    }
+
+    /// Get peak and RMS levels over a region of a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to measure
+    /// * `start_time` - start of region in seconds on the timeline
+    /// * `end_time` - end of region in seconds on the timeline
+    ///
+    /// # Returns
+    /// `Result<(f32, f32), String>` - Ok with (peak, rms) linear amplitudes, Err if track index invalid
+    pub fn get_region_levels(&self, track_index: usize, start_time: f64, end_time: f64) -> Result<(f32, f32), String>
+    {
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+        let (start_sample, end_sample) = Self::region_sample_range(track, start_time, end_time);
+        Ok(Self::measure_levels(&track.audio_data[start_sample..end_sample]))
+    }
+
+    /// Find silent regions in a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to analyze
+    /// * `threshold_db` - level below which audio counts as silent, in dBFS
+    /// * `min_duration` - minimum length of a silent region in seconds
+    ///
+    /// # Returns
+    /// `Result<Vec<(f64, f64)>, String>` - Ok with (start, end) times on the timeline, Err if track index invalid
+    ///
+    /// # Notes
+    /// Levels are measured with a 10 ms RMS window so single quiet or loud samples
+    /// do not start or split a region
+    pub fn detect_silence(&self, track_index: usize, threshold_db: f64, min_duration: f64) -> Result<Vec<(f64, f64)>, String>
+    {
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+
+        let threshold = effects::db_to_gain(threshold_db);
+        let min_frames = (min_duration.max(0.0) * track.sample_rate as f64).round() as usize;
+        let runs = analysis::detect_silence(&track.audio_data, track.channels, track.sample_rate, threshold, min_frames);
+
+        let rate = track.sample_rate as f64;
+        Ok(runs
+            .into_iter()
+            .map(|(start, end)| (track.start_offset + start as f64 / rate, track.start_offset + end as f64 / rate))
+            .collect())
+    }
+
+    /// Find clipped regions in a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to analyze
+    /// * `min_run` - minimum number of consecutive full-scale samples on a channel to report
+    ///
+    /// # Returns
+    /// `Result<Vec<(f64, f64)>, String>` - Ok with (start, end) times on the timeline, Err if track index invalid
+    pub fn detect_clipping(&self, track_index: usize, min_run: usize) -> Result<Vec<(f64, f64)>, String>
+    {
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+
+        let spans = analysis::detect_clipping(&track.audio_data, track.channels, min_run);
+
+        let rate = track.sample_rate as f64;
+        Ok(spans
+            .into_iter()
+            .map(|(start, end)| (track.start_offset + start as f64 / rate, track.start_offset + end as f64 / rate))
+            .collect())
+    }
+
+    /// Trim leading and trailing silence from a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to trim
+    /// * `threshold_db` - level below which audio counts as silent, in dBFS
+    /// * `margin` - seconds of silence to keep before and after the audio
+    ///
+    /// # Returns
+    /// `Result<(f64, f64), String>` - Ok with seconds removed from the start and end, Err if track index invalid or track is silent
+    ///
+    /// # Notes
+    /// The track's start offset is advanced by the amount removed from its head so the
+    /// remaining audio keeps its position on the timeline
+    pub fn trim_silence(&mut self, track_index: usize, threshold_db: f64, margin: f64) -> Result<(f64, f64), String>
+    {
+        let track = self.tracks
+            .get_mut(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+
+        let total_frames = track.audio_data.len() / track.channels;
+        let threshold = effects::db_to_gain(threshold_db);
+        let runs = analysis::detect_silence(&track.audio_data, track.channels, track.sample_rate, threshold, 1);
+
+        if runs.first().is_some_and(|&(start, end)| start == 0 && end == total_frames)
+        {
+            return Err("Track contains only silence".to_string());
+        }
+
+        let margin_frames = (margin.max(0.0) * track.sample_rate as f64).round() as usize;
+        let head = runs
+            .first()
+            .filter(|&&(start, _)| start == 0)
+            .map_or(0, |&(_, end)| end.saturating_sub(margin_frames));
+        let tail = runs
+            .last()
+            .filter(|&&(_, end)| end == total_frames)
+            .map_or(total_frames, |&(start, _)| (start + margin_frames).min(total_frames));
+
+        let audio_data = track.audio_data.to_mut();
+        audio_data.truncate(tail * track.channels);
+        audio_data.drain(..head * track.channels);
+
+        let rate = track.sample_rate as f64;
+        track.start_offset += head as f64 / rate;
+        track.gain_envelope = Self::shift_envelope(&track.gain_envelope, head as f64 / rate);
+        Ok((head as f64 / rate, (total_frames - tail) as f64 / rate))
+    }
+
+    /// Convert a timeline region to a range of interleaved sample indices within a track
+    ///
+    /// # Parameters
+    /// * `track` - audio track
+    /// * `start_time` - start of region in seconds on the timeline
+    /// * `end_time` - end of region in seconds on the timeline
+    ///
+    /// # Returns
+    /// `(usize, usize)` - (start_sample, end_sample), clamped to the track and aligned to frames
+    fn region_sample_range(track: &AudioTrack, start_time: f64, end_time: f64) -> (usize, usize)
+    {
+        let total_frames = track.audio_data.len() / track.channels;
+        let start_frame = Self::frame_at_time(track, start_time);
+        let end_frame = Self::frame_at_time(track, end_time);
+
+        let start_frame = start_frame.min(total_frames);
+        let end_frame = end_frame.clamp(start_frame, total_frames);
+        (start_frame * track.channels, end_frame * track.channels)
+    }
+
+    /// Convert a timeline position to a frame index within a track
+    ///
+    /// # Parameters
+    /// * `track` - audio track
+    /// * `time` - timeline position in seconds
+    ///
+    /// # Returns
+    /// `usize` - frame index, 0 before the track starts and not clamped to its length
+    ///
+    /// # Notes
+    /// Rounds down, allowing `FRAME_EPSILON` for times computed from a frame index
+    fn frame_at_time(track: &AudioTrack, time: f64) -> usize
+    {
+        ((time - track.start_offset).max(0.0) * track.sample_rate as f64 + FRAME_EPSILON) as usize
+    }
+
+    /// Move envelope breakpoints earlier in time
+    ///
+    /// # Parameters
+    /// * `envelope` - (time, gain_db) breakpoints
+    /// * `shift` - seconds to subtract from each breakpoint time
+    ///
+    /// # Returns
+    /// `Vec<(f64, f64)>` - shifted breakpoints
+    fn shift_envelope(envelope: &[(f64, f64)], shift: f64) -> Vec<(f64, f64)>
+    {
+        envelope.iter().map(|&(time, gain_db)| (time - shift, gain_db)).collect()
+    }
+
+    /// Map a timeline position to a frame within a track
+    ///
+    /// # Parameters
+    /// * `track` - audio track to index
+    /// * `time` - timeline position in seconds
+    ///
+    /// # Returns
+    /// `Option<usize>` - frame index within the track, or None if the track has no audio at that time
+    fn track_frame_at(track: &AudioTrack, time: f64) -> Option<usize>
+    {
+        if time < track.start_offset
+        {
+            return None;
+        }
+
+        let frame = Self::frame_at_time(track, time);
+        (frame < track.audio_data.len() / track.channels).then_some(frame)
+    }
+
+    /// Measure peak and RMS amplitude of a block of samples
+    ///
+    /// # Parameters
+    /// * `samples` - samples to measure
+    ///
+    /// # Returns
+    /// `(f32, f32)` - (peak, rms), or zeros for an empty block
+    fn measure_levels(samples: &[f32]) -> (f32, f32)
+    {
+        if samples.is_empty()
+        {
+            return (0.0, 0.0);
+        }
+
+        let peak = samples.iter().fold(0.0f32, |peak, &s| peak.max(s.abs()));
+        let sum_squares: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+        let rms = (sum_squares / samples.len() as f64).sqrt() as f32;
+        (peak, rms)
+    }
+
+    /// Get waveform data for a specific time range for all tracks
+    ///
+    /// # Parameters
+    /// * `start_time` - start of range in seconds
+    /// * `end_time` - end of range in seconds
+    /// * `num_pixels` - desired number of display pixels
+    ///
+    /// # Returns
+    /// `Vec<Vec<(f32, f32, f32, f32)>>` - waveform data per track as (min_l, max_l, min_r, max_r) tuples
+    ///
+    /// # Notes
+    /// Returns separate waveform data for each track. For mono audio, left and right
+    /// values are identical.
+    pub fn get_waveform_for_range(&self, start_time: f64, end_time: f64, num_pixels: usize) -> Vec<Vec<(f32, f32, f32, f32)>>
+    {
+        if self.tracks.is_empty() || num_pixels == 0
+        {
+            return Vec::new();
+        }
+
+        self.tracks.iter().map(|track|
+        {
+            Self::get_track_waveform(track, start_time, end_time, num_pixels)
+        }).collect()
+    }
+
+    /// Get waveform data for a specific time range for one track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to analyze
+    /// * `start_time` - start of range in seconds
+    /// * `end_time` - end of range in seconds
+    /// * `num_pixels` - desired number of display pixels
+    ///
+    /// # Returns
+    /// `Result<Vec<(f32, f32, f32, f32)>, String>` - waveform data as (min_l, max_l, min_r, max_r) tuples,
+    /// Err if track index invalid
+    pub fn get_track_waveform_for_range(&self, track_index: usize, start_time: f64, end_time: f64, num_pixels: usize) -> Result<Vec<(f32, f32, f32, f32)>, String>
+    {
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+        Ok(Self::get_track_waveform(track, start_time, end_time, num_pixels))
+    }
+
+    /// Get waveform data for a single track
+    ///
+    /// # Parameters
+    /// * `track` - audio track to analyze
+    /// * `start_time` - start of range in seconds
+    /// * `end_time` - end of range in seconds
+    /// * `num_pixels` - desired number of display pixels
+    ///
+    /// # Returns
+    /// `Vec<(f32, f32, f32, f32)>` - waveform data as (min_l, max_l, min_r, max_r) tuples
+    fn get_track_waveform(track: &AudioTrack, start_time: f64, end_time: f64, num_pixels: usize) -> Vec<(f32, f32, f32, f32)>
+    {
+        if track.audio_data.is_empty() || num_pixels == 0
+        {
+            return vec![(0.0, 0.0, 0.0, 0.0); num_pixels];
+        }
+
+        let track_audio_duration = (track.audio_data.len() / track.channels) as f64 / track.sample_rate as f64;
+        let track_end_time = track.start_offset + track_audio_duration;
+
+        // if the view range doesn't overlap with this track, return silence
+        if end_time <= track.start_offset || start_time >= track_end_time
+        {
+            return vec![(0.0, 0.0, 0.0, 0.0); num_pixels];
+        }
+
+        // calculate times relative to track's audio data
+        let relative_start = (start_time - track.start_offset).max(0.0);
+        let relative_end = (end_time - track.start_offset).min(track_audio_duration);
+
+        let start_frame = ((relative_start * track.sample_rate as f64) as usize).min(track.audio_data.len() / track.channels);
+        let end_frame = ((relative_end * track.sample_rate as f64) as usize).min(track.audio_data.len() / track.channels);
+
+        if start_frame >= end_frame
+        {
+            return vec![(0.0, 0.0, 0.0, 0.0); num_pixels];
+        }
+
+        // calculate how many pixels correspond to the actual audio portion
+        let view_duration = end_time - start_time;
+        let audio_start_in_view = (track.start_offset - start_time).max(0.0);
+        let audio_end_in_view = (track_end_time - start_time).min(view_duration);
+
+        let start_pixel = (((audio_start_in_view / view_duration) * num_pixels as f64) as usize).min(num_pixels - 1);
+        let end_pixel = (((audio_end_in_view / view_duration) * num_pixels as f64).ceil() as usize).min(num_pixels);
+        let audio_pixels = end_pixel.saturating_sub(start_pixel).max(1);
+
+        let frame_count = end_frame - start_frame;
+        let samples_per_pixel = (frame_count as f64) / (audio_pixels as f64);
+
+        // build result with silence before and after as needed
+        let mut waveform = vec![(0.0, 0.0, 0.0, 0.0); num_pixels];
+
+        if samples_per_pixel < 1.0
+        {
+            // we're zoomed in far enough to see individual samples
+            // return one entry per actual sample in their correct pixel positions
+            let pixels_per_sample = audio_pixels as f64 / frame_count as f64;
+
+            for (i, frame) in (start_frame..end_frame).enumerate()
+            {
+                let pixel_idx = start_pixel + (i as f64 * pixels_per_sample) as usize;
+                if pixel_idx >= num_pixels
+                {
+                    break;
+                }
+
+                if track.channels == 2
+                {
+                    let idx = frame * 2;
+                    if idx + 1 < track.audio_data.len()
+                    {
+                        let left = track.audio_data[idx];
+                        let right = track.audio_data[idx + 1];
+                        waveform[pixel_idx] = (0.0, left, 0.0, right);
+                    }
+                }
+                else if track.channels == 1
+                {
+                    if frame < track.audio_data.len()
+                    {
+                        let sample = track.audio_data[frame];
+                        waveform[pixel_idx] = (0.0, sample, 0.0, sample);
+                    }
+                }
+                else
+                {
+                    let idx = frame * track.channels;
+                    if idx < track.audio_data.len()
+                    {
+                        let sample = track.audio_data[idx];
+                        waveform[pixel_idx] = (0.0, sample, 0.0, sample);
+                    }
+                }
+            }
+
+            // early return to bypass max/min rendering
+            return waveform;
+        }
+
+        // normal case: aggregate samples per pixel
+        // boundaries use integer division so that each pixel ends exactly where the next
+        // begins and the last pixel ends at end_frame
+        let pixel_boundary = |i: usize| start_frame + (i as u64 * frame_count as u64 / audio_pixels as u64) as usize;
+
+        // once pixels span whole mip blocks, read the cached summaries instead of every frame
+        let mipmap = if samples_per_pixel >= waveform::MIPMAP_BASE_FRAMES as f64
+        {
+            track.audio_data.mipmap(track.channels)
+        }
+        else
+        {
+            None
+        };
+
+        for i in 0..audio_pixels
+        {
+            let pixel_idx = start_pixel + i;
+            let pixel_start_frame = pixel_boundary(i);
+            let pixel_end_frame = pixel_boundary(i + 1);
+
+            if pixel_start_frame >= pixel_end_frame
+            {
+                continue;
+            }
+
+            waveform[pixel_idx] = match mipmap
+            {
+                Some(mipmap) => mipmap.peaks(&track.audio_data, track.channels, pixel_start_frame, pixel_end_frame),
+                None => waveform::scan_peaks(&track.audio_data, track.channels, pixel_start_frame, pixel_end_frame),
+            };
+        }
+
+        waveform
+    }
+
+    /// Mix all tracks together for playback
+    ///
+    /// # Parameters
+    /// * `start_time` - start time in seconds
+    /// * `end_time` - end time in seconds
+    ///
+    /// # Returns
+    /// `(Vec<f32>, u32, usize)` - mixed audio data, sample rate, and channel count
+    ///
+    /// # Notes
+    /// Preserves mono if all tracks are mono, otherwise converts to stereo, folding
+    /// tracks with more than two channels down with `fold_to_stereo`.
+    /// Uses the sample rate of the first track. Accounts for track start offsets.
+    fn mix_tracks_for_playback(&self, start_time: f64, end_time: f64) -> (Vec<f32>, u32, usize)
+    {
+        Self::mix_tracks(&self.tracks, start_time, end_time)
+    }
+
+    /// Mix a set of tracks together
+    ///
+    /// # Parameters
+    /// * `tracks` - tracks to mix
+    /// * `start_time` - start time in seconds
+    /// * `end_time` - end time in seconds
+    ///
+    /// # Returns
+    /// `(Vec<f32>, u32, usize)` - mixed audio data, sample rate, and channel count
+    ///
+    /// # Notes
+    /// Preserves mono if all tracks are mono, otherwise converts to stereo, folding
+    /// tracks with more than two channels down with `fold_to_stereo`.
+    /// Uses the sample rate of the first track. Accounts for track start offsets.
+    fn mix_tracks(tracks: &[AudioTrack], start_time: f64, end_time: f64) -> (Vec<f32>, u32, usize)
+    {
+        let (mut mixed_data, sample_rate, output_channels) = Self::sum_tracks(tracks, start_time, end_time);
+
+        for sample in &mut mixed_data
+        {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+
+        (mixed_data, sample_rate, output_channels)
+    }
+
+    /// Sum a set of tracks together without clamping the result
+    ///
+    /// # Parameters
+    /// * `tracks` - tracks to mix
+    /// * `start_time` - start time in seconds
+    /// * `end_time` - end time in seconds
+    ///
+    /// # Returns
+    /// `(Vec<f32>, u32, usize)` - summed audio data, sample rate, and channel count
+    ///
+    /// # Notes
+    /// Same layout rules as `mix_tracks`; samples may exceed -1.0..1.0
+    fn sum_tracks(tracks: &[AudioTrack], start_time: f64, end_time: f64) -> (Vec<f32>, u32, usize)
+    {
+        if tracks.is_empty()
+        {
+            return (Vec::new(), 44100, 2);
+        }
+
+        let sample_rate = tracks[0].sample_rate;
+        let has_stereo = tracks.iter().any(|t| t.channels >= 2);
+        let output_channels = if has_stereo { 2 } else { 1 };
+
+        let start_frame = (start_time * sample_rate as f64) as usize;
+        let end_frame = (end_time * sample_rate as f64) as usize;
+        let total_frames = end_frame.saturating_sub(start_frame);
+
+        if total_frames == 0
+        {
+            return (Vec::new(), sample_rate, output_channels);
+        }
+
+        let mut mixed_data = vec![0.0f32; total_frames * output_channels];
+
+        for track in tracks
+        {
+            Self::mix_track_into(&mut mixed_data, output_channels, track, start_time, sample_rate, MonoFold::default());
+        }
+
+        (mixed_data, sample_rate, output_channels)
+    }
+
+    /// Add one track into a mix buffer
+    ///
+    /// # Parameters
+    /// * `mixed` - interleaved mix buffer, starting at `start_time`
+    /// * `output_channels` - channel count of the mix, 1 or 2
+    /// * `track` - track to add, with its gain envelope applied
+    /// * `start_time` - timeline position of the first frame of `mixed` in seconds
+    /// * `sample_rate` - sample rate of the mix
+    /// * `mono_fold` - left and right weights for stereo tracks when the mix is mono
+    ///
+    /// # Notes
+    /// The output frames the track covers are found once up front. When the track is
+    /// at the mix rate its frames map one to one onto them, so the common layouts run
+    /// as plain slice additions the compiler can vectorize. Tracks at another rate
+    /// are indexed frame by frame through `track_frame_at`.
+    fn mix_track_into(mixed: &mut [f32], output_channels: usize, track: &AudioTrack, start_time: f64, sample_rate: u32, mono_fold: MonoFold)
+    {
+        let total_frames = mixed.len() / output_channels;
+        let track_frames = track.audio_data.len() / track.channels;
+        let frame_time = |frame_idx: usize| start_time + (frame_idx as f64 / sample_rate as f64);
+
+        // first output frame at or after the track's start, settled against frame_time
+        // itself so rounding agrees with track_frame_at
+        let mut first = ((track.start_offset - start_time) * sample_rate as f64).ceil().max(0.0) as usize;
+        while first > 0 && frame_time(first - 1) >= track.start_offset
+        {
+            first -= 1;
+        }
+        while first < total_frames && frame_time(first) < track.start_offset
+        {
+            first += 1;
+        }
+        if first >= total_frames
+        {
+            return;
+        }
+
+        if track.sample_rate != sample_rate
+        {
+            for frame_idx in first..total_frames
+            {
+                let time = frame_time(frame_idx);
+                let Some(track_frame) = Self::track_frame_at(track, time) else
+                {
+                    break;
+                };
+                let frame = &track.audio_data[track_frame * track.channels..(track_frame + 1) * track.channels];
+                Self::mix_frame(&mut mixed[frame_idx * output_channels..(frame_idx + 1) * output_channels], frame, track.gain_at(time - track.start_offset), mono_fold);
+            }
+            return;
+        }
+
+        let Some(first_track_frame) = Self::track_frame_at(track, frame_time(first)) else
+        {
+            return;
+        };
+        let count = (total_frames - first).min(track_frames - first_track_frame);
+        let output = &mut mixed[first * output_channels..(first + count) * output_channels];
+        let input = &track.audio_data[first_track_frame * track.channels..(first_track_frame + count) * track.channels];
+
+        match (output_channels, track.channels, track.gain_envelope.is_empty())
+        {
+            (2, 2, true) | (1, 1, true) =>
+            {
+                for (out, &sample) in output.iter_mut().zip(input)
+                {
+                    *out += sample;
+                }
+            }
+            (2, 1, true) =>
+            {
+                for (out, &sample) in output.chunks_exact_mut(2).zip(input)
+                {
+                    out[0] += sample;
+                    out[1] += sample;
+                }
+            }
+            _ =>
+            {
+                for (i, (out, frame)) in output.chunks_exact_mut(output_channels).zip(input.chunks_exact(track.channels)).enumerate()
+                {
+                    let gain = track.gain_at(frame_time(first + i) - track.start_offset);
+                    Self::mix_frame(out, frame, gain, mono_fold);
+                }
+            }
+        }
+    }
+
+    /// Add one track frame into one mix frame
+    ///
+    /// # Parameters
+    /// * `out` - mix frame, one or two samples
+    /// * `frame` - track frame, one sample per channel
+    /// * `gain` - linear gain to apply
+    /// * `mono_fold` - left and right weights for a stereo frame into a mono mix
+    ///
+    /// # Notes
+    /// Frames of other channel counts fold to mono with `fold_to_mono`
+    fn mix_frame(out: &mut [f32], frame: &[f32], gain: f32, mono_fold: MonoFold)
+    {
+        if out.len() == 2
+        {
+            let (left, right) = Self::fold_to_stereo(frame);
+            out[0] += left * gain;
+            out[1] += right * gain;
+        }
+        else if frame.len() == 2
+        {
+            out[0] += (frame[0] * mono_fold.left + frame[1] * mono_fold.right) * gain;
+        }
+        else
+        {
+            out[0] += Self::fold_to_mono(frame) * gain;
+        }
+    }
+
+    /// Fold one frame of any channel count down to mono
+    ///
+    /// # Parameters
+    /// * `frame` - one sample per channel
+    ///
+    /// # Returns
+    /// `f32` - average of all channels
+    fn fold_to_mono(frame: &[f32]) -> f32
+    {
+        frame.iter().sum::<f32>() / frame.len().max(1) as f32
+    }
+
+    /// Fold one frame of any channel count down to stereo
+    ///
+    /// # Parameters
+    /// * `frame` - one sample per channel
+    ///
+    /// # Returns
+    /// `(f32, f32)` - left and right samples
+    ///
+    /// # Notes
+    /// Mono is duplicated and stereo passes through. Quad (FL FR RL RR), 5.1
+    /// (FL FR FC LFE RL RR), and 7.1 (FL FR FC LFE RL RR SL SR) use ITU-style
+    /// weights with the centre and surrounds at -3 dB, the LFE dropped, and the
+    /// result scaled so a full-scale signal on every channel stays at full scale.
+    /// Any other layout is unknown, so every channel is averaged into both sides.
+    fn fold_to_stereo(frame: &[f32]) -> (f32, f32)
+    {
+        const MINUS_3DB: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+        match frame.len()
+        {
+            1 => (frame[0], frame[0]),
+            2 => (frame[0], frame[1]),
+            4 =>
+            {
+                let scale = 1.0 / (1.0 + MINUS_3DB);
+                ((frame[0] + MINUS_3DB * frame[2]) * scale, (frame[1] + MINUS_3DB * frame[3]) * scale)
+            }
+            6 =>
+            {
+                let scale = 1.0 / (1.0 + 2.0 * MINUS_3DB);
+                let centre = MINUS_3DB * frame[2];
+                ((frame[0] + centre + MINUS_3DB * frame[4]) * scale,
+                 (frame[1] + centre + MINUS_3DB * frame[5]) * scale)
+            }
+            8 =>
+            {
+                let scale = 1.0 / (1.0 + 3.0 * MINUS_3DB);
+                let centre = MINUS_3DB * frame[2];
+                ((frame[0] + centre + MINUS_3DB * (frame[4] + frame[6])) * scale,
+                 (frame[1] + centre + MINUS_3DB * (frame[5] + frame[7])) * scale)
+            }
+            _ =>
+            {
+                let mono = Self::fold_to_mono(frame);
+                (mono, mono)
+            }
+        }
+    }
+
+    /// Mix tracks with specific channel mode for export
+    ///
+    /// # Parameters
+    /// * `start_time` - start time in seconds
+    /// * `end_time` - end time in seconds
+    /// * `channel_mode` - channel configuration mode
+    /// * `mono_fold` - stereo weights and gain for the mono mode
+    ///
+    /// # Returns
+    /// `Vec<(Vec<f32>, u32, usize, String)>` - list of (audio data, sample rate, channels, suffix)
+    ///
+    /// # Notes
+    /// Returns multiple results for split mode, single result otherwise. Sums are
+    /// not clamped; `render_export` applies the bus clamp or limiter.
+    fn mix_tracks_for_export(&self, start_time: f64, end_time: f64, channel_mode: &str, mono_fold: MonoFold) -> Vec<(Vec<f32>, u32, usize, String)>
+    {
+        if self.tracks.is_empty()
+        {
+            return vec![(Vec::new(), 44100, 2, String::new())];
+        }
+
+        let sample_rate = self.tracks[0].sample_rate;
+        let start_frame = (start_time * sample_rate as f64) as usize;
+        let end_frame = (end_time * sample_rate as f64) as usize;
+        let total_frames = end_frame.saturating_sub(start_frame);
+
+        if total_frames == 0
+        {
+            return vec![(Vec::new(), sample_rate, 2, String::new())];
+        }
+
+        match channel_mode
+        {
+            "split" =>
+            {
+                // split all stereo tracks to separate mono tracks with _L and _R suffixes
+                let mut results = Vec::new();
+                for track in &self.tracks
+                {
+                    if track.channels == 2
+                    {
+                        let mut left_data = vec![0.0f32; total_frames];
+                        let mut right_data = vec![0.0f32; total_frames];
+                        let mut written_frames = 0;
+
+                        for frame_idx in 0..total_frames
+                        {
+                            let output_time = start_time + (frame_idx as f64 / sample_rate as f64);
+                            if let Some(track_frame) = Self::track_frame_at(track, output_time)
+                            {
+                                let track_idx = track_frame * 2;
+                                let gain = track.gain_at(output_time - track.start_offset);
+                                left_data[frame_idx] = track.audio_data[track_idx] * gain;
+                                right_data[frame_idx] = track.audio_data[track_idx + 1] * gain;
+                                written_frames = frame_idx + 1;
+                            }
+                        }
+
+                        // stop at the end of the track's audio, keeping any leading gap from its offset
+                        left_data.truncate(written_frames);
+                        right_data.truncate(written_frames);
+
+                        results.push((left_data, sample_rate, 1, "_L".to_string()));
+                        results.push((right_data, sample_rate, 1, "_R".to_string()));
+                    }
+                }
+                if results.is_empty()
+                {
+                    results.push((Vec::new(), sample_rate, 1, String::new()));
+                }
+                results
+            }
+            "mono_to_stereo" =>
+            {
+                // combine pairs of mono tracks into stereo tracks
+                let mut stereo_data = vec![0.0f32; total_frames * 2];
+
+                let mono_tracks: Vec<&AudioTrack> = self.tracks.iter().filter(|t| t.channels == 1).collect();
+
+                // process pairs of mono tracks
+                for pair_idx in (0..mono_tracks.len()).step_by(2)
+                {
+                    if pair_idx + 1 >= mono_tracks.len()
+                    {
+                        break;
+                    }
+
+                    let left_track = mono_tracks[pair_idx];
+                    let right_track = mono_tracks[pair_idx + 1];
+
+                    for frame_idx in 0..total_frames
+                    {
+                        let output_idx = frame_idx * 2;
+                        let output_time = start_time + (frame_idx as f64 / sample_rate as f64);
+
+                        if let Some(track_frame) = Self::track_frame_at(left_track, output_time)
+                        {
+                            stereo_data[output_idx] = left_track.audio_data[track_frame] * left_track.gain_at(output_time - left_track.start_offset);
+                        }
+
+                        if let Some(track_frame) = Self::track_frame_at(right_track, output_time)
+                        {
+                            stereo_data[output_idx + 1] = right_track.audio_data[track_frame] * right_track.gain_at(output_time - right_track.start_offset);
+                        }
+                    }
+                }
+
+                vec![(stereo_data, sample_rate, 2, String::new())]
+            }
+            "mono" =>
+            {
+                // downmix all tracks to mono
+                let mut mono_data = vec![0.0f32; total_frames];
+
+                for track in &self.tracks
+                {
+                    Self::mix_track_into(&mut mono_data, 1, track, start_time, sample_rate, mono_fold);
+                }
+
+                for sample in &mut mono_data
+                {
+                    *sample *= mono_fold.gain;
+                }
+
+                vec![(mono_data, sample_rate, 1, String::new())]
+            }
+            _ =>
+            {
+                // default: mix all tracks however they would be played back
+                let (data, rate, channels) = Self::sum_tracks(&self.tracks, start_time, end_time);
+                vec![(data, rate, channels, String::new())]
+            }
+        }
+    }
+
+    /// Start audio playback
+    ///
+    /// # Parameters
+    /// * `start_time` - optional start time in seconds
+    /// * `end_time` - optional end time in seconds
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    ///
+    /// # Notes
+    /// If both times are None and playback is paused, resumes from current position.
+    /// Mixes all tracks together for playback.
+    pub fn play(&mut self, start_time: Option<f64>, end_time: Option<f64>) -> Result<(), String>
+    {
+        // resume paused playback if no times specified
+        if start_time.is_none() && end_time.is_none()
+        {
+            if let Some(ref mut playback) = self.playback
+            {
+                if playback.is_paused()
+                {
+                    playback.resume()?;
+                    return Ok(());
+                }
+            }
+        }
+
+        let duration = self.get_duration();
+        let start = start_time.unwrap_or(0.0);
+        let end = end_time.unwrap_or(duration);
+
+        let (mixed_data, sample_rate, channels) = self.mix_tracks_for_playback(start, end);
+        self.start_playback(mixed_data, sample_rate, channels, start)?;
+        self.playback_mode = Some(PlaybackMode::Mix { end: end_time });
+        Ok(())
+    }
+
+    /// Play a single track on its own
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to audition
+    /// * `start_time` - optional start time in seconds
+    /// * `end_time` - optional end time in seconds (defaults to the end of the track)
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if track index invalid or playback fails
+    ///
+    /// # Notes
+    /// A one-shot audition: only this track is mixed, keeping its own channel count
+    /// and sample rate. Other tracks are unaffected.
+    pub fn play_track(&mut self, track_index: usize, start_time: Option<f64>, end_time: Option<f64>) -> Result<(), String>
+    {
+        let (mixed_data, sample_rate, channels, start) = self.mix_track_for_playback(track_index, start_time, end_time)?;
+        self.start_playback(mixed_data, sample_rate, channels, start)?;
+        self.playback_mode = Some(PlaybackMode::Track { track_index, end: end_time });
+        Ok(())
+    }
+
+    /// Mix a single track for an audition
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to mix
+    /// * `start_time` - optional start time in seconds (defaults to the start of the track)
+    /// * `end_time` - optional end time in seconds (defaults to the end of the track)
+    ///
+    /// # Returns
+    /// `Result<(Vec<f32>, u32, usize, f64), String>` - Ok with mixed audio data, sample rate, channel count, and start time, Err if track index invalid
+    fn mix_track_for_playback(&self, track_index: usize, start_time: Option<f64>, end_time: Option<f64>) -> Result<(Vec<f32>, u32, usize, f64), String>
+    {
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+
+        let track_duration = (track.audio_data.len() / track.channels) as f64 / track.sample_rate as f64;
+        let start = start_time.unwrap_or(track.start_offset.max(0.0));
+        let end = end_time.unwrap_or(track.start_offset + track_duration);
+
+        let (mixed_data, sample_rate, channels) = Self::mix_tracks(std::slice::from_ref(track), start, end);
+        Ok((mixed_data, sample_rate, channels, start))
+    }
+
+    /// Play a region backwards
+    ///
+    /// # Parameters
+    /// * `start_time` - optional start of the region in seconds (defaults to 0)
+    /// * `end_time` - optional end of the region in seconds (defaults to the project end)
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the region is empty or playback fails
+    ///
+    /// # Notes
+    /// Playback runs from the end of the region back to its start. The track
+    /// samples are left untouched.
+    pub fn play_reverse(&mut self, start_time: Option<f64>, end_time: Option<f64>) -> Result<(), String>
+    {
+        let start = start_time.unwrap_or(0.0);
+        let end = end_time.unwrap_or_else(|| self.get_duration());
+        if end <= start
+        {
+            return Err(format!("Invalid reverse region: {:.3}s to {:.3}s", start, end));
+        }
+
+        let (mixed_data, sample_rate, channels) = self.mix_tracks_for_playback(start, end);
+        self.open_playback(sample_rate, channels)?.play_reverse(mixed_data, start)?;
+        self.playback_mode = Some(PlaybackMode::Reverse { start });
+        Ok(())
+    }
+
+    /// Send a mixed buffer to the output device
+    ///
+    /// # Parameters
+    /// * `mixed_data` - interleaved samples to play
+    /// * `sample_rate` - sample rate of the buffer in Hz
+    /// * `channels` - number of interleaved channels
+    /// * `start` - timeline position of the first sample in seconds
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    ///
+    /// # Notes
+    /// Rebuilds the output stream if the rate or channel count differs from the current one
+    fn start_playback(&mut self, mixed_data: Vec<f32>, sample_rate: u32, channels: usize, start: f64) -> Result<(), String>
+    {
+        let ab_loop = self.ab_loop;
+        let playback = self.open_playback(sample_rate, channels)?;
+        playback.play(mixed_data, start)?;
+        if let Some((a, b)) = ab_loop
+        {
+            playback.set_loop(a, b);
+        }
+        Ok(())
+    }
+
+    /// Get the output stream, rebuilding it if the rate or channel count differs from the current one
+    ///
+    /// # Parameters
+    /// * `sample_rate` - sample rate of the audio to play in Hz
+    /// * `channels` - number of interleaved channels
+    ///
+    /// # Returns
+    /// `Result<&mut AudioPlayback, String>` - Ok with the playback instance
+    fn open_playback(&mut self, sample_rate: u32, channels: usize) -> Result<&mut AudioPlayback, String>
+    {
+        let needs_new_playback = self.playback.is_none() ||
+            self.playback_sample_rate != Some(sample_rate) ||
+            self.playback_channels != Some(channels);
+
+        if needs_new_playback
+        {
+            let mut playback = match self.output_device.as_deref()
+            {
+                Some(name) => AudioPlayback::with_device(sample_rate, channels, name)?,
+                None => AudioPlayback::new(sample_rate, channels)?,
+            };
+            playback.set_playback_speed(self.playback_speed)?;
+            playback.set_fade_length(self.playback_fade_ms);
+            playback.set_seek_crossfade(self.playback_seek_crossfade_ms);
+            playback.set_balance(self.playback_balance)?;
+            playback.set_resample_quality(self.resample_quality);
+            self.playback = Some(playback);
+            self.playback_sample_rate = Some(sample_rate);
+            self.playback_channels = Some(channels);
+        }
+
+        Ok(self.playback.as_mut().unwrap())
+    }
+
+    /// Play several regions back to back without gaps
+    ///
+    /// # Parameters
+    /// * `regions` - (start, end) of each region in seconds, in play order
+    /// * `crossfade_ms` - overlap between consecutive regions in milliseconds, 0 for a hard cut
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if no regions are given, a region is empty, or playback fails
+    ///
+    /// # Notes
+    /// Every region is mixed up front. Use `get_current_queue_index` to see which one is playing.
+    pub fn play_queue(&mut self, regions: &[(f64, f64)], crossfade_ms: f64) -> Result<(), String>
+    {
+        if regions.is_empty()
+        {
+            return Err("No regions to queue".to_string());
+        }
+        if crossfade_ms < 0.0 || !crossfade_ms.is_finite()
+        {
+            return Err(format!("Invalid crossfade length: {}", crossfade_ms));
+        }
+
+        let mut items = Vec::with_capacity(regions.len());
+        let mut layout = None;
+        for &(start_time, end_time) in regions
+        {
+            if end_time <= start_time
+            {
+                return Err(format!("Invalid queue region: {:.3}s to {:.3}s", start_time, end_time));
+            }
+            let (data, sample_rate, channels) = self.mix_tracks_for_playback(start_time, end_time);
+            layout = Some((sample_rate, channels));
+            items.push((data, start_time, crossfade_ms));
+        }
+
+        // every region is mixed from the same tracks, so they share one layout
+        let (sample_rate, channels) = layout.unwrap();
+        self.open_playback(sample_rate, channels)?.play_queue(items)?;
+        self.playback_mode = Some(PlaybackMode::Queue { regions: regions.to_vec(), crossfade_ms, first_index: 0 });
+        Ok(())
+    }
+
+    /// Get which queued region is playing
+    ///
+    /// # Returns
+    /// `Option<usize>` - index into the regions passed to `play_queue`, None if no queue is playing
+    pub fn get_current_queue_index(&self) -> Option<usize>
+    {
+        let first_index = match self.playback_mode
+        {
+            Some(PlaybackMode::Queue { first_index, .. }) => first_index,
+            _ => 0,
+        };
+        self.playback
+            .as_ref()
+            .and_then(|p| p.get_current_queue_index())
+            .map(|index| index + first_index)
+    }
+
+    /// Play a region repeatedly
+    ///
+    /// # Parameters
+    /// * `start_time` - loop start in seconds
+    /// * `end_time` - loop end in seconds
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the region is empty or playback fails
+    ///
+    /// # Notes
+    /// Playback wraps from the loop end back to its start until stopped or the loop is cleared
+    pub fn play_loop(&mut self, start_time: f64, end_time: f64) -> Result<(), String>
+    {
+        if end_time <= start_time
+        {
+            return Err(format!("Invalid loop region: {:.3}s to {:.3}s", start_time, end_time));
+        }
+
+        self.play(Some(start_time), Some(end_time))?;
+
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.set_loop(start_time, end_time);
+        }
+        self.playback_mode = Some(PlaybackMode::Loop { start: start_time, end: end_time });
+        Ok(())
+    }
+
+    /// Stop looping and let playback run to the end of the current region
+    pub fn clear_loop(&mut self)
+    {
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.clear_loop();
+        }
+    }
+
+    /// Set A/B loop markers
+    ///
+    /// # Parameters
+    /// * `a` - loop start marker in seconds
+    /// * `b` - loop end marker in seconds
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the markers are not finite or coincide
+    ///
+    /// # Notes
+    /// Markers given in the wrong order are swapped. Takes effect immediately on
+    /// playing audio without restarting it, and stays set for later calls to `play`
+    /// until cleared. Playback wraps from B back to A once it reaches B.
+    pub fn set_ab_loop(&mut self, a: f64, b: f64) -> Result<(), String>
+    {
+        if !a.is_finite() || !b.is_finite() || a == b
+        {
+            return Err(format!("Invalid A/B loop: {:.3}s to {:.3}s", a, b));
+        }
+
+        let (a, b) = if a > b { (b, a) } else { (a, b) };
+        self.ab_loop = Some((a, b));
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.set_loop(a, b);
+        }
+        Ok(())
+    }
+
+    /// Clear the A/B loop markers and let playback run on past B
+    pub fn clear_ab_loop(&mut self)
+    {
+        if self.ab_loop.take().is_some()
+        {
+            if let Some(ref mut playback) = self.playback
+            {
+                playback.clear_loop();
+            }
+        }
+    }
+
+    /// Get the A/B loop markers
+    ///
+    /// # Returns
+    /// `Option<(f64, f64)>` - (A, B) in seconds, None if no A/B loop is set
+    pub fn get_ab_loop(&self) -> Option<(f64, f64)>
+    {
+        self.ab_loop
+    }
+
+    /// List the names of available output devices
+    ///
+    /// # Returns
+    /// `Vec<String>` - device names, empty if no audio devices are available
+    pub fn list_output_devices(&self) -> Vec<String>
+    {
+        AudioPlayback::list_output_devices()
+    }
+
+    /// Select the output device used for playback
+    ///
+    /// # Parameters
+    /// * `device_name` - name of the output device, or None for the default device
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if playback cannot be restarted
+    ///
+    /// # Notes
+    /// The playback stream is rebuilt on the new device. If audio was playing it
+    /// continues from the current position in the same mode it was started in, so a
+    /// single track, loop, queue, or reverse playback carries on as before. Paused
+    /// playback is stopped. Unknown names fall back to the default device.
+    pub fn set_output_device(&mut self, device_name: Option<String>) -> Result<(), String>
+    {
+        let was_playing = self.is_playing();
+        let position = self.get_playback_position();
+        let queue_index = self.get_current_queue_index();
+
+        self.output_device = device_name;
+        self.playback = None;
+        self.playback_sample_rate = None;
+        self.playback_channels = None;
+
+        if !was_playing
+        {
+            return Ok(());
+        }
+
+        match self.playback_mode.clone()
+        {
+            Some(PlaybackMode::Track { track_index, end }) => self.play_track(track_index, Some(position), end),
+            Some(PlaybackMode::Reverse { start }) if position > start => self.play_reverse(Some(start), Some(position)),
+            Some(PlaybackMode::Reverse { .. }) => Ok(()),
+            Some(PlaybackMode::Loop { start, end }) =>
+            {
+                self.play_loop(start, end)?;
+                self.set_playback_position(position);
+                Ok(())
+            }
+            Some(PlaybackMode::Queue { regions, crossfade_ms, first_index }) =>
+            {
+                // requeue from the region that was playing, starting at the current position
+                let current = queue_index.unwrap_or(first_index).min(regions.len() - 1);
+                let mut remaining = regions[current..].to_vec();
+                remaining[0].0 = position.clamp(remaining[0].0, remaining[0].1);
+                if remaining[0].1 <= remaining[0].0
+                {
+                    remaining.remove(0);
+                }
+                if remaining.is_empty()
+                {
+                    return Ok(());
+                }
+                let first_index = regions.len() - remaining.len();
+                self.play_queue(&remaining, crossfade_ms)?;
+                self.playback_mode = Some(PlaybackMode::Queue { regions, crossfade_ms, first_index });
+                Ok(())
+            }
+            Some(PlaybackMode::Mix { end }) => self.play(Some(position), end),
+            None => self.play(Some(position), None),
+        }
+    }
+
+    /// Set the playback speed
+    ///
+    /// # Parameters
+    /// * `speed` - rate multiplier, where 1.0 is normal speed
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if speed is not positive
+    ///
+    /// # Notes
+    /// Takes effect immediately without re-mixing. Pitch changes with speed.
+    pub fn set_playback_speed(&mut self, speed: f64) -> Result<(), String>
+    {
+        if speed <= 0.0 || !speed.is_finite()
+        {
+            return Err(format!("Invalid playback speed: {}", speed));
+        }
+
+        self.playback_speed = speed;
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.set_playback_speed(speed)?;
+        }
+        Ok(())
+    }
+
+    /// Set the quality of every sample rate conversion
+    ///
+    /// # Parameters
+    /// * `quality` - interpolation used when loading, resampling tracks, changing speed or pitch, and playing on a device at another rate
+    ///
+    /// # Notes
+    /// Applies to conversions made from now on; audio already converted is left as it is
+    pub fn set_resample_quality(&mut self, quality: ResampleQuality)
+    {
+        self.resample_quality = quality;
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.set_resample_quality(quality);
+        }
+    }
+
+    /// Get the quality of sample rate conversion
+    ///
+    /// # Returns
+    /// `ResampleQuality` - quality used by every resampling path
+    pub fn get_resample_quality(&self) -> ResampleQuality
+    {
+        self.resample_quality
+    }
+
+    /// Set the length of the fade applied when playback starts, pauses, or stops
+    ///
+    /// # Parameters
+    /// * `fade_ms` - fade length in milliseconds, 0 for instant changes
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the length is negative
+    pub fn set_fade_length(&mut self, fade_ms: f64) -> Result<(), String>
+    {
+        if fade_ms < 0.0 || !fade_ms.is_finite()
+        {
+            return Err(format!("Invalid fade length: {}", fade_ms));
+        }
+
+        self.playback_fade_ms = fade_ms;
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.set_fade_length(fade_ms);
+        }
+        Ok(())
+    }
+
+    /// Set the length of the crossfade applied when seeking during playback
+    ///
+    /// # Parameters
+    /// * `fade_ms` - crossfade length in milliseconds, 0 to jump instantly
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the length is negative
+    pub fn set_seek_crossfade(&mut self, fade_ms: f64) -> Result<(), String>
+    {
+        if fade_ms < 0.0 || !fade_ms.is_finite()
+        {
+            return Err(format!("Invalid seek crossfade length: {}", fade_ms));
+        }
+
+        self.playback_seek_crossfade_ms = fade_ms;
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.set_seek_crossfade(fade_ms);
+        }
+        Ok(())
+    }
+
+    /// Set the master stereo balance of playback
+    ///
+    /// # Parameters
+    /// * `balance` - -1.0 for full left, 0.0 for centre, 1.0 for full right
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if balance is outside -1.0 to 1.0
+    ///
+    /// # Notes
+    /// Applied to the playback output only, after the mix, so track pans and exports
+    /// are unaffected. Kept when the output stream is rebuilt.
+    pub fn set_balance(&mut self, balance: f32) -> Result<(), String>
+    {
+        if !(-1.0..=1.0).contains(&balance)
+        {
+            return Err(format!("Invalid balance: {}", balance));
+        }
+
+        self.playback_balance = balance;
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.set_balance(balance)?;
+        }
+        Ok(())
+    }
+
+    /// Get the master stereo balance of playback
+    ///
+    /// # Returns
+    /// `f32` - -1.0 for full left, 0.0 for centre, 1.0 for full right
+    pub fn get_balance(&self) -> f32
+    {
+        self.playback_balance
+    }
+
+    /// Pause audio playback
+    pub fn pause(&mut self)
+    {
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.pause();
+        }
+    }
+
+    /// Stop audio playback and reset position
+    pub fn stop(&mut self)
+    {
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.stop();
+        }
+    }
+
+    /// Check if audio is currently playing
+    ///
+    /// # Returns
+    /// `bool` - true if playing
+    pub fn is_playing(&self) -> bool
+    {
+        self.playback.as_ref().map(|p| p.is_playing()).unwrap_or(false)
+    }
+
+    /// Check if playback ran to the end on its own
+    ///
+    /// # Returns
+    /// `bool` - true if the last playback reached its end, false if it is still going, was paused or stopped, or never started
+    pub fn has_finished(&self) -> bool
+    {
+        self.playback.as_ref().map(|p| p.has_finished()).unwrap_or(false)
+    }
+
+    /// Get the number of buffer underruns on the output stream
+    ///
+    /// # Returns
+    /// `usize` - times the device ran out of samples, 0 if playback has not started
+    ///
+    /// # Notes
+    /// The count restarts whenever the stream is rebuilt, e.g. when the sample rate or device changes
+    pub fn get_underrun_count(&self) -> usize
+    {
+        self.playback.as_ref().map(|p| p.get_underrun_count()).unwrap_or(0)
+    }
+
+    /// Get details of the most recent buffer underrun on the output stream
+    ///
+    /// # Returns
+    /// `Option<(usize, usize)>` - (frames the device was given, frames that elapsed before more arrived),
+    /// None if no underrun occurred or playback has not started
+    pub fn get_last_underrun(&self) -> Option<(usize, usize)>
+    {
+        self.playback.as_ref().and_then(|p| p.get_last_underrun())
+    }
+
+    /// Get a probe for polling the playback position from another thread
+    ///
+    /// # Returns
+    /// `Option<PositionProbe>` - probe for the current output stream, None if playback has not started
+    ///
+    /// # Notes
+    /// The probe goes stale if the stream is rebuilt, e.g. when the sample rate or device changes
+    pub fn position_probe(&self) -> Option<PositionProbe>
+    {
+        self.playback.as_ref().map(|p| p.position_probe())
+    }
+
+    /// Get output level meter readings
+    ///
+    /// # Returns
+    /// `Vec<f32>` - decaying peak level of each output channel, empty if playback has not started
+    pub fn get_output_levels(&self) -> Vec<f32>
+    {
+        self.playback
+            .as_ref()
+            .map(|p| p.get_output_levels())
+            .unwrap_or_default()
+    }
+
+    /// Get current playback position
+    ///
+    /// # Returns
+    /// `f64` - position in seconds
+    pub fn get_playback_position(&self) -> f64
+    {
+        self.playback
+            .as_ref()
+            .map(|p| p.get_position())
+            .unwrap_or(0.0)
+    }
+
+    /// Set playback position
+    ///
+    /// # Parameters
+    /// * `position` - new position in seconds
+    ///
+    /// # Notes
+    /// Playing audio continues from the new position; paused audio resumes from it
+    pub fn set_playback_position(&mut self, position: f64)
+    {
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.set_position(position);
+        }
+    }
+
+    /// Delete a region of audio from specified tracks
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds on the timeline
+    /// * `end_time` - end of region in seconds on the timeline
+    /// * `track_indices` - slice of track indices to delete from
+    /// * `crossfade_ms` - length of an equal-power crossfade across the join in milliseconds, 0 for a butt join
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the crossfade length is negative
+    ///
+    /// # Notes
+    /// With a crossfade, the audio leading up to the region fades out while the end
+    /// of the removed audio fades in on top of it, running into the audio after the
+    /// region. Exactly the region's length is still removed. The crossfade is
+    /// shortened if the region or the audio before it is shorter.
+    pub fn delete_region(&mut self, start_time: f64, end_time: f64, track_indices: &[usize], crossfade_ms: f64) -> Result<(), String>
+    {
+        if crossfade_ms < 0.0 || !crossfade_ms.is_finite()
+        {
+            return Err(format!("Invalid crossfade length: {}", crossfade_ms));
+        }
+
+        for &track_idx in track_indices
+        {
+            if track_idx >= self.tracks.len()
+            {
+                continue;
+            }
+
+            let track = &mut self.tracks[track_idx];
+            let (start_sample, end_sample) = Self::region_sample_range(track, start_time, end_time);
+            if end_sample <= start_sample
+            {
+                continue;
+            }
+            let start_frame = start_sample / track.channels;
+            let region_frames = (end_sample - start_sample) / track.channels;
+            let fade_frames = ((crossfade_ms * track.sample_rate as f64 / 1000.0) as usize)
+                .min(start_frame)
+                .min(region_frames);
+
+            // keep the last fade_frames of the region to fade in over the join
+            let audio_data = track.audio_data.edit_from((start_frame - fade_frames) * track.channels);
+            audio_data.drain(start_sample..end_sample - fade_frames * track.channels);
+            effects::crossfade_join(audio_data, track.channels, start_frame, fade_frames);
+            track.audio_data.refresh_mipmap(track.channels);
+        }
+
+        Ok(())
+    }
+
+    /// Keep only a region of audio in specified tracks
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds on the timeline
+    /// * `end_time` - end of region in seconds on the timeline
+    /// * `track_indices` - slice of track indices to crop
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the region is empty, reversed, or not finite
+    ///
+    /// # Notes
+    /// Each track's start offset is advanced by the audio removed from its head so the
+    /// kept audio stays where it was on the timeline. A track outside the region is
+    /// left with no audio.
+    pub fn crop_to_region(&mut self, start_time: f64, end_time: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if !start_time.is_finite() || !end_time.is_finite() || end_time <= start_time
+        {
+            return Err(format!("Invalid region {:.3}-{:.3}", start_time, end_time));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            let (start_sample, end_sample) = Self::region_sample_range(track, start_time, end_time);
+            let audio_data = track.audio_data.to_mut();
+            audio_data.truncate(end_sample);
+            audio_data.drain(..start_sample);
+
+            let shift = (start_sample / track.channels) as f64 / track.sample_rate as f64;
+            track.start_offset += shift;
+            track.gain_envelope = Self::shift_envelope(&track.gain_envelope, shift);
+        });
+        Ok(())
+    }
+
+    /// Overwrite audio in place, starting at a timeline position
+    ///
+    /// # Parameters
+    /// * `position` - timeline position in seconds where the new audio starts
+    /// * `audio_data` - interleaved samples to write, e.g. from `get_region_samples`
+    /// * `sample_rate` - sample rate of `audio_data` in Hz
+    /// * `channels` - channel count of `audio_data`
+    /// * `track_indices` - slice of track indices to overwrite
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the samples are malformed or cannot match a track's channels
+    ///
+    /// # Notes
+    /// Unlike inserting, nothing after the written span moves and track lengths never
+    /// change: audio that would run past the end of a track is dropped. The samples are
+    /// resampled and channel-converted to each track first.
+    pub fn overwrite_region(&mut self, position: f64, audio_data: &[f32], sample_rate: u32, channels: usize, track_indices: &[usize]) -> Result<(), String>
+    {
+        Self::check_sample_layout(audio_data.len(), sample_rate, channels)?;
+
+        let quality = self.resample_quality;
+        for &track_idx in track_indices
+        {
+            let Some(track) = self.tracks.get_mut(track_idx) else
+            {
+                continue;
+            };
+
+            let converted = Self::conform_samples(audio_data, sample_rate, channels, track.sample_rate, track.channels, quality)?;
+            let (start_sample, _) = Self::region_sample_range(track, position, position);
+            let end_sample = (start_sample + converted.len()).min(track.audio_data.len());
+            track.audio_data.range_mut(start_sample, end_sample).copy_from_slice(&converted[..end_sample - start_sample]);
+            track.audio_data.refresh_mipmap(track.channels);
+        }
+
+        Ok(())
+    }
+
+    /// Remove DC offset from specified tracks
+    ///
+    /// # Parameters
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    ///
+    /// # Notes
+    /// Each channel's mean over the whole track is subtracted separately
+    pub fn remove_dc_offset(&mut self, track_indices: &[usize]) -> Result<(), String>
+    {
+        self.apply_to_tracks(track_indices, |track|
+        {
+            effects::remove_dc_offset(&mut track.audio_data, track.channels);
+        });
+        Ok(())
+    }
+
+    /// Invert the polarity of a region in specified tracks
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds on the timeline
+    /// * `end_time` - end of region in seconds on the timeline
+    /// * `track_indices` - slice of track indices to process
+    /// * `channel` - optional single channel to invert (None inverts all channels)
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    ///
+    /// # Notes
+    /// Tracks without the requested channel are left untouched
+    pub fn invert_region(&mut self, start_time: f64, end_time: f64, track_indices: &[usize], channel: Option<usize>) -> Result<(), String>
+    {
+        self.apply_to_tracks(track_indices, |track|
+        {
+            if channel.is_some_and(|ch| ch >= track.channels)
+            {
+                return;
+            }
+            let (start_sample, end_sample) = Self::region_sample_range(track, start_time, end_time);
+            effects::invert(track.audio_data.range_mut(start_sample, end_sample), track.channels, channel);
+        });
+        Ok(())
+    }
+
+    /// Change the length of a region without changing its pitch
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds on the timeline
+    /// * `end_time` - end of region in seconds on the timeline
+    /// * `ratio` - length ratio (> 1 lengthens, < 1 shortens)
+    /// * `track_indices` - slice of track indices to process
+    /// * `window_ms` - optional WSOLA window length in milliseconds (None for default 40)
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if ratio is not positive
+    ///
+    /// # Notes
+    /// The stretched region replaces the original and any following audio is shifted
+    /// to accommodate the new length
+    pub fn time_stretch(&mut self, start_time: f64, end_time: f64, ratio: f64, track_indices: &[usize], window_ms: Option<f64>) -> Result<(), String>
+    {
+        if ratio <= 0.0 || !ratio.is_finite()
+        {
+            return Err(format!("Invalid stretch ratio: {}", ratio));
+        }
+
+        let window_ms = window_ms.unwrap_or(effects::DEFAULT_STRETCH_WINDOW_MS);
+        self.apply_to_tracks(track_indices, |track|
+        {
+            let (start_sample, end_sample) = Self::region_sample_range(track, start_time, end_time);
+            let stretched = effects::time_stretch(
+                &track.audio_data[start_sample..end_sample],
+                track.channels,
+                track.sample_rate,
+                ratio,
+                window_ms,
+            );
+            track.audio_data.edit_from(start_sample).splice(start_sample..end_sample, stretched);
+        });
+        Ok(())
+    }
+
+    /// Shift the pitch of a region without changing its length
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds on the timeline
+    /// * `end_time` - end of region in seconds on the timeline
+    /// * `semitones` - pitch shift in semitones (positive raises pitch)
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if semitones is not finite
+    pub fn pitch_shift(&mut self, start_time: f64, end_time: f64, semitones: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if !semitones.is_finite()
+        {
+            return Err(format!("Invalid pitch shift: {}", semitones));
+        }
+
+        let quality = self.resample_quality;
+        self.apply_to_tracks(track_indices, |track|
+        {
+            let (start_sample, end_sample) = Self::region_sample_range(track, start_time, end_time);
+            let shifted = effects::pitch_shift(
+                &track.audio_data[start_sample..end_sample],
+                track.channels,
+                track.sample_rate,
+                semitones,
+                effects::DEFAULT_STRETCH_WINDOW_MS,
+                quality,
+            );
+            track.audio_data.range_mut(start_sample, end_sample).copy_from_slice(&shifted);
+        });
+        Ok(())
+    }
+
+    /// Change the speed of a region, altering both pitch and length
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds on the timeline
+    /// * `end_time` - end of region in seconds on the timeline
+    /// * `factor` - speed factor (2.0 plays twice as fast and an octave higher)
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if factor is not positive
+    ///
+    /// # Notes
+    /// Following audio is shifted to accommodate the region's new length
+    pub fn change_speed(&mut self, start_time: f64, end_time: f64, factor: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if factor <= 0.0 || !factor.is_finite()
+        {
+            return Err(format!("Invalid speed factor: {}", factor));
+        }
+
+        let quality = self.resample_quality;
+        self.apply_to_tracks(track_indices, |track|
+        {
+            let (start_sample, end_sample) = Self::region_sample_range(track, start_time, end_time);
+            let resampled = effects::change_speed(
+                &track.audio_data[start_sample..end_sample],
+                track.channels,
+                track.sample_rate,
+                factor,
+                quality,
+            );
+            track.audio_data.edit_from(start_sample).splice(start_sample..end_sample, resampled);
+        });
+        Ok(())
+    }
+
+    /// Crossfade the audio either side of a point in time
+    ///
+    /// # Parameters
+    /// * `time` - join point in seconds on the timeline
+    /// * `duration` - crossfade length in seconds
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if duration is negative
+    ///
+    /// # Notes
+    /// The audio before `time` fades out while the audio after it fades in on top
+    /// with equal-power curves, shortening each track by the crossfade length
+    pub fn crossfade_at(&mut self, time: f64, duration: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if duration < 0.0 || !duration.is_finite()
+        {
+            return Err(format!("Invalid crossfade duration: {}", duration));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            let (join_sample, _) = Self::region_sample_range(track, time, time);
+            let fade_frames = (duration * track.sample_rate as f64) as usize;
+            let first_sample = join_sample.saturating_sub(fade_frames * track.channels);
+            effects::crossfade_join(track.audio_data.edit_from(first_sample), track.channels, join_sample / track.channels, fade_frames);
+        });
+        Ok(())
+    }
+
+    /// Apply a low-pass filter to specified tracks
+    ///
+    /// # Parameters
+    /// * `cutoff_hz` - cutoff frequency in Hz
+    /// * `track_indices` - slice of track indices to process
+    /// * `q` - optional filter Q (None for Butterworth 0.707)
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if cutoff is not positive
+    pub fn apply_lowpass(&mut self, cutoff_hz: f64, track_indices: &[usize], q: Option<f64>) -> Result<(), String>
+    {
+        if cutoff_hz <= 0.0 || !cutoff_hz.is_finite()
+        {
+            return Err(format!("Invalid cutoff frequency: {}", cutoff_hz));
+        }
+
+        let q = q.unwrap_or(filters::DEFAULT_Q);
+        self.apply_to_tracks(track_indices, |track|
+        {
+            let coeffs = BiquadCoefficients::lowpass(track.sample_rate, cutoff_hz, q);
+            filters::apply_biquad(&mut track.audio_data, track.channels, &coeffs);
+        });
+        Ok(())
+    }
+
+    /// Apply a high-pass filter to specified tracks
+    ///
+    /// # Parameters
+    /// * `cutoff_hz` - cutoff frequency in Hz
+    /// * `track_indices` - slice of track indices to process
+    /// * `q` - optional filter Q (None for Butterworth 0.707)
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if cutoff is not positive
+    pub fn apply_highpass(&mut self, cutoff_hz: f64, track_indices: &[usize], q: Option<f64>) -> Result<(), String>
+    {
+        if cutoff_hz <= 0.0 || !cutoff_hz.is_finite()
+        {
+            return Err(format!("Invalid cutoff frequency: {}", cutoff_hz));
+        }
+
+        let q = q.unwrap_or(filters::DEFAULT_Q);
+        self.apply_to_tracks(track_indices, |track|
+        {
+            let coeffs = BiquadCoefficients::highpass(track.sample_rate, cutoff_hz, q);
+            filters::apply_biquad(&mut track.audio_data, track.channels, &coeffs);
+        });
+        Ok(())
+    }
+
+    /// Apply a peaking EQ band to specified tracks
+    ///
+    /// # Parameters
+    /// * `freq_hz` - centre frequency in Hz
+    /// * `gain_db` - boost (positive) or cut (negative) in dB
+    /// * `q` - filter Q (bandwidth)
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if frequency or Q is not positive
+    ///
+    /// # Notes
+    /// Call repeatedly with different frequencies to build a multi-band EQ.
+    /// Output is clamped to [-1.0, 1.0].
+    pub fn apply_peaking_eq(&mut self, freq_hz: f64, gain_db: f64, q: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if freq_hz <= 0.0 || !freq_hz.is_finite()
+        {
+            return Err(format!("Invalid frequency: {}", freq_hz));
+        }
+        if q <= 0.0 || !q.is_finite()
+        {
+            return Err(format!("Invalid Q: {}", q));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            let coeffs = BiquadCoefficients::peaking(track.sample_rate, freq_hz, gain_db, q);
+            filters::apply_biquad(&mut track.audio_data, track.channels, &coeffs);
+
+            for sample in track.audio_data.iter_mut()
+            {
+                *sample = sample.clamp(-1.0, 1.0);
+            }
+        });
+        Ok(())
+    }
+
+    /// Apply a graphic EQ to specified tracks
+    ///
+    /// # Parameters
+    /// * `gains_db` - boost or cut in dB for each band of `filters::GRAPHIC_EQ_BANDS_HZ`, lowest first
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the number of gains does not match the bands
+    ///
+    /// # Notes
+    /// Each band is an octave-wide peaking filter, chained in order. Bands at 0 dB and
+    /// bands at or above a track's Nyquist frequency are skipped. Output is clamped to [-1.0, 1.0].
+    pub fn apply_graphic_eq(&mut self, gains_db: &[f64], track_indices: &[usize]) -> Result<(), String>
+    {
+        if gains_db.len() != filters::GRAPHIC_EQ_BANDS_HZ.len()
+        {
+            return Err(format!("Expected {} band gains, got {}", filters::GRAPHIC_EQ_BANDS_HZ.len(), gains_db.len()));
+        }
+        if let Some(gain_db) = gains_db.iter().find(|gain_db| !gain_db.is_finite())
+        {
+            return Err(format!("Invalid band gain: {}", gain_db));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            let nyquist = track.sample_rate as f64 / 2.0;
+            for (&freq_hz, &gain_db) in filters::GRAPHIC_EQ_BANDS_HZ.iter().zip(gains_db)
+            {
+                if gain_db == 0.0 || freq_hz >= nyquist
+                {
+                    continue;
+                }
+                let coeffs = BiquadCoefficients::peaking(track.sample_rate, freq_hz, gain_db, filters::GRAPHIC_EQ_Q);
+                filters::apply_biquad(&mut track.audio_data, track.channels, &coeffs);
+            }
+
+            for sample in track.audio_data.iter_mut()
+            {
+                *sample = sample.clamp(-1.0, 1.0);
+            }
+        });
+        Ok(())
+    }
+
+    /// Remove mains hum from specified tracks
+    ///
+    /// # Parameters
+    /// * `base_hz` - hum frequency in Hz, usually 50 or 60
+    /// * `harmonics` - number of harmonics above `base_hz` to notch as well
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if base frequency is not positive
+    ///
+    /// # Notes
+    /// A narrow notch is applied at `base_hz` and at 2x, 3x, ... up to `harmonics + 1` times it.
+    /// Harmonics at or above a track's Nyquist frequency are skipped.
+    pub fn remove_hum(&mut self, base_hz: f64, harmonics: usize, track_indices: &[usize]) -> Result<(), String>
+    {
+        if base_hz <= 0.0 || !base_hz.is_finite()
+        {
+            return Err(format!("Invalid hum frequency: {}", base_hz));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            let nyquist = track.sample_rate as f64 / 2.0;
+            for multiple in 1..=harmonics + 1
+            {
+                let freq_hz = base_hz * multiple as f64;
+                if freq_hz >= nyquist
+                {
+                    break;
+                }
+                let coeffs = BiquadCoefficients::notch(track.sample_rate, freq_hz, filters::HUM_NOTCH_Q);
+                filters::apply_biquad(&mut track.audio_data, track.channels, &coeffs);
+            }
+        });
+        Ok(())
+    }
+
+    /// Apply dynamic range compression to specified tracks
+    ///
+    /// # Parameters
+    /// * `threshold_db` - level above which gain is reduced, in dBFS
+    /// * `ratio` - compression ratio (e.g. 4.0 for 4:1)
+    /// * `attack_ms` - attack time in milliseconds
+    /// * `release_ms` - release time in milliseconds
+    /// * `makeup_db` - gain applied after compression in dB
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if ratio is below 1 or times are negative
+    pub fn apply_compressor(&mut self, threshold_db: f64, ratio: f64, attack_ms: f64, release_ms: f64,
+                            makeup_db: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if ratio < 1.0 || !ratio.is_finite()
+        {
+            return Err(format!("Invalid compression ratio: {}", ratio));
+        }
+        if attack_ms < 0.0 || release_ms < 0.0
+        {
+            return Err("Attack and release times must not be negative".to_string());
+        }
+
+        let params = effects::CompressorParams
+        {
+            threshold_db,
+            ratio,
+            attack_ms,
+            release_ms,
+            makeup_db,
+        };
+        self.apply_to_tracks(track_indices, |track|
+        {
+            effects::compress(&mut track.audio_data, track.channels, track.sample_rate, &params);
+        });
+        Ok(())
+    }
+
+    /// Reduce sibilance on specified tracks
+    ///
+    /// # Parameters
+    /// * `freq_hz` - centre of the sibilance band in Hz, typically 5-8 kHz
+    /// * `threshold_db` - band level above which it is turned down, in dBFS
+    /// * `ratio` - compression ratio applied to the band (e.g. 4.0 for 4:1)
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the frequency is not positive or the ratio is below 1
+    ///
+    /// # Notes
+    /// Only the band around `freq_hz` is turned down, so lower frequencies pass
+    /// through untouched. Tracks whose Nyquist frequency is below `freq_hz` are skipped.
+    pub fn apply_deesser(&mut self, freq_hz: f64, threshold_db: f64, ratio: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if freq_hz <= 0.0 || !freq_hz.is_finite()
+        {
+            return Err(format!("Invalid de-esser frequency: {}", freq_hz));
+        }
+        if ratio < 1.0 || !ratio.is_finite()
+        {
+            return Err(format!("Invalid compression ratio: {}", ratio));
+        }
+        if !threshold_db.is_finite()
+        {
+            return Err(format!("Invalid threshold: {}", threshold_db));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            if freq_hz < track.sample_rate as f64 / 2.0
+            {
+                effects::deess(&mut track.audio_data, track.channels, track.sample_rate, freq_hz, threshold_db, ratio);
+            }
+        });
+        Ok(())
+    }
+
+    /// Apply slow automatic gain control to specified tracks
+    ///
+    /// # Parameters
+    /// * `target_db` - RMS level to keep the signal near, in dBFS
+    /// * `max_gain_db` - largest boost applied, in dB
+    /// * `window_ms` - length of the RMS window in milliseconds
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the target is above 0 dBFS, the gain cap is negative, or the window is not positive
+    ///
+    /// # Notes
+    /// Meant for levelling speech: the gain follows a long, centred RMS window
+    /// instead of a compressor's envelope, so it rides the level without pumping.
+    /// Passages quieter than -60 dBFS keep the gain of the speech around them.
+    pub fn apply_agc(&mut self, target_db: f64, max_gain_db: f64, window_ms: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if target_db > 0.0 || !target_db.is_finite()
+        {
+            return Err(format!("Invalid target level: {}", target_db));
+        }
+        if max_gain_db < 0.0 || !max_gain_db.is_finite()
+        {
+            return Err(format!("Invalid maximum gain: {}", max_gain_db));
+        }
+        if window_ms <= 0.0 || !window_ms.is_finite()
+        {
+            return Err(format!("Invalid window length: {}", window_ms));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            effects::auto_gain(&mut track.audio_data, track.channels, track.sample_rate, target_db, max_gain_db, window_ms);
+        });
+        Ok(())
+    }
+
+    /// Normalize the mix so its peak within a region hits a target level
+    ///
+    /// # Parameters
+    /// * `target_db` - peak level for the mix in dBFS, at most 0
+    /// * `start_time` - optional start of the region to measure in seconds (defaults to 0)
+    /// * `end_time` - optional end of the region to measure in seconds (defaults to the project end)
+    ///
+    /// # Returns
+    /// `Result<f64, String>` - Ok with the gain applied in dB, Err if the target or region is invalid or the mix is silent
+    ///
+    /// # Notes
+    /// The region is mixed the way playback and export mix it, but before the
+    /// output is clamped, so a mix that clips is measured at its true peak. One
+    /// gain is then applied to the whole of every track heard in the region,
+    /// keeping their balance intact.
+    pub fn normalize_mix(&mut self, target_db: f64, start_time: Option<f64>, end_time: Option<f64>) -> Result<f64, String>
+    {
+        if target_db > 0.0 || !target_db.is_finite()
+        {
+            return Err(format!("Invalid target level: {}", target_db));
+        }
+
+        let start = start_time.unwrap_or(0.0);
+        let end = end_time.unwrap_or_else(|| self.get_duration());
+        if !start.is_finite() || !end.is_finite() || end <= start
+        {
+            return Err(format!("Invalid normalize region: {:.3}s to {:.3}s", start, end));
+        }
+
+        let contributing: Vec<usize> = self.tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, track)|
+            {
+                let track_duration = (track.audio_data.len() / track.channels) as f64 / track.sample_rate as f64;
+                !track.audio_data.is_empty() && track.start_offset < end && track.start_offset + track_duration > start
+            })
+            .map(|(i, _)| i)
+            .collect();
+        if contributing.is_empty()
+        {
+            return Err("No audio in the selected region".to_string());
+        }
+
+        let sample_rate = self.tracks[0].sample_rate;
+        let output_channels = if self.tracks.iter().any(|t| t.channels >= 2) { 2 } else { 1 };
+        let total_frames = ((end * sample_rate as f64) as usize).saturating_sub((start * sample_rate as f64) as usize);
+        let mut mixed = vec![0.0f32; total_frames * output_channels];
+        for &i in &contributing
+        {
+            Self::mix_track_into(&mut mixed, output_channels, &self.tracks[i], start, sample_rate, MonoFold::default());
+        }
+
+        let peak = mixed.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        if peak <= 0.0
+        {
+            return Err("Mix is silent in the selected region".to_string());
+        }
+
+        let gain = (effects::db_to_gain(target_db) / peak as f64) as f32;
+        for &i in &contributing
+        {
+            for sample in self.tracks[i].audio_data.iter_mut()
+            {
+                *sample *= gain;
+            }
+        }
+        Ok(effects::gain_to_db(gain as f64))
+    }
+
+    /// Apply a brickwall limiter to specified tracks
+    ///
+    /// # Parameters
+    /// * `ceiling_db` - maximum output level in dBFS
+    /// * `release_ms` - gain recovery time in milliseconds
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if release time is negative
+    ///
+    /// # Notes
+    /// Uses a 5 ms lookahead so no output sample exceeds the ceiling
+    pub fn apply_limiter(&mut self, ceiling_db: f64, release_ms: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if release_ms < 0.0 || !release_ms.is_finite()
+        {
+            return Err(format!("Invalid release time: {}", release_ms));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            effects::limit(&mut track.audio_data, track.channels, track.sample_rate, ceiling_db, release_ms);
+        });
+        Ok(())
+    }
+
+    /// Apply reverb to specified tracks
+    ///
+    /// # Parameters
+    /// * `room_size` - room size 0.0-1.0
+    /// * `damping` - high frequency damping 0.0-1.0
+    /// * `wet` - wet/dry balance 0.0-1.0
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    ///
+    /// # Notes
+    /// Parameters outside 0.0-1.0 are clamped
+    pub fn apply_reverb(&mut self, room_size: f64, damping: f64, wet: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        self.apply_to_tracks(track_indices, |track|
+        {
+            effects::reverb(&mut track.audio_data, track.channels, track.sample_rate, room_size, damping, wet);
+        });
+        Ok(())
+    }
+
+    /// Apply a bitcrusher to specified tracks
+    ///
+    /// # Parameters
+    /// * `bits` - effective bit depth 1-24
+    /// * `downsample_factor` - number of frames each held sample lasts (1 for no rate reduction)
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if bits or downsample factor is out of range
+    ///
+    /// # Notes
+    /// Rate reduction is sample-and-hold without an anti-aliasing filter, so it aliases on purpose
+    pub fn apply_bitcrush(&mut self, bits: u32, downsample_factor: usize, track_indices: &[usize]) -> Result<(), String>
+    {
+        if !(1..=24).contains(&bits)
+        {
+            return Err(format!("Invalid bit depth: {} (expected 1-24)", bits));
+        }
+        if downsample_factor == 0
+        {
+            return Err("Downsample factor must be at least 1".to_string());
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            effects::bitcrush(&mut track.audio_data, track.channels, bits, downsample_factor);
+        });
+        Ok(())
+    }
+
+    /// Adjust the stereo width of specified tracks
+    ///
+    /// # Parameters
+    /// * `width` - side level relative to the original (0.0 = mono, 1.0 = unchanged, > 1.0 = wider)
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if width is negative
+    ///
+    /// # Notes
+    /// Only stereo tracks are changed; other tracks are left untouched
+    pub fn apply_stereo_width(&mut self, width: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if width < 0.0 || !width.is_finite()
+        {
+            return Err(format!("Invalid stereo width: {}", width));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            if track.channels == 2
+            {
+                effects::stereo_width(&mut track.audio_data, width);
+            }
+        });
+        Ok(())
+    }
+
+    /// Apply tanh saturation to specified tracks
+    ///
+    /// # Parameters
+    /// * `drive` - input gain into the waveshaper (> 0, higher is more distorted)
+    /// * `mix` - wet/dry balance 0.0-1.0
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if drive is not positive
+    ///
+    /// # Notes
+    /// Mix outside 0.0-1.0 is clamped
+    pub fn apply_saturation(&mut self, drive: f64, mix: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if drive <= 0.0 || !drive.is_finite()
+        {
+            return Err(format!("Invalid drive: {}", drive));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            effects::saturate(&mut track.audio_data, drive, mix);
+        });
+        Ok(())
+    }
+
+    /// Apply tremolo to specified tracks
+    ///
+    /// # Parameters
+    /// * `rate_hz` - modulation rate in Hz
+    /// * `depth` - modulation depth 0.0-1.0 (1.0 dips fully to silence)
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if rate is not positive
+    ///
+    /// # Notes
+    /// Depth outside 0.0-1.0 is clamped
+    pub fn apply_tremolo(&mut self, rate_hz: f64, depth: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if rate_hz <= 0.0 || !rate_hz.is_finite()
+        {
+            return Err(format!("Invalid tremolo rate: {}", rate_hz));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            effects::tremolo(&mut track.audio_data, track.channels, track.sample_rate, rate_hz, depth);
+        });
+        Ok(())
+    }
+
+    /// Apply vibrato to specified tracks
+    ///
+    /// # Parameters
+    /// * `rate_hz` - modulation rate in Hz
+    /// * `depth_ms` - peak delay deviation in milliseconds
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if rate is not positive or depth is negative
+    pub fn apply_vibrato(&mut self, rate_hz: f64, depth_ms: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if rate_hz <= 0.0 || !rate_hz.is_finite()
+        {
+            return Err(format!("Invalid vibrato rate: {}", rate_hz));
+        }
+        if depth_ms < 0.0 || !depth_ms.is_finite()
+        {
+            return Err(format!("Invalid vibrato depth: {}", depth_ms));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            effects::vibrato(&mut track.audio_data, track.channels, track.sample_rate, rate_hz, depth_ms);
+        });
+        Ok(())
+    }
+
+    /// Apply chorus to specified tracks
+    ///
+    /// # Parameters
+    /// * `voices` - number of modulated delay lines 1-8
+    /// * `rate_hz` - modulation rate in Hz
+    /// * `depth_ms` - peak delay deviation of each voice in milliseconds
+    /// * `mix` - wet/dry balance 0.0-1.0
+    /// * `track_indices` - slice of track indices to process
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if voices, rate, or depth is out of range
+    ///
+    /// # Notes
+    /// Mix outside 0.0-1.0 is clamped
+    pub fn apply_chorus(&mut self, voices: usize, rate_hz: f64, depth_ms: f64, mix: f64, track_indices: &[usize]) -> Result<(), String>
+    {
+        if !(1..=effects::CHORUS_MAX_VOICES).contains(&voices)
+        {
+            return Err(format!("Invalid voice count: {} (expected 1-{})", voices, effects::CHORUS_MAX_VOICES));
+        }
+        if rate_hz <= 0.0 || !rate_hz.is_finite()
+        {
+            return Err(format!("Invalid chorus rate: {}", rate_hz));
+        }
+        if !(0.0..=effects::CHORUS_BASE_DELAY_MS).contains(&depth_ms)
+        {
+            return Err(format!("Invalid chorus depth: {} (expected 0-{} ms)", depth_ms, effects::CHORUS_BASE_DELAY_MS));
+        }
+
+        self.apply_to_tracks(track_indices, |track|
+        {
+            effects::chorus(&mut track.audio_data, track.channels, track.sample_rate, voices, rate_hz, depth_ms, mix);
+        });
+        Ok(())
+    }
+
+    /// Remove clicks and pops from specified tracks
+    ///
+    /// # Parameters
+    /// * `track_indices` - slice of track indices to process
+    /// * `sensitivity` - detection sensitivity 0.0-1.0 (higher catches smaller clicks)
+    ///
+    /// # Returns
+    /// `Result<usize, String>` - Ok with the number of clicks repaired across all tracks,
+    /// Err if sensitivity is outside 0.0-1.0
+    pub fn remove_clicks(&mut self, track_indices: &[usize], sensitivity: f64) -> Result<usize, String>
+    {
+        if !(0.0..=1.0).contains(&sensitivity)
+        {
+            return Err(format!("Invalid sensitivity: {} (expected 0.0-1.0)", sensitivity));
+        }
+
+        let mut repaired = 0;
+        self.apply_to_tracks(track_indices, |track|
+        {
+            repaired += effects::remove_clicks(&mut track.audio_data, track.channels, track.sample_rate, sensitivity);
+        });
+        Ok(repaired)
+    }
+
+    /// Learn the background noise spectrum from a region of a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to learn from
+    /// * `start_time` - start of a noise-only region in seconds on the timeline
+    /// * `end_time` - end of the region in seconds on the timeline
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if track index invalid or the region is too short
+    ///
+    /// # Notes
+    /// The profile replaces any previous one and is used by `apply_noise_reduction`
+    pub fn learn_noise_profile(&mut self, track_index: usize, start_time: f64, end_time: f64) -> Result<(), String>
+    {
+        let track = self.tracks
+            .get(track_index)
+            .ok_or_else(|| format!("Invalid track index: {}", track_index))?;
+
+        let (start, end) = Self::region_sample_range(track, start_time, end_time);
+        self.noise_profile = Some(NoiseProfile::learn(&track.audio_data[start..end], track.channels, track.sample_rate)?);
+        Ok(())
+    }
+
+    /// Reduce steady background noise in specified tracks using the learned profile
+    ///
+    /// # Parameters
+    /// * `track_indices` - slice of track indices to process
+    /// * `amount` - fraction of the noise estimate to subtract, 0.0-1.0
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if no profile has been learned, amount is out of range,
+    /// or a track's sample rate differs from the profile's
+    pub fn apply_noise_reduction(&mut self, track_indices: &[usize], amount: f64) -> Result<(), String>
+    {
+        let profile = self.noise_profile
+            .as_ref()
+            .ok_or("No noise profile has been learned")?;
+
+        if !(0.0..=1.0).contains(&amount)
+        {
+            return Err(format!("Invalid amount: {} (expected 0.0-1.0)", amount));
+        }
+        if let Some(track) = track_indices
+            .iter()
+            .filter_map(|&idx| self.tracks.get(idx))
+            .find(|track| track.sample_rate != profile.sample_rate())
+        {
+            return Err(format!("Noise profile was learned at {} Hz but track is {} Hz", profile.sample_rate(), track.sample_rate));
+        }
+
+        for &track_idx in track_indices
+        {
+            if let Some(track) = self.tracks.get_mut(track_idx)
+            {
+                profile.reduce(&mut track.audio_data, track.channels, amount);
+            }
+        }
+        Ok(())
+    }
+
+    /// Run an operation on each of the specified tracks
+    ///
+    /// # Parameters
+    /// * `track_indices` - slice of track indices to process
+    /// * `operation` - closure applied to each track
+    ///
+    /// # Notes
+    /// Invalid indices are skipped. Each track's waveform mipmap is brought up to
+    /// date afterwards, so redraws after an edit stay fast.
+    fn apply_to_tracks<F>(&mut self, track_indices: &[usize], mut operation: F)
+    where
+        F: FnMut(&mut AudioTrack),
+    {
+        for &track_idx in track_indices
+        {
+            if let Some(track) = self.tracks.get_mut(track_idx)
+            {
+                operation(track);
+                track.audio_data.refresh_mipmap(track.channels);
+            }
+        }
+    }
+
+    /// Mix audio for export
+    ///
+    /// # Parameters
+    /// * `start_time` - optional start time in seconds (None for beginning)
+    /// * `end_time` - optional end time in seconds (None for end)
+    /// * `channel_mode` - optional channel mode ('stereo', 'mono', 'split', 'mono_to_stereo')
+    /// * `mono_fold` - stereo weights and gain for the mono mode
+    /// * `bus_limiter` - optional limiter for the summed mix (None to clamp to -1.0..1.0)
+    ///
+    /// # Returns
+    /// `Vec<(Vec<f32>, u32, usize, String)>` - list of (audio data, sample rate, channels, file suffix)
+    ///
+    /// # Notes
+    /// Mixing is separated from `write_export` so encoding can run without holding the engine.
+    /// All tracks are mixed together unless a channel mode other than auto is given.
+    pub fn render_export(&self, start_time: Option<f64>, end_time: Option<f64>, channel_mode: Option<&str>,
+                         mono_fold: MonoFold, bus_limiter: Option<BusLimiter>) -> Vec<(Vec<f32>, u32, usize, String)>
+    {
+        let duration = self.get_duration();
+        let start = start_time.unwrap_or(0.0);
+        let end = end_time.unwrap_or(duration);
+
+        let mode = channel_mode.unwrap_or("auto");
+        let mut items = if mode == "auto"
+        {
+            let (data, rate, channels) = Self::sum_tracks(&self.tracks, start, end);
+            vec![(data, rate, channels, String::new())]
+        }
+        else
+        {
+            self.mix_tracks_for_export(start, end, mode, mono_fold)
+        };
+
+        for (data, rate, channels, _) in &mut items
+        {
+            Self::finish_bus(data, *rate, *channels, bus_limiter);
+        }
+        items
+    }
+
+    /// Bring a summed export mix within full scale
+    ///
+    /// # Parameters
+    /// * `data` - interleaved samples to process in place
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of interleaved channels
+    /// * `bus_limiter` - optional limiter settings (None to hard clamp)
+    ///
+    /// # Notes
+    /// Clamping clips anything over full scale, adding harsh distortion to loud
+    /// mixes. The limiter instead lowers the gain ahead of each peak.
+    fn finish_bus(data: &mut [f32], sample_rate: u32, channels: usize, bus_limiter: Option<BusLimiter>)
+    {
+        if let Some(limiter) = bus_limiter
+        {
+            effects::limit(data, channels, sample_rate, limiter.ceiling_db, limiter.release_ms);
+        }
+
+        for sample in data.iter_mut()
+        {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+    }
+
+    /// Mix a region for export as a seamless loop
+    ///
+    /// # Parameters
+    /// * `start_time` - start of the loop in seconds
+    /// * `end_time` - end of the loop in seconds
+    /// * `crossfade_ms` - length of the crossfade from the end back into the start, in milliseconds
+    ///
+    /// # Returns
+    /// `Result<Vec<ExportItem>, String>` - `render_export` items, Err if the region or crossfade is invalid
+    ///
+    /// # Notes
+    /// The region's last `crossfade_ms` are blended over its first, so the result is that
+    /// much shorter and repeats without a click. The crossfade is capped at half the region.
+    /// MP3 and Ogg Vorbis add encoder padding, so only WAV and FLAC loop sample-accurately.
+    pub fn render_loop(&self, start_time: f64, end_time: f64, crossfade_ms: f64) -> Result<Vec<ExportItem>, String>
+    {
+        if !start_time.is_finite() || !end_time.is_finite() || start_time < 0.0 || end_time <= start_time
+        {
+            return Err(format!("Invalid region {:.3}-{:.3}", start_time, end_time));
+        }
+        if crossfade_ms < 0.0 || !crossfade_ms.is_finite()
+        {
+            return Err(format!("Invalid crossfade length: {}", crossfade_ms));
+        }
+
+        let mut items = self.render_export(Some(start_time), Some(end_time), None, MonoFold::default(), None);
+        for (data, sample_rate, channels, _) in &mut items
+        {
+            let fade_frames = (crossfade_ms * *sample_rate as f64 / 1000.0).round() as usize;
+            effects::make_loop(data, *channels, fade_frames);
+        }
+        Ok(items)
+    }
+
+    /// Encode and write mixed audio to files
+    ///
+    /// # Parameters
+    /// * `path` - output file path with extension (.wav, .flac, .mp3, or .ogg)
+    /// * `export_items` - mixed audio from `render_export`
+    /// * `options` - encoder settings and tags
+    /// * `progress` - optional callback receiving the fraction complete, 0.0 to 1.0
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    ///
+    /// # Notes
+    /// Format is determined by file extension. Split mode creates multiple files with
+    /// _L and _R suffixes. Progress increases monotonically across all files and
+    /// finishes at exactly 1.0.
+    pub fn write_export(path: &str, export_items: Vec<(Vec<f32>, u32, usize, String)>, options: &ExportOptions,
+                        progress: Option<&mut dyn FnMut(f64)>) -> Result<(), String>
+    {
+        let mut no_progress = |_: f64| {};
+        let progress: &mut dyn FnMut(f64) = match progress
+        {
+            Some(progress) => progress,
+            None => &mut no_progress,
+        };
+
+        let path_lower = path.to_lowercase();
+        let (base_path, extension) = if let Some(pos) = path.rfind('.')
+        {
+            (&path[..pos], &path[pos..])
+        }
+        else
+        {
+            (path, "")
+        };
+
+        let item_count = export_items.len().max(1) as f64;
+        for (item_index, (export_data, sample_rate, channels, suffix)) in export_items.into_iter().enumerate()
+        {
+            let final_path = if suffix.is_empty()
+            {
+                path.to_string()
+            }
+            else
+            {
+                format!("{}{}{}", base_path, suffix, extension)
+            };
+
+            // scale each file's progress into its share of the whole export
+            let mut item_progress = |fraction: f64| progress((item_index as f64 + fraction.min(1.0)) / item_count);
+
+            if path_lower.ends_with(".wav")
+            {
+                Self::export_wav(&final_path, &export_data, sample_rate, channels, options.bit_depth.unwrap_or(16), &mut item_progress)?;
+            }
+            else if path_lower.ends_with(".flac")
+            {
+                Self::export_flac(&final_path, &export_data, sample_rate, channels, options, &mut item_progress)?;
+            }
+            else if path_lower.ends_with(".mp3")
+            {
+                Self::export_mp3(&final_path, &export_data, sample_rate, channels, options.bitrate_kbps.unwrap_or(192),
+                                 &options.tags, &mut item_progress)?;
+            }
+            else if path_lower.ends_with(".ogg")
+            {
+                Self::export_ogg(&final_path, &export_data, sample_rate, channels, options.quality.unwrap_or(0.5), &mut item_progress)?;
+            }
+            else
+            {
+                return Err("Unsupported format. Use .wav, .flac, .mp3, or .ogg".to_string());
+            }
+        }
+
+        progress(1.0);
+        Ok(())
+    }
+
+    /// Mix several regions for batch export
+    ///
+    /// # Parameters
+    /// * `regions` - list of (start_time, end_time, output path) in seconds
+    /// * `channel_mode` - optional channel mode ('stereo', 'mono', 'split', 'mono_to_stereo')
+    /// * `mono_fold` - stereo weights and gain for the mono mode
+    /// * `bus_limiter` - optional limiter for the summed mix (None to clamp to -1.0..1.0)
+    ///
+    /// # Returns
+    /// `Result<Vec<RenderedRegion>, String>` - each output path with its `render_export` result
+    ///
+    /// # Errors
+    /// Returns error if any region is empty, reversed, or not finite, before anything is mixed
+    pub fn render_regions(&self, regions: &[(f64, f64, String)], channel_mode: Option<&str>, mono_fold: MonoFold,
+                          bus_limiter: Option<BusLimiter>) -> Result<Vec<RenderedRegion>, String>
+    {
+        for (start, end, path) in regions
+        {
+            if !start.is_finite() || !end.is_finite() || *start < 0.0 || end <= start
+            {
+                return Err(format!("Invalid region {:.3}-{:.3} for {}", start, end, path));
+            }
+        }
+
+        Ok(regions
+            .iter()
+            .map(|(start, end, path)| (path.clone(), self.render_export(Some(*start), Some(*end), channel_mode, mono_fold, bus_limiter)))
+            .collect())
+    }
+
+    /// Encode and write regions mixed by `render_regions`
+    ///
+    /// # Parameters
+    /// * `regions` - each output path with its mixed audio
+    /// * `options` - encoder settings and tags shared by every file
+    /// * `progress` - optional callback receiving the fraction complete, 0.0 to 1.0
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if every region was written
+    ///
+    /// # Notes
+    /// Each path's extension selects its format, so regions may use different formats.
+    /// Stops at the first region that fails; earlier files are left in place.
+    pub fn write_regions(regions: Vec<RenderedRegion>, options: &ExportOptions,
+                         progress: Option<&mut dyn FnMut(f64)>) -> Result<(), String>
+    {
+        let mut no_progress = |_: f64| {};
+        let progress: &mut dyn FnMut(f64) = match progress
+        {
+            Some(progress) => progress,
+            None => &mut no_progress,
+        };
+
+        let region_count = regions.len().max(1) as f64;
+        for (region_index, (path, export_items)) in regions.into_iter().enumerate()
+        {
+            let mut region_progress = |fraction: f64| progress((region_index as f64 + fraction) / region_count);
+            Self::write_export(&path, export_items, options, Some(&mut region_progress))
+                .map_err(|e| format!("{}: {}", path, e))?;
+        }
+
+        progress(1.0);
+        Ok(())
+    }
+
+    /// Mix each track on its own for stem export
+    ///
+    /// # Parameters
+    /// * `apply_envelope` - whether each track's gain envelope is applied
+    ///
+    /// # Returns
+    /// `Vec<(Vec<f32>, u32, usize, String)>` - list of (audio data, sample rate, channels, track name)
+    ///
+    /// # Notes
+    /// Every stem spans the whole project from time zero, so track offsets are kept
+    /// as leading silence and the stems line up when imported elsewhere.
+    pub fn render_stems(&self, apply_envelope: bool) -> Vec<(Vec<f32>, u32, usize, String)>
+    {
+        let duration = self.get_duration();
+        self.tracks.iter().map(|track|
+        {
+            let (data, rate, channels) = if apply_envelope || track.gain_envelope.is_empty()
+            {
+                Self::mix_tracks(std::slice::from_ref(track), 0.0, duration)
+            }
+            else
+            {
+                let mut flat = track.clone();
+                flat.gain_envelope.clear();
+                Self::mix_tracks(std::slice::from_ref(&flat), 0.0, duration)
+            };
+            (data, rate, channels, track.name.clone())
+        }).collect()
+    }
+
+    /// Encode and write stems to a directory
+    ///
+    /// # Parameters
+    /// * `dir` - output directory (created if missing)
+    /// * `format` - file extension of the output format, e.g. 'wav'
+    /// * `stems` - per-track audio from `render_stems`
+    /// * `options` - encoder settings and tags
+    /// * `progress` - optional callback receiving the fraction complete, 0.0 to 1.0
+    ///
+    /// # Returns
+    /// `Result<Vec<String>, String>` - Ok with the paths written, in track order
+    ///
+    /// # Notes
+    /// Files are named after their tracks, with characters that are not valid in
+    /// file names replaced and a numeric suffix added to repeated names.
+    pub fn write_stems(dir: &str, format: &str, stems: Vec<(Vec<f32>, u32, usize, String)>, options: &ExportOptions,
+                       progress: Option<&mut dyn FnMut(f64)>) -> Result<Vec<String>, String>
+    {
+        let mut no_progress = |_: f64| {};
+        let progress: &mut dyn FnMut(f64) = match progress
+        {
+            Some(progress) => progress,
+            None => &mut no_progress,
+        };
+
+        let format = format.trim_start_matches('.').to_lowercase();
+        if !EXPORT_FORMATS.contains(&format.as_str())
+        {
+            return Err(format!("Unsupported format: {}", format));
+        }
+
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+        let stem_count = stems.len().max(1) as f64;
+        let mut used_names: Vec<String> = Vec::new();
+        let mut paths = Vec::new();
+        for (stem_index, (data, sample_rate, channels, name)) in stems.into_iter().enumerate()
+        {
+            // drop the extension of tracks named after the file they were loaded from
+            let stem_name = match name.rsplit_once('.')
+            {
+                Some((stem, ext)) if IMPORT_FORMATS.contains(&ext.to_lowercase().as_str()) => stem,
+                _ => name.as_str(),
+            };
+            let base_name: String = stem_name
+                .chars()
+                .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+                .collect();
+            let base_name = if base_name.trim().is_empty() { format!("Track {}", stem_index + 1) } else { base_name };
+
+            let mut file_name = base_name.clone();
+            let mut copy = 2;
+            while used_names.contains(&file_name.to_lowercase())
+            {
+                file_name = format!("{}_{}", base_name, copy);
+                copy += 1;
+            }
+            used_names.push(file_name.to_lowercase());
+
+            let path = Path::new(dir).join(format!("{}.{}", file_name, format));
+            let path = path.to_str().ok_or("Invalid output path")?.to_string();
+
+            let mut stem_progress = |fraction: f64| progress((stem_index as f64 + fraction) / stem_count);
+            Self::write_export(&path, vec![(data, sample_rate, channels, String::new())], options, Some(&mut stem_progress))?;
+            paths.push(path);
+        }
+
+        progress(1.0);
+        Ok(paths)
+    }
+
+    /// Export the full mix as a headerless PCM file
+    ///
+    /// # Parameters
+    /// * `path` - output file path
+    /// * `sample_format` - sample encoding ('s16', 's24', 's32', or 'f32')
+    /// * `endianness` - byte order ('le' or 'be')
+    ///
+    /// # Returns
+    /// `Result<(u32, usize), String>` - Ok with (sample_rate, channels) of the written audio
+    ///
+    /// # Notes
+    /// The file has no header, so the returned layout is needed to read it back
+    pub fn export_raw(&self, path: &str, sample_format: &str, endianness: &str) -> Result<(u32, usize), String>
+    {
+        let format = RawFormat::parse(sample_format, endianness)?;
+        if self.tracks.is_empty()
+        {
+            return Err("No tracks to export".to_string());
+        }
+
+        let (data, sample_rate, channels) = self.mix_tracks_for_playback(0.0, self.get_duration());
+
+        let mut file = File::create(path)
+            .map_err(|e| format!("Failed to create raw file: {}", e))?;
+        file.write_all(&pcm::encode(&data, format))
+            .map_err(|e| format!("Failed to write raw file: {}", e))?;
+
+        Ok((sample_rate, channels))
+    }
+
+    /// Export audio as WAV file
+    ///
+    /// # Parameters
+    /// * `path` - output file path
+    /// * `data` - audio sample data
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels
+    /// * `bit_depth` - 16 or 24 for integer samples, 32 for float samples
+    /// * `progress` - callback receiving the fraction of samples written
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the bit depth is unsupported
+    ///
+    /// # Notes
+    /// Integer output clamps to -1.0..1.0; float output stores samples unchanged
+    fn export_wav(path: &str, data: &[f32], sample_rate: u32, channels: usize, bit_depth: u16,
+                  progress: &mut dyn FnMut(f64)) -> Result<(), String>
+    {
+        let sample_format = match bit_depth
+        {
+            16 | 24 => hound::SampleFormat::Int,
+            32 => hound::SampleFormat::Float,
+            _ => return Err(format!("Unsupported WAV bit depth: {} (expected 16, 24, or 32)", bit_depth)),
+        };
+
+        let spec = hound::WavSpec
+        {
+            channels: channels as u16,
+            sample_rate,
+            bits_per_sample: bit_depth,
+            sample_format,
+        };
+
+        let mut writer = hound::WavWriter::create(path, spec)
+            .map_err(|e| format!("Failed to create WAV file: {}", e))?;
+
+        for (chunk_idx, chunk) in data.chunks(EXPORT_PROGRESS_CHUNK).enumerate()
+        {
+            for &sample in chunk
+            {
+                let written = match bit_depth
+                {
+                    16 => writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16),
+                    24 => writer.write_sample((sample.clamp(-1.0, 1.0) as f64 * 8_388_607.0) as i32),
+                    _ => writer.write_sample(sample),
+                };
+                written.map_err(|e| format!("Failed to write sample: {}", e))?;
+            }
+            progress(((chunk_idx + 1) * EXPORT_PROGRESS_CHUNK).min(data.len()) as f64 / data.len() as f64);
+        }
+
+        writer.finalize()
+              .map_err(|e| format!("Failed to finalize WAV: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Export audio as FLAC file
+    ///
+    /// # Parameters
+    /// * `path` - output file path
+    /// * `data` - audio sample data
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels
+    /// * `options` - bit depth (default 16) and dither, compression level (default 5), block size,
+    ///   cue sheet, padding (default 4 KiB), and tags written as a Vorbis comment block
+    /// * `progress` - callback receiving the fraction of frames encoded
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the bit depth is unsupported
+    ///
+    /// # Notes
+    /// At 24 bits samples keep 256 times finer steps than at 16, so quiet detail and
+    /// high-resolution mixes survive the conversion from f32
+    fn export_flac(path: &str, data: &[f32], sample_rate: u32, channels: usize, options: &ExportOptions,
+                   progress: &mut dyn FnMut(f64)) -> Result<(), String>
+    {
+        use std::path::Path;
+
+        let bit_depth = options.bit_depth.unwrap_or(16);
+        if bit_depth != 16 && bit_depth != 24
+        {
+            return Err(format!("Unsupported FLAC bit depth: {} (expected 16 or 24)", bit_depth));
+        }
+
+        let comments = options.tags.vorbis_comments();
+        let flac_options = FlacOptions
+        {
+            bits_per_sample: bit_depth as u8,
+            dither: options.flac_dither,
+            compression_level: options.compression_level.unwrap_or(5),
+            block_size: options.flac_block_size,
+            comments: &comments,
+            cue_sheet: options.cue_sheet.as_ref(),
+            padding_bytes: options.flac_padding.unwrap_or(DEFAULT_FLAC_PADDING_BYTES),
+        };
+        crate::flac::export_to_flac_with_level(Path::new(path), data, sample_rate, channels as u16, &flac_options, progress)
+            .map_err(|e| format!("Failed to export FLAC: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Export audio as MP3 file
+    ///
+    /// # Parameters
+    /// * `path` - output file path
+    /// * `data` - audio sample data
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels
+    /// * `bitrate_kbps` - bitrate in kbps (128, 160, 192, 256, or 320)
+    /// * `tags` - tags written as an ID3v2 header (omitted when no tags are set)
+    /// * `progress` - callback receiving the fraction of samples encoded
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    fn export_mp3(path: &str, data: &[f32], sample_rate: u32, channels: usize, bitrate_kbps: u32,
+                  tags: &ExportTags, progress: &mut dyn FnMut(f64)) -> Result<(), String>
+    {
+        use id3::TagLike;
+        use mp3lame_encoder::{Builder, InterleavedPcm, FlushNoGap, Bitrate};
+        use std::mem::MaybeUninit;
+
+        // convert to i16 samples
+        let mut samples_i16 = Vec::with_capacity(data.len());
+        for &sample in data
+        {
+            let sample_i16 = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            samples_i16.push(sample_i16);
+        }
+
+        let mut mp3_encoder = Builder::new()
+            .ok_or("Failed to create MP3 encoder")?;
+
+        mp3_encoder.set_sample_rate(sample_rate)
+                   .map_err(|e| format!("Failed to set sample rate: {:?}", e))?;
+
+        mp3_encoder.set_num_channels(channels as u8)
+                   .map_err(|e| format!("Failed to set channels: {:?}", e))?;
+
+        let bitrate = match bitrate_kbps
+        {
+            128 => Bitrate::Kbps128,
+            160 => Bitrate::Kbps160,
+            192 => Bitrate::Kbps192,
+            256 => Bitrate::Kbps256,
+            320 => Bitrate::Kbps320,
+            _ => Bitrate::Kbps192,
+        };
+
+        mp3_encoder.set_brate(bitrate)
+                   .map_err(|e| format!("Failed to set bitrate: {:?}", e))?;
+
+        mp3_encoder.set_quality(mp3lame_encoder::Quality::Good)
+                   .map_err(|e| format!("Failed to set quality: {:?}", e))?;
+
+        let mut mp3_encoder = mp3_encoder.build()
+                                         .map_err(|e| format!("Failed to build encoder: {:?}", e))?;
+
+        let mut mp3_out = Vec::new();
+
+        // the ID3v2 header must precede the first MPEG frame
+        let mut id3_tag = id3::Tag::new();
+        if let Some(ref title) = tags.title
+        {
+            id3_tag.set_title(title.as_str());
+        }
+        if let Some(ref artist) = tags.artist
+        {
+            id3_tag.set_artist(artist.as_str());
+        }
+        if let Some(ref album) = tags.album
+        {
+            id3_tag.set_album(album.as_str());
+        }
+        if let Some(year) = tags.year
+        {
+            id3_tag.set_year(year);
+        }
+        if id3_tag.frames().next().is_some()
+        {
+            id3_tag.write_to(&mut mp3_out, id3::Version::Id3v24)
+                   .map_err(|e| format!("Failed to write ID3 tag: {}", e))?;
+        }
+
+        // encode in whole-frame chunks so progress can be reported
+        let chunk_len = (EXPORT_PROGRESS_CHUNK / channels.max(1)).max(1) * channels.max(1);
+
+        // calculate proper buffer size: 1.25 * num_samples + 7200
+        let buffer_size = (chunk_len * 5 / 4 + 7200).max(16384);
+        let mut output: Vec<MaybeUninit<u8>> = vec![MaybeUninit::uninit(); buffer_size];
+
+        let mut encoded_samples = 0;
+        for chunk in samples_i16.chunks(chunk_len)
+        {
+            let input = InterleavedPcm(chunk);
+            let encoded_size = mp3_encoder.encode(input, &mut output[..])
+                                          .map_err(|e| format!("Failed to encode MP3: {:?}", e))?;
+
+            // safely convert MaybeUninit to initialized bytes
+            mp3_out.extend(output[..encoded_size].iter().map(|byte| unsafe { byte.assume_init() }));
+
+            encoded_samples += chunk.len();
+            progress(encoded_samples as f64 / samples_i16.len() as f64);
+        }
+
+        let _flushed_size = mp3_encoder.flush_to_vec::<FlushNoGap>(&mut mp3_out)
+                                       .map_err(|e| format!("Failed to flush MP3: {:?}", e))?;
+
+        let mut file = File::create(path)
+            .map_err(|e| format!("Failed to create MP3 file: {}", e))?;
+        file.write_all(&mp3_out)
+            .map_err(|e| format!("Failed to write MP3 file: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Export audio as Ogg Vorbis file
+    ///
+    /// # Parameters
+    /// * `path` - output file path
+    /// * `data` - audio sample data
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels
+    /// * `quality` - VBR quality from -0.2 (smallest) to 1.0 (best), 0.5 is roughly 160 kbps stereo
+    /// * `progress` - callback receiving the fraction of samples encoded
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    fn export_ogg(path: &str, data: &[f32], sample_rate: u32, channels: usize, quality: f32,
+                  progress: &mut dyn FnMut(f64)) -> Result<(), String>
+    {
+        use std::io::BufWriter;
+        use std::num::{NonZeroU32, NonZeroU8};
+        use vorbis_rs::{VorbisBitrateManagementStrategy, VorbisEncoderBuilder};
+
+        if !(-0.2..=1.0).contains(&quality)
+        {
+            return Err(format!("Invalid Vorbis quality: {} (expected -0.2 to 1.0)", quality));
+        }
+
+        let sample_rate = NonZeroU32::new(sample_rate)
+            .ok_or("Invalid sample rate: 0")?;
+        let channel_count = u8::try_from(channels).ok()
+            .and_then(NonZeroU8::new)
+            .ok_or(format!("Unsupported channel count for Vorbis: {}", channels))?;
+
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create Ogg file: {}", e))?;
+
+        let mut ogg_encoder = VorbisEncoderBuilder::new(sample_rate, channel_count, BufWriter::new(file))
+            .map_err(|e| format!("Failed to create Vorbis encoder: {}", e))?
+            .bitrate_management_strategy(VorbisBitrateManagementStrategy::QualityVbr { target_quality: quality })
+            .build()
+            .map_err(|e| format!("Failed to build encoder: {}", e))?;
+
+        // vorbis takes planar blocks, so deinterleave one chunk at a time
+        let chunk_len = (EXPORT_PROGRESS_CHUNK / channels).max(1) * channels;
+        let mut planar: Vec<Vec<f32>> = vec![Vec::with_capacity(chunk_len / channels); channels];
+
+        let mut encoded_samples = 0;
+        for chunk in data.chunks(chunk_len)
+        {
+            for (ch, plane) in planar.iter_mut().enumerate()
+            {
+                plane.clear();
+                plane.extend(chunk.iter().skip(ch).step_by(channels));
+            }
+
+            ogg_encoder.encode_audio_block(&planar)
+                       .map_err(|e| format!("Failed to encode Vorbis: {}", e))?;
+
+            encoded_samples += chunk.len();
+            progress(encoded_samples as f64 / data.len() as f64);
+        }
+
+        let mut writer = ogg_encoder.finish()
+                                    .map_err(|e| format!("Failed to finish Vorbis stream: {}", e))?;
+        writer.flush()
+              .map_err(|e| format!("Failed to write Ogg file: {}", e))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::fft::{band_energy, dominant_frequency, magnitude_spectrum};
+    use crate::playback::{headless, render_frames};
+    use tempfile::TempDir;
+
+    /// Write interleaved samples to a 16-bit WAV file
+    fn write_wav(path: &Path, data: &[f32], sample_rate: u32, channels: usize)
+    {
+        let spec = hound::WavSpec
+        {
+            channels: channels as u16,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for &sample in data
+        {
+            writer.write_sample((sample * i16::MAX as f32) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn load_file_resamples_to_project_rate()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tone48k.wav");
+        write_wav(&path, &generators::sine_tone(440.0, 48000, 0.5, 48000, 1), 48000, 1);
+
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 1.0, 0.5, 44100, 1).unwrap();
+        let (sample_rate, channels, mismatched) = engine.load_file(path.to_str().unwrap(), true).unwrap();
+
+        assert_eq!((sample_rate, channels, mismatched), (44100, 1, None));
+        assert_eq!(engine.tracks[1].sample_rate, 44100);
+        assert_eq!(engine.tracks[1].audio_data.len(), engine.tracks[0].audio_data.len());
+        assert!((dominant_frequency(&engine.tracks[1].audio_data, 1, 44100) - 440.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn resample_track_keeps_duration()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 1.5, 0.5, 22050, 2).unwrap();
+        let before = engine.get_duration();
+
+        engine.resample_track(0, 44100).unwrap();
+
+        assert_eq!(engine.tracks[0].sample_rate, 44100);
+        assert_eq!(engine.tracks[0].audio_data.len(), 2 * 66150);
+        assert!((engine.get_duration() - before).abs() < 1.0 / 22050.0);
+    }
+
+    #[test]
+    fn track_to_mono_halves_samples()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(vec![0.2, 0.4, -0.6, 0.0, 1.0, 1.0], 44100, 2, "stereo").unwrap();
+
+        engine.track_to_mono(0).unwrap();
+
+        assert_eq!(engine.tracks[0].audio_data.as_slice(), &[0.3, -0.3, 1.0]);
+        assert_eq!(engine.get_track_info()[0].2, 1);
+    }
+
+    #[test]
+    fn track_to_stereo_doubles_samples()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(vec![0.1, -0.5, 0.9], 44100, 1, "mono").unwrap();
+
+        engine.track_to_stereo(0).unwrap();
+
+        assert_eq!(engine.tracks[0].audio_data.as_slice(), &[0.1, 0.1, -0.5, -0.5, 0.9, 0.9]);
+        assert_eq!(engine.get_track_info()[0].2, 2);
+    }
+
+    #[test]
+    fn split_track_pieces_concatenate_to_original()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_noise_track(1.0, "white", 7, 44100, 2).unwrap();
+        let original = engine.tracks[0].audio_data.to_vec();
+
+        let (first, second) = engine.split_track(0, 0.37).unwrap();
+
+        assert_eq!((first, second), (0, 1));
+        let mut joined = engine.tracks[0].audio_data.to_vec();
+        joined.extend_from_slice(&engine.tracks[1].audio_data);
+        assert_eq!(joined, original);
+        assert_eq!(engine.tracks[0].audio_data.len(), (0.37f64 * 44100.0) as usize * 2);
+    }
+
+    #[test]
+    fn duplicate_track_copies_independently()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(220.0, 0.5, 0.5, 44100, 1).unwrap();
+
+        let copy = engine.duplicate_track(0).unwrap();
+        assert_eq!(engine.tracks[copy].audio_data.as_slice(), engine.tracks[0].audio_data.as_slice());
+
+        engine.invert_region(0.0, 0.5, &[copy], None).unwrap();
+        assert_eq!(engine.tracks[0].audio_data.as_slice(), generators::sine_tone(220.0, 22050, 0.5, 44100, 1).as_slice());
+        assert_eq!(engine.tracks[copy].audio_data[100], -engine.tracks[0].audio_data[100]);
+    }
+
+    #[test]
+    fn remove_track_keeps_the_others()
+    {
+        let mut engine = AudioEngine::new();
+        for frequency in [100.0, 200.0, 300.0]
+        {
+            engine.add_tone_track(frequency, 0.1, 0.5, 44100, 1).unwrap();
+        }
+
+        engine.remove_track(1).unwrap();
+
+        let names: Vec<String> = engine.get_track_info().into_iter().map(|info| info.0).collect();
+        assert_eq!(engine.get_track_count(), 2);
+        assert_eq!(names, ["Tone 100 Hz", "Tone 300 Hz"]);
+        assert!(engine.remove_track(2).is_err());
+    }
+
+    #[test]
+    fn rename_track_shows_in_track_info()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 0.1, 0.5, 44100, 1).unwrap();
+
+        engine.rename_track(0, "Lead vocal").unwrap();
+
+        assert_eq!(engine.get_track_info()[0].0, "Lead vocal");
+        assert!(engine.rename_track(1, "missing").is_err());
+    }
+
+    #[test]
+    fn track_waveform_matches_full_waveform()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 1.0, 0.8, 44100, 1).unwrap();
+        engine.add_noise_track(0.6, "pink", 3, 44100, 2).unwrap();
+
+        let full = engine.get_waveform_for_range(0.1, 0.9, 200);
+        for (index, waveform) in full.iter().enumerate()
+        {
+            assert_eq!(&engine.get_track_waveform_for_range(index, 0.1, 0.9, 200).unwrap(), waveform);
+        }
+        assert!(engine.get_track_waveform_for_range(2, 0.1, 0.9, 200).is_err());
+    }
+
+    #[test]
+    fn track_levels_of_square_and_sine()
+    {
+        let mut engine = AudioEngine::new();
+        let square: Vec<f32> = (0..44100).map(|i| if (i / 50) % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        engine.add_track_from_samples(square, 44100, 1, "square").unwrap();
+        engine.add_tone_track(1000.0, 1.0, effects::db_to_gain(-6.0), 44100, 2).unwrap();
+
+        let (peak, rms) = engine.get_track_levels(0).unwrap();
+        assert!((peak - 1.0).abs() < 1e-6 && (rms - 1.0).abs() < 1e-6);
+
+        let (peak, rms) = engine.get_track_levels(1).unwrap();
+        assert!((peak - 0.501).abs() < 0.001);
+        assert!((rms - 0.354).abs() < 0.001);
+    }
+
+    #[test]
+    fn invert_region_negates_only_the_region()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_noise_track(1.0, "white", 11, 44100, 2).unwrap();
+        let original = engine.tracks[0].audio_data.to_vec();
+
+        engine.invert_region(0.25, 0.5, &[0], None).unwrap();
+
+        let (start, end) = (11025 * 2, 22050 * 2);
+        let data = &engine.tracks[0].audio_data;
+        assert_eq!(&data[..start], &original[..start]);
+        assert!(data[start..end].iter().zip(&original[start..end]).all(|(a, b)| *a == -*b));
+        assert_eq!(&data[end..], &original[end..]);
+    }
+
+    #[test]
+    fn pitch_shift_octave_keeps_region_length()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 3.0, 0.5, 44100, 1).unwrap();
+
+        engine.pitch_shift(1.0, 2.0, 12.0, &[0]).unwrap();
+
+        assert_eq!(engine.tracks[0].audio_data.len(), 3 * 44100);
+        let region = engine.get_region_samples(0, 1.1, 1.9).unwrap();
+        assert!((dominant_frequency(region, 1, 44100) - 880.0).abs() < 10.0);
+        let after = engine.get_region_samples(0, 2.1, 2.9).unwrap();
+        assert!((dominant_frequency(after, 1, 44100) - 440.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn change_speed_halves_region_and_doubles_pitch()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 3.0, 0.5, 44100, 1).unwrap();
+
+        engine.change_speed(1.0, 3.0, 2.0, &[0]).unwrap();
+
+        assert_eq!(engine.tracks[0].audio_data.len(), 2 * 44100);
+        let region = engine.get_region_samples(0, 1.0, 2.0).unwrap();
+        assert!((dominant_frequency(region, 1, 44100) - 880.0).abs() < 4.0);
+    }
+
+    /// Largest change between consecutive samples of the first channel
+    fn max_step(data: &[f32], channels: usize) -> f32
+    {
+        data.iter()
+            .step_by(channels)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn crossfade_at_smooths_the_join()
+    {
+        // the first tone stops mid-cycle, so a butt join jumps by about 0.5
+        let mut joined = generators::sine_tone(300.0, 22100, 0.5, 44100, 1);
+        joined.extend(generators::sine_tone(500.0, 22050, 0.5, 44100, 1));
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(joined, 44100, 1, "joined").unwrap();
+        assert!(max_step(&engine.tracks[0].audio_data[22090..22110], 1) > 0.3);
+
+        engine.crossfade_at(22100.0 / 44100.0, 0.01, &[0]).unwrap();
+
+        // no step larger than the faster tone's own steepest slope
+        let limit = 0.5 * 2.0 * std::f32::consts::PI * 500.0 / 44100.0;
+        assert_eq!(engine.tracks[0].audio_data.len(), 44150 - 441);
+        assert!(max_step(&engine.tracks[0].audio_data, 1) < limit * 1.2);
+    }
+
+    #[test]
+    fn noise_track_is_reproducible_with_a_seed()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_noise_track(1.0, "white", 42, 44100, 2).unwrap();
+        engine.add_noise_track(1.0, "white", 42, 44100, 2).unwrap();
+        engine.add_noise_track(1.0, "white", 43, 44100, 2).unwrap();
+
+        assert_eq!(engine.tracks[0].audio_data.len(), 2 * 44100);
+        assert_eq!(engine.tracks[0].audio_data.as_slice(), engine.tracks[1].audio_data.as_slice());
+        assert_ne!(engine.tracks[0].audio_data.as_slice(), engine.tracks[2].audio_data.as_slice());
+        assert!(engine.add_noise_track(1.0, "brown", 42, 44100, 2).is_err());
+    }
+
+    #[test]
+    fn tone_track_peaks_at_requested_frequency()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(1234.5, 1.0, 0.5, 48000, 2).unwrap();
+
+        let data = &engine.tracks[0].audio_data;
+        let (_, bin_hz) = magnitude_spectrum(data, 2, 48000);
+        assert!((dominant_frequency(data, 2, 48000) - 1234.5).abs() <= bin_hz);
+    }
+
+    #[test]
+    fn detect_silence_finds_the_gap_between_tones()
+    {
+        let mut signal = generators::sine_tone(440.0, 22050, 0.5, 44100, 1);
+        signal.extend(vec![0.0; 44100]);
+        signal.extend(generators::sine_tone(440.0, 22050, 0.5, 44100, 1));
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(signal, 44100, 1, "gap").unwrap();
+
+        let silences = engine.detect_silence(0, -40.0, 0.2).unwrap();
+
+        // the 10 ms RMS window may blur each edge by up to one window
+        assert_eq!(silences.len(), 1);
+        let (start, end) = silences[0];
+        assert!((start - 0.5).abs() < 0.01 && (end - 1.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn trim_silence_keeps_tone_and_margins()
     {
-        if self.tracks.is_empty()
+        let mut signal = vec![0.0; 2 * 22050];
+        signal.extend(generators::sine_tone(440.0, 44100, 0.5, 44100, 2));
+        signal.extend(vec![0.0; 2 * 22050]);
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(signal, 44100, 2, "padded").unwrap();
+
+        let (head, tail) = engine.trim_silence(0, -40.0, 0.05).unwrap();
+
+        let duration = engine.tracks[0].audio_data.len() as f64 / (2.0 * 44100.0);
+        assert!((duration - 1.1).abs() < 0.01);
+        assert!((head - 0.45).abs() < 0.01 && (tail - 0.45).abs() < 0.01);
+        assert!((engine.tracks[0].start_offset - head).abs() < 1e-9);
+    }
+
+    #[test]
+    fn detect_clipping_reports_injected_region()
+    {
+        let mut signal = generators::sine_tone(440.0, 44100, 0.5, 44100, 2);
+        for sample in signal[2 * 10000 + 1..2 * 10100 + 1].iter_mut().step_by(2)
         {
-            return (Vec::new(), 44100, 2);
+            *sample = 1.2;
         }
+        // a single full scale sample is shorter than the minimum run
+        signal[2 * 30000] = -1.0;
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(signal, 44100, 2, "clipped").unwrap();
 
-        let sample_rate = self.tracks[0].sample_rate;
-        let has_stereo = self.tracks.iter().any(|t| t.channels == 2);
-        let output_channels = if has_stereo { 2 } else { 1 };
+        let spans = engine.detect_clipping(0, 3).unwrap();
 
-        let start_frame = (start_time * sample_rate as f64) as usize;
-        let end_frame = (end_time * sample_rate as f64) as usize;
-        let total_frames = end_frame.saturating_sub(start_frame);
+        assert_eq!(spans, vec![(10000.0 / 44100.0, 10100.0 / 44100.0)]);
+    }
 
-        if total_frames == 0
+    #[test]
+    fn offset_tracks_sum_where_they_overlap()
+    {
+        let tone = generators::sine_tone(440.0, 44100, 0.25, 44100, 1);
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(tone.clone(), 44100, 1, "first").unwrap();
+        engine.add_track_from_samples(tone.clone(), 44100, 1, "second").unwrap();
+
+        engine.set_track_offset(1, 0.5).unwrap();
+
+        assert!((engine.get_duration() - 1.5).abs() < 1e-9);
+        let (mixed, _, channels) = engine.mix_tracks_for_playback(0.0, 1.5);
+        assert_eq!((mixed.len(), channels), (66150, 1));
+        for (n, &sample) in mixed.iter().enumerate()
         {
-            return (Vec::new(), sample_rate, output_channels);
+            let first = tone.get(n).copied().unwrap_or(0.0);
+            let second = n.checked_sub(22050).map_or(0.0, |m| tone.get(m).copied().unwrap_or(0.0));
+            assert!((sample - (first + second)).abs() < 1e-6);
         }
 
-        let mut mixed_data = vec![0.0f32; total_frames * output_channels];
+        // a negative offset trims the head of the track off the timeline
+        engine.set_track_offset(1, -0.5).unwrap();
+        assert!((engine.get_duration() - 1.0).abs() < 1e-9);
+        let (mixed, _, _) = engine.mix_tracks_for_playback(0.0, 1.0);
+        assert!((mixed[0] - (tone[0] + tone[22050])).abs() < 1e-6);
+        assert!((mixed[30000] - tone[30000]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gain_envelope_ramps_the_mix()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(vec![0.5; 44100], 44100, 1, "constant").unwrap();
+
+        engine.set_gain_envelope(0, vec![(1.0, 0.0), (0.0, f64::NEG_INFINITY)]).unwrap();
 
-        for track in &self.tracks
+        // gains are interpolated linearly in amplitude, so a constant signal ramps linearly
+        let (mixed, _, _) = engine.mix_tracks_for_playback(0.0, 1.0);
+        assert_eq!(mixed[0], 0.0);
+        for (n, &sample) in mixed.iter().enumerate()
         {
-            // calculate where this track contributes to the output
-            // track audio starts at track.start_offset
-            let track_audio_duration = (track.audio_data.len() / track.channels) as f64 / track.sample_rate as f64;
-            let track_end_time = track.start_offset + track_audio_duration;
+            assert!((sample as f64 - 0.5 * n as f64 / 44100.0).abs() < 1e-4);
+        }
+        assert!(mixed.windows(2).all(|pair| pair[1] >= pair[0]));
+    }
 
-            // skip if track doesn't overlap with playback range
-            if end_time <= track.start_offset || start_time >= track_end_time
-            {
-                continue;
-            }
+    #[test]
+    fn play_track_mixes_only_that_track()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 1.0, 0.5, 44100, 2).unwrap();
+        engine.add_noise_track(1.0, "white", 5, 44100, 1).unwrap();
+        engine.set_track_offset(1, 0.25).unwrap();
 
-            // calculate frame ranges accounting for offset
-            for frame_idx in 0..total_frames
-            {
-                // what time does this output frame represent?
-                let output_time = start_time + (frame_idx as f64 / sample_rate as f64);
+        let (mixed, sample_rate, channels, start) = engine.mix_track_for_playback(1, Some(0.5), Some(0.75)).unwrap();
 
-                // is this time within the track's audio?
-                if output_time < track.start_offset || output_time >= track_end_time
-                {
-                    continue;
-                }
+        assert_eq!((sample_rate, channels, start), (44100, 1, 0.5));
+        assert_eq!(mixed.as_slice(), engine.get_region_samples(1, 0.5, 0.75).unwrap());
 
-                // calculate the frame within the track's audio data
-                let track_local_time = output_time - track.start_offset;
-                let track_frame = (track_local_time * track.sample_rate as f64) as usize;
-                let output_idx = frame_idx * output_channels;
+        // by default the whole track is mixed from its offset
+        let (mixed, _, _, start) = engine.mix_track_for_playback(1, None, None).unwrap();
+        assert_eq!(start, 0.25);
+        assert_eq!(mixed.as_slice(), engine.tracks[1].audio_data.as_slice());
+        assert!(engine.mix_track_for_playback(2, None, None).is_err());
+    }
 
-                // skip if track has ended
-                if track_frame >= track.audio_data.len() / track.channels
-                {
-                    continue;
-                }
+    #[test]
+    fn add_track_from_samples_checks_layout()
+    {
+        let mut engine = AudioEngine::new();
+        let samples: Vec<f32> = (0..8).map(|i| i as f32 / 8.0).collect();
 
-                if output_channels == 2
-                {
-                    if track.channels == 2
-                    {
-                        let track_idx = track_frame * 2;
-                        if track_idx + 1 < track.audio_data.len()
-                        {
-                            mixed_data[output_idx] += track.audio_data[track_idx];
-                            mixed_data[output_idx + 1] += track.audio_data[track_idx + 1];
-                        }
-                    }
-                    else if track.channels == 1
-                    {
-                        if track_frame < track.audio_data.len()
-                        {
-                            let sample = track.audio_data[track_frame];
-                            mixed_data[output_idx] += sample;
-                            mixed_data[output_idx + 1] += sample;
-                        }
-                    }
-                }
-                else
-                {
-                    if track.channels == 1
-                    {
-                        if track_frame < track.audio_data.len()
-                        {
-                            mixed_data[output_idx] += track.audio_data[track_frame];
-                        }
-                    }
-                }
-            }
-        }
+        let index = engine.add_track_from_samples(samples.clone(), 22050, 2, "from array").unwrap();
 
-        for sample in &mut mixed_data
-        {
-            *sample = sample.clamp(-1.0, 1.0);
-        }
+        assert_eq!(index, 0);
+        assert_eq!(engine.tracks[0].audio_data.as_slice(), samples.as_slice());
+        assert_eq!(engine.get_track_info()[0], ("from array".to_string(), 22050, 2, 4.0 / 22050.0, 0.0));
+        assert!(engine.add_track_from_samples(vec![0.0; 7], 22050, 2, "odd").is_err());
+        assert!(engine.add_track_from_samples(vec![0.0; 8], 22050, 0, "no channels").is_err());
+        assert_eq!(engine.get_track_count(), 1);
+    }
 
-        (mixed_data, sample_rate, output_channels)
+    #[test]
+    fn track_samples_match_audio_data()
+    {
+        let mut engine = AudioEngine::new();
+        let samples: Vec<f32> = (0..2000).map(|i| (i as f32 * 0.01).sin()).collect();
+        engine.add_track_from_samples(samples.clone(), 1000, 2, "known").unwrap();
+
+        assert_eq!(engine.get_track_samples(0).unwrap(), samples.as_slice());
+        assert_eq!(engine.get_region_samples(0, 0.25, 0.5).unwrap(), &samples[500..1000]);
+        assert!(engine.get_track_samples(1).is_err());
     }
 
-    /// Mix tracks with specific channel mode for export
-    ///
-    /// # Parameters
-    /// * `start_time` - start time in seconds
-    /// * `end_time` - end time in seconds
-    /// * `channel_mode` - channel configuration mode
-    ///
-    /// # Returns
-    /// `Vec<(Vec<f32>, u32, usize, String)>` - list of (audio data, sample rate, channels, suffix)
-    ///
-    /// # Notes
-    /// Returns multiple results for split mode, single result otherwise
-    fn mix_tracks_for_export(&self, start_time: f64, end_time: f64, channel_mode: &str) -> Vec<(Vec<f32>, u32, usize, String)>
+    #[test]
+    fn load_and_export_errors_are_reported()
     {
-        if self.tracks.is_empty()
-        {
-            return vec![(Vec::new(), 44100, 2, String::new())];
-        }
+        // the editor raises these as LoadError and ExportError respectively
+        let dir = TempDir::new().unwrap();
+        let mut engine = AudioEngine::new();
+        let missing = dir.path().join("missing.wav");
+        assert!(engine.load_file(missing.to_str().unwrap(), false).is_err());
+        assert_eq!(engine.get_track_count(), 0);
+
+        let items = vec![(vec![0.0; 100], 44100, 1, String::new())];
+        let unsupported = dir.path().join("out.xyz");
+        let error = AudioEngine::write_export(unsupported.to_str().unwrap(), items, &ExportOptions::default(), None).unwrap_err();
+        assert!(error.starts_with("Unsupported format"));
+        assert!(!unsupported.exists());
+    }
 
-        let sample_rate = self.tracks[0].sample_rate;
-        let start_frame = (start_time * sample_rate as f64) as usize;
-        let end_frame = (end_time * sample_rate as f64) as usize;
-        let total_frames = end_frame.saturating_sub(start_frame);
+    #[test]
+    fn export_progress_rises_to_one()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("progress.flac");
+        let items = vec![(generators::sine_tone(440.0, 3 * 44100, 0.5, 44100, 2), 44100, 2, String::new())];
+
+        let mut reported = Vec::new();
+        let mut record = |fraction: f64| reported.push(fraction);
+        AudioEngine::write_export(path.to_str().unwrap(), items, &ExportOptions::default(), Some(&mut record)).unwrap();
+
+        // one report per FLAC frame, then the final 1.0
+        assert!(reported.len() > 10);
+        assert!(reported.windows(2).all(|pair| pair[1] >= pair[0]));
+        assert!(reported.iter().all(|fraction| (0.0..=1.0).contains(fraction)));
+        assert_eq!(reported.last(), Some(&1.0));
+    }
 
-        if total_frames == 0
+    #[test]
+    fn stop_and_clear_release_playback()
+    {
+        // the editor's __exit__ runs exactly these two steps
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 1.0, 0.5, 1000, 1).unwrap();
+        let mut playback = headless(1000, 1);
+        playback.play(engine.tracks[0].audio_data.to_vec(), 0.0).unwrap();
+        render_frames(&playback, 100);
+        engine.playback = Some(playback);
+        assert!(engine.is_playing());
+
+        engine.stop();
+        engine.clear_tracks();
+
+        assert!(!engine.is_playing());
+        assert!(engine.playback.is_none());
+        assert_eq!(engine.get_track_count(), 0);
+    }
+
+    #[test]
+    fn project_round_trips_two_tracks()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.json");
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 0.5, 0.5, 44100, 2).unwrap();
+        engine.add_noise_track(0.25, "pink", 9, 44100, 1).unwrap();
+        engine.rename_track(1, "Room tone").unwrap();
+        engine.set_track_offset(1, 0.3).unwrap();
+        engine.set_gain_envelope(0, vec![(0.0, -6.0), (0.5, 0.0)]).unwrap();
+        engine.set_gain_envelope(1, vec![(0.1, -12.0)]).unwrap();
+
+        engine.save_project(path.to_str().unwrap()).unwrap();
+        let mut loaded = AudioEngine::new();
+        assert_eq!(loaded.load_project(path.to_str().unwrap()).unwrap(), 2);
+
+        assert_eq!(loaded.get_track_info(), engine.get_track_info());
+        for (original, restored) in engine.tracks.iter().zip(&loaded.tracks)
         {
-            return vec![(Vec::new(), sample_rate, 2, String::new())];
+            assert_eq!(restored.gain_envelope, original.gain_envelope);
+            assert_eq!(restored.audio_data.as_slice(), original.audio_data.as_slice());
         }
+    }
 
-        match channel_mode
+    #[test]
+    fn ogg_export_writes_an_ogg_stream()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tone.ogg");
+        let items = vec![(generators::sine_tone(440.0, 44100, 0.5, 44100, 2), 44100, 2, String::new())];
+
+        AudioEngine::write_export(path.to_str().unwrap(), items, &ExportOptions::default(), None).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.starts_with(b"OggS"));
+        assert!(bytes.len() > 1000);
+    }
+
+    #[test]
+    fn raw_s16le_round_trips()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tone.raw");
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 0.5, 0.8, 22050, 2).unwrap();
+        let original = engine.tracks[0].audio_data.to_vec();
+
+        let (sample_rate, channels) = engine.export_raw(path.to_str().unwrap(), "s16", "le").unwrap();
+        assert_eq!((sample_rate, channels), (22050, 2));
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 2 * original.len() as u64);
+
+        let mut imported = AudioEngine::new();
+        let index = imported.import_raw(path.to_str().unwrap(), 22050, 2, "s16", "le").unwrap();
+        let restored = &imported.tracks[index].audio_data;
+        assert_eq!(restored.len(), original.len());
+        assert!(restored.iter().zip(&original).all(|(a, b)| (a - b).abs() <= 1.0 / 32767.0));
+    }
+
+    #[test]
+    fn wav_export_honours_bit_depth()
+    {
+        let dir = TempDir::new().unwrap();
+        let data: Vec<f32> = generators::white_noise(4410, 2, 3).iter().map(|&s| s * 0.9).collect();
+        let export = |name: &str, bit_depth: u16|
         {
-            "split" =>
-            {
-                // split all stereo tracks to separate mono tracks with _L and _R suffixes
-                let mut results = Vec::new();
-                for track in &self.tracks
-                {
-                    if track.channels == 2
-                    {
-                        let track_start_frame = (start_time * track.sample_rate as f64) as usize;
-                        let track_total_frames = total_frames.min(
-                            (track.audio_data.len() / 2).saturating_sub(track_start_frame)
-                        );
+            let path = dir.path().join(name);
+            let options = ExportOptions { bit_depth: Some(bit_depth), ..Default::default() };
+            AudioEngine::write_export(path.to_str().unwrap(), vec![(data.clone(), 44100, 2, String::new())], &options, None).unwrap();
+            hound::WavReader::open(path).unwrap()
+        };
 
-                        let mut left_data = Vec::with_capacity(track_total_frames);
-                        let mut right_data = Vec::with_capacity(track_total_frames);
+        let reader = export("deep.wav", 24);
+        assert_eq!(reader.spec().bits_per_sample, 24);
+        assert_eq!(reader.spec().sample_format, hound::SampleFormat::Int);
 
-                        for frame_idx in 0..track_total_frames
-                        {
-                            let track_frame = track_start_frame + frame_idx;
-                            let track_idx = track_frame * 2;
-                            if track_idx + 1 < track.audio_data.len()
-                            {
-                                left_data.push(track.audio_data[track_idx]);
-                                right_data.push(track.audio_data[track_idx + 1]);
-                            }
-                            else
-                            {
-                                break;
-                            }
-                        }
+        let mut reader = export("float.wav", 32);
+        assert_eq!(reader.spec().bits_per_sample, 32);
+        assert_eq!(reader.spec().sample_format, hound::SampleFormat::Float);
+        let restored: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+        assert_eq!(restored, data);
+    }
 
-                        results.push((left_data, sample_rate, 1, "_L".to_string()));
-                        results.push((right_data, sample_rate, 1, "_R".to_string()));
-                    }
-                }
-                if results.is_empty()
-                {
-                    results.push((Vec::new(), sample_rate, 1, String::new()));
-                }
-                results
-            }
-            "mono_to_stereo" =>
-            {
-                // combine pairs of mono tracks into stereo tracks
-                let mut stereo_data = vec![0.0f32; total_frames * 2];
+    #[test]
+    fn mp3_export_starts_with_id3_title()
+    {
+        use id3::TagLike;
 
-                let mono_tracks: Vec<&AudioTrack> = self.tracks.iter().filter(|t| t.channels == 1).collect();
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tagged.mp3");
+        let tags = ExportTags { title: Some("Test Tone".to_string()), artist: Some("soundly".to_string()), ..Default::default() };
+        let options = ExportOptions { tags, ..Default::default() };
+        let items = vec![(generators::sine_tone(440.0, 44100, 0.5, 44100, 2), 44100, 2, String::new())];
 
-                // process pairs of mono tracks
-                for pair_idx in (0..mono_tracks.len()).step_by(2)
-                {
-                    if pair_idx + 1 >= mono_tracks.len()
-                    {
-                        break;
-                    }
+        AudioEngine::write_export(path.to_str().unwrap(), items, &options, None).unwrap();
 
-                    let left_track = mono_tracks[pair_idx];
-                    let right_track = mono_tracks[pair_idx + 1];
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.starts_with(b"ID3"));
+        let tag = id3::Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.title(), Some("Test Tone"));
+        assert_eq!(tag.artist(), Some("soundly"));
+    }
 
-                    let left_start = (start_time * left_track.sample_rate as f64) as usize;
-                    let right_start = (start_time * right_track.sample_rate as f64) as usize;
+    #[test]
+    fn stems_write_one_file_per_track()
+    {
+        let dir = TempDir::new().unwrap();
+        let stem_dir = dir.path().join("stems");
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(110.0, 1.0, 0.5, 44100, 2).unwrap();
+        engine.add_tone_track(220.0, 0.5, 0.5, 44100, 1).unwrap();
+        engine.add_noise_track(0.25, "white", 1, 44100, 2).unwrap();
+        engine.rename_track(0, "bass.wav").unwrap();
+        engine.rename_track(1, "lead").unwrap();
+        engine.rename_track(2, "lead").unwrap();
+
+        let stems = engine.render_stems(true);
+        let paths = AudioEngine::write_stems(stem_dir.to_str().unwrap(), "wav", stems, &ExportOptions::default(), None).unwrap();
+
+        let names: Vec<String> = paths.iter()
+            .map(|path| Path::new(path).file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["bass.wav", "lead.wav", "lead_2.wav"]);
+        // every stem spans the whole project so they line up on import
+        for path in &paths
+        {
+            let reader = hound::WavReader::open(path).unwrap();
+            assert_eq!(reader.duration(), 44100);
+        }
+    }
 
-                    for frame_idx in 0..total_frames
-                    {
-                        let output_idx = frame_idx * 2;
+    #[test]
+    fn export_regions_writes_each_span()
+    {
+        let dir = TempDir::new().unwrap();
+        let first = dir.path().join("first.wav").to_str().unwrap().to_string();
+        let second = dir.path().join("second.flac").to_str().unwrap().to_string();
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 2.0, 0.5, 44100, 2).unwrap();
+
+        let regions = vec![(0.0, 0.5, first.clone()), (0.75, 2.0, second.clone())];
+        let rendered = engine.render_regions(&regions, None, MonoFold::default(), None).unwrap();
+        AudioEngine::write_regions(rendered, &ExportOptions::default(), None).unwrap();
+
+        assert_eq!(hound::WavReader::open(&first).unwrap().duration(), 22050);
+        let mut reloaded = AudioEngine::new();
+        reloaded.load_file(&second, false).unwrap();
+        assert!((reloaded.get_duration() - 1.25).abs() < 1.0 / 44100.0);
+        assert!(engine.render_regions(&[(1.0, 0.5, first)], None, MonoFold::default(), None).is_err());
+    }
 
-                        if left_start + frame_idx < left_track.audio_data.len()
-                        {
-                            stereo_data[output_idx] = left_track.audio_data[left_start + frame_idx];
-                        }
+    #[test]
+    fn tags_are_read_back_from_loaded_files()
+    {
+        let dir = TempDir::new().unwrap();
+        let tagged = dir.path().join("tagged.flac");
+        let tags = ExportTags { title: Some("Morning".to_string()), artist: Some("The Band".to_string()), year: Some(2024), ..Default::default() };
+        let options = ExportOptions { tags, ..Default::default() };
+        let items = vec![(generators::sine_tone(440.0, 4410, 0.5, 44100, 1), 44100, 1, String::new())];
+        AudioEngine::write_export(tagged.to_str().unwrap(), items, &options, None).unwrap();
+        let untagged = dir.path().join("untagged.wav");
+        write_wav(&untagged, &[0.0; 100], 44100, 1);
+
+        let mut engine = AudioEngine::new();
+        engine.load_file(tagged.to_str().unwrap(), false).unwrap();
+        engine.load_file(untagged.to_str().unwrap(), false).unwrap();
+
+        let metadata = engine.get_track_metadata(0).unwrap();
+        assert_eq!(metadata.get("title").map(String::as_str), Some("Morning"));
+        assert_eq!(metadata.get("artist").map(String::as_str), Some("The Band"));
+        assert_eq!(metadata.get("date").map(String::as_str), Some("2024"));
+        assert!(engine.get_track_metadata(1).unwrap().is_empty());
+        assert!(engine.get_track_metadata(2).is_err());
+    }
 
-                        if right_start + frame_idx < right_track.audio_data.len()
-                        {
-                            stereo_data[output_idx + 1] = right_track.audio_data[right_start + frame_idx];
-                        }
-                    }
-                }
+    #[test]
+    fn waveform_last_pixel_includes_final_samples()
+    {
+        let frames = 10007;
+        let mut data = vec![0.0f32; 2 * frames];
+        data[2 * (frames - 2)] = 0.9;
+        data[2 * (frames - 1) + 1] = -0.7;
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(data, 44100, 2, "spike").unwrap();
+
+        // 10007 frames do not divide evenly into 7 pixels
+        let waveform = engine.get_track_waveform_for_range(0, 0.0, frames as f64 / 44100.0, 7).unwrap();
+
+        assert_eq!(waveform.len(), 7);
+        assert_eq!(waveform[6], (0.0, 0.9, -0.7, 0.0));
+        assert!(waveform[..6].iter().all(|&pixel| pixel == (0.0, 0.0, 0.0, 0.0)));
+    }
 
-                vec![(stereo_data, sample_rate, 2, String::new())]
-            }
-            "mono" =>
-            {
-                // downmix all tracks to mono
-                let mut mono_data = vec![0.0f32; total_frames];
+    #[test]
+    fn four_channel_track_mixes_to_stereo_and_mono()
+    {
+        // only the rear pair carries signal, so dropping extra channels would leave silence
+        let tone = generators::sine_tone(440.0, 44100, 0.5, 44100, 1);
+        let quad: Vec<f32> = tone.iter().flat_map(|&s| [0.0, 0.0, s, 0.5 * s]).collect();
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(quad, 44100, 4, "quad").unwrap();
+
+        let (stereo, _, channels) = engine.mix_tracks_for_playback(0.0, 1.0);
+        assert_eq!((channels, stereo.len()), (2, 2 * 44100));
+        for ch in 0..2
+        {
+            assert!(stereo.iter().skip(ch).step_by(2).any(|s| s.abs() > 0.1));
+        }
 
-                for track in &self.tracks
-                {
-                    let track_start_frame = (start_time * track.sample_rate as f64) as usize;
-                    let track_total_frames = total_frames.min(
-                        (track.audio_data.len() / track.channels).saturating_sub(track_start_frame)
-                    );
+        let mono = engine.render_export(None, None, Some("mono"), MonoFold::default(), None);
+        assert_eq!(mono[0].2, 1);
+        assert_eq!(mono[0].0.len(), 44100);
+        assert!(mono[0].0.iter().any(|s| s.abs() > 0.1));
+    }
 
-                    for frame_idx in 0..track_total_frames
-                    {
-                        let track_frame = track_start_frame + frame_idx;
+    /// Resident anonymous memory of this process in KiB, which excludes file-backed pages
+    #[cfg(target_os = "linux")]
+    fn anonymous_memory_kb() -> usize
+    {
+        std::fs::read_to_string("/proc/self/status")
+            .unwrap()
+            .lines()
+            .find_map(|line| line.strip_prefix("RssAnon:"))
+            .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+            .unwrap()
+    }
 
-                        if track.channels == 2
-                        {
-                            let track_idx = track_frame * 2;
-                            if track_idx + 1 < track.audio_data.len()
-                            {
-                                let mono_sample = (track.audio_data[track_idx] + track.audio_data[track_idx + 1]) / 2.0;
-                                mono_data[frame_idx] += mono_sample;
-                            }
-                        }
-                        else if track.channels == 1
-                        {
-                            if track_frame < track.audio_data.len()
-                            {
-                                mono_data[frame_idx] += track.audio_data[track_frame];
-                            }
-                        }
-                    }
-                }
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn streaming_load_stays_out_of_memory()
+    {
+        // four minutes of stereo, 85 MB once decoded to f32
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("long.wav");
+        let frames = 4 * 60 * 44100;
+        let spec = hound::WavSpec { channels: 2, sample_rate: 44100, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for n in 0..frames
+        {
+            let sample = ((n as f64 * 440.0 * std::f64::consts::TAU / 44100.0).sin() * 16000.0) as i16;
+            writer.write_sample(sample).unwrap();
+            writer.write_sample(-sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let before = anonymous_memory_kb();
+        let mut engine = AudioEngine::new();
+        engine.load_file_streaming(path.to_str().unwrap()).unwrap();
+        let waveform = engine.get_waveform_for_range(0.0, 240.0, 1000);
+        let growth = anonymous_memory_kb().saturating_sub(before);
+
+        assert_eq!(engine.tracks[0].audio_data.len(), 2 * frames);
+        assert!(waveform[0].iter().all(|&(min_l, max_l, min_r, max_r)| max_l > 0.45 && min_l < -0.45 && max_r > 0.45 && min_r < -0.45));
+        // generous enough for other tests running alongside, far below the decoded size
+        assert!(growth < 32 * 1024, "anonymous memory grew by {} KiB", growth);
+    }
 
-                for sample in &mut mono_data
-                {
-                    *sample = sample.clamp(-1.0, 1.0);
-                }
+    #[test]
+    fn load_files_matches_sequential_loads()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut paths = Vec::new();
+        for (index, (frequency, rate, channels)) in [(220.0, 44100, 2), (330.0, 48000, 1), (440.0, 22050, 2)].into_iter().enumerate()
+        {
+            let path = dir.path().join(format!("clip{}.wav", index));
+            write_wav(&path, &generators::sine_tone(frequency, rate as usize / 2, 0.5, rate, channels), rate, channels);
+            paths.push(path.to_str().unwrap().to_string());
+        }
 
-                vec![(mono_data, sample_rate, 1, String::new())]
-            }
-            _ =>
-            {
-                // default: mix all tracks however they would be played back
-                let (data, rate, channels) = self.mix_tracks_for_playback(start_time, end_time);
-                vec![(data, rate, channels, String::new())]
-            }
+        let mut batch = AudioEngine::new();
+        let batch_results = batch.load_files(&paths, false).unwrap();
+        let mut sequential = AudioEngine::new();
+        let sequential_results: Vec<_> = paths.iter().map(|path| sequential.load_file(path, false).unwrap()).collect();
+
+        assert_eq!(batch_results, sequential_results);
+        // each later file reports the project rate it disagrees with
+        assert_eq!(batch_results.iter().map(|result| result.2).collect::<Vec<_>>(), [None, Some(44100), Some(44100)]);
+        assert_eq!(batch.get_track_info(), sequential.get_track_info());
+        for (a, b) in batch.tracks.iter().zip(&sequential.tracks)
+        {
+            assert_eq!(a.audio_data.as_slice(), b.audio_data.as_slice());
         }
     }
 
-    /// Start audio playback
-    ///
-    /// # Parameters
-    /// * `start_time` - optional start time in seconds
-    /// * `end_time` - optional end time in seconds
-    ///
-    /// # Returns
-    /// `Result<(), String>` - Ok if successful
-    ///
-    /// # Notes
-    /// If both times are None and playback is paused, resumes from current position.
-    /// Mixes all tracks together for playback.
-    pub fn play(&mut self, start_time: Option<f64>, end_time: Option<f64>) -> Result<(), String>
+    #[test]
+    fn cached_waveform_follows_edits()
     {
-        // resume paused playback if no times specified
-        if start_time.is_none() && end_time.is_none()
+        let mut engine = AudioEngine::new();
+        engine.add_noise_track(10.0, "pink", 4, 44100, 2).unwrap();
+        // zoomed out far enough to read from the mipmap
+        let _ = engine.get_waveform_for_range(0.0, 10.0, 300);
+
+        engine.invert_region(1.0, 2.0, &[0], None).unwrap();
+        engine.delete_region(4.0, 6.5, &[0], 0.0).unwrap();
+        let cached = engine.get_waveform_for_range(0.0, 10.0, 300);
+
+        let mut fresh = AudioEngine::new();
+        fresh.add_track_from_samples(engine.tracks[0].audio_data.to_vec(), 44100, 2, "fresh").unwrap();
+        assert_eq!(cached, fresh.get_waveform_for_range(0.0, 10.0, 300));
+    }
+
+    /// Mix tracks frame by frame through the general per-frame path, as a reference for the fast paths
+    fn reference_mix(tracks: &[AudioTrack], start_time: f64, end_time: f64) -> Vec<f32>
+    {
+        let sample_rate = tracks[0].sample_rate;
+        let output_channels = if tracks.iter().any(|t| t.channels >= 2) { 2 } else { 1 };
+        let start_frame = (start_time * sample_rate as f64) as usize;
+        let total_frames = ((end_time * sample_rate as f64) as usize).saturating_sub(start_frame);
+        let mut mixed = vec![0.0f32; total_frames * output_channels];
+
+        for track in tracks
         {
-            if let Some(ref mut playback) = self.playback
+            for frame_idx in 0..total_frames
             {
-                if playback.is_paused()
+                let time = start_time + frame_idx as f64 / sample_rate as f64;
+                if let Some(track_frame) = AudioEngine::track_frame_at(track, time)
                 {
-                    playback.resume()?;
-                    return Ok(());
+                    let frame = &track.audio_data[track_frame * track.channels..(track_frame + 1) * track.channels];
+                    let out = &mut mixed[frame_idx * output_channels..(frame_idx + 1) * output_channels];
+                    AudioEngine::mix_frame(out, frame, track.gain_at(time - track.start_offset), MonoFold::default());
                 }
             }
         }
+        mixed
+    }
 
-        let duration = self.get_duration();
-        let start = start_time.unwrap_or(0.0);
-        let end = end_time.unwrap_or(duration);
+    /// Tracks covering every fast path: matching layouts, mono into stereo, and positive and negative offsets
+    fn mix_test_engine(seconds: f64) -> AudioEngine
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_noise_track(seconds, "white", 1, 44100, 2).unwrap();
+        engine.add_noise_track(seconds, "pink", 2, 44100, 1).unwrap();
+        engine.add_noise_track(seconds, "white", 3, 44100, 2).unwrap();
+        engine.set_track_offset(1, 0.37).unwrap();
+        engine.set_track_offset(2, -0.21).unwrap();
+        engine
+    }
 
-        let (mixed_data, sample_rate, channels) = self.mix_tracks_for_playback(start, end);
+    #[test]
+    fn fast_mix_matches_per_frame_mix()
+    {
+        let engine = mix_test_engine(2.0);
+        for (start, end) in [(0.0, 2.5), (0.3, 1.1), (1.9, 2.4)]
+        {
+            let (fast, _, channels) = AudioEngine::sum_tracks(&engine.tracks, start, end);
+            assert_eq!(channels, 2);
+            assert_eq!(fast, reference_mix(&engine.tracks, start, end));
+        }
+
+        let mut mono = AudioEngine::new();
+        mono.add_tone_track(440.0, 1.0, 0.5, 44100, 1).unwrap();
+        mono.add_noise_track(1.0, "white", 5, 44100, 1).unwrap();
+        mono.set_track_offset(1, 0.5).unwrap();
+        assert_eq!(AudioEngine::sum_tracks(&mono.tracks, 0.0, 1.5).0, reference_mix(&mono.tracks, 0.0, 1.5));
+    }
+
+    /// Run with `cargo test --release -- --ignored` to compare speeds
+    #[test]
+    #[ignore]
+    fn fast_mix_outpaces_per_frame_mix()
+    {
+        let engine = mix_test_engine(120.0);
+
+        let timer = std::time::Instant::now();
+        let (fast, _, _) = AudioEngine::sum_tracks(&engine.tracks, 0.0, 120.0);
+        let fast_time = timer.elapsed();
+
+        let timer = std::time::Instant::now();
+        let reference = reference_mix(&engine.tracks, 0.0, 120.0);
+        let reference_time = timer.elapsed();
+
+        assert_eq!(fast, reference);
+        assert!(fast_time < reference_time, "fast {:?}, per frame {:?}", fast_time, reference_time);
+    }
+
+    #[test]
+    fn mono_fold_weights_scale_the_downmix()
+    {
+        let mut engine = AudioEngine::new();
+        let data: Vec<f32> = (0..4410)
+            .map(|i| (i as f32 * 0.05).sin())
+            .flat_map(|s| [0.4 * s, 0.2 * s])
+            .collect();
+        engine.add_track_from_samples(data.clone(), 44100, 2, "tone").unwrap();
+
+        let render = |mono_fold: MonoFold| engine.render_export(None, None, Some("mono"), mono_fold, None).remove(0);
+        let (average, _, channels, _) = render(MonoFold::default());
+        assert_eq!((channels, average.len()), (1, 4410));
+        for (mono, frame) in average.iter().zip(data.chunks(2))
+        {
+            assert!((mono - (frame[0] + frame[1]) / 2.0).abs() < 1e-6);
+        }
 
-        let needs_new_playback = self.playback.is_none() ||
-            self.playback_sample_rate != Some(sample_rate);
+        // -3 dB per channel is sqrt(2) louder than the plain average
+        let weight = 10f32.powf(-3.0 / 20.0);
+        let (weighted, _, _, _) = render(MonoFold { left: weight, right: weight, gain: 1.0 });
+        for (w, a) in weighted.iter().zip(&average)
+        {
+            assert!((w - a * 2.0 * weight).abs() < 1e-6);
+        }
 
-        if needs_new_playback
+        let (left_only, _, _, _) = render(MonoFold { left: 1.0, right: 0.0, gain: 0.5 });
+        for (mono, frame) in left_only.iter().zip(data.chunks(2))
         {
-            self.playback = Some(AudioPlayback::new(sample_rate, channels)?);
-            self.playback_sample_rate = Some(sample_rate);
+            assert!((mono - frame[0] * 0.5).abs() < 1e-6);
         }
+    }
 
-        if let Some(ref mut playback) = self.playback
+    #[test]
+    fn overwrite_replaces_only_the_written_span()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 5.0, 0.5, 44100, 2).unwrap();
+        let original = engine.tracks[0].audio_data.to_vec();
+
+        let silence = vec![0.0f32; 44100 * 2];
+        engine.overwrite_region(2.0, &silence, 44100, 2, &[0]).unwrap();
+        let written = engine.tracks[0].audio_data.to_vec();
+        assert_eq!(written.len(), original.len());
+        assert_eq!(written[..88200 * 2], original[..88200 * 2]);
+        assert!(written[88200 * 2..132300 * 2].iter().all(|&s| s == 0.0));
+        assert_eq!(written[132300 * 2..], original[132300 * 2..]);
+
+        // mono audio at half the rate is conformed, and the part past the end is dropped
+        let half_rate_silence = vec![0.0f32; 22050 * 2];
+        engine.overwrite_region(4.0, &half_rate_silence, 22050, 1, &[0]).unwrap();
+        let truncated = engine.tracks[0].audio_data.to_vec();
+        assert_eq!(truncated.len(), original.len());
+        assert_eq!(truncated[..176400 * 2], written[..176400 * 2]);
+        assert!(truncated[176400 * 2..].iter().all(|&s| s == 0.0));
+        assert_eq!(engine.get_duration(), 5.0);
+    }
+
+    #[test]
+    fn crop_keeps_only_the_selected_span()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 10.0, 0.5, 44100, 2).unwrap();
+        let original = engine.tracks[0].audio_data.to_vec();
+
+        engine.crop_to_region(3.0, 5.0, &[0]).unwrap();
+        let track = &engine.tracks[0];
+        assert_eq!(track.audio_data.len(), 88200 * 2);
+        assert_eq!(track.audio_data.to_vec(), original[132300 * 2..220500 * 2]);
+        // the kept audio stays where it was on the timeline
+        assert_eq!(track.start_offset, 3.0);
+        assert_eq!(engine.get_duration(), 5.0);
+
+        assert!(engine.crop_to_region(5.0, 3.0, &[0]).is_err());
+    }
+
+    #[test]
+    fn appended_track_follows_the_destination()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 2.0, 0.5, 44100, 2).unwrap();
+        engine.add_tone_track(880.0, 1.5, 0.3, 44100, 1).unwrap();
+        let first = engine.tracks[0].audio_data.to_vec();
+        let second = engine.tracks[1].audio_data.to_vec();
+
+        assert_eq!(engine.append_track(0, 1, true), Ok(0));
+        assert_eq!(engine.tracks.len(), 1);
+        let combined = engine.tracks[0].audio_data.to_vec();
+        assert_eq!(combined.len(), (88200 + 66150) * 2);
+        assert!((engine.get_duration() - 3.5).abs() < 1e-9);
+
+        // the destination is untouched and the mono source lands in both channels right after it
+        assert_eq!(combined[..88200 * 2], first[..]);
+        for (frame, &sample) in combined[88200 * 2..].chunks(2).zip(&second)
         {
-            playback.play(mixed_data, start)?;
+            assert_eq!(frame, [sample, sample]);
         }
 
-        Ok(())
+        assert!(engine.append_track(0, 0, false).is_err());
+        assert!(engine.append_track(0, 3, false).is_err());
     }
 
-    /// Pause audio playback
-    pub fn pause(&mut self)
+    #[test]
+    fn bitcrush_snaps_to_the_bit_depth_levels()
     {
-        if let Some(ref mut playback) = self.playback
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 0.5, 0.9, 44100, 2).unwrap();
+        engine.apply_bitcrush(4, 3, &[0]).unwrap();
+        let crushed = engine.tracks[0].audio_data.to_vec();
+
+        // 4 bits leave the 16 levels -8/8 to 7/8
+        let levels: std::collections::BTreeSet<i32> = crushed.iter().map(|&s| (s * 8.0) as i32).collect();
+        assert!(crushed.iter().all(|&s| (s * 8.0).fract() == 0.0));
+        assert_eq!(levels, (-7..=7).collect());
+
+        // each held frame repeats for the downsample factor
+        for block in crushed.chunks(2 * 3)
         {
-            playback.pause();
+            assert!(block.chunks(2).all(|frame| frame == &block[..2]));
         }
+
+        assert!(engine.apply_bitcrush(0, 1, &[0]).is_err());
+        assert!(engine.apply_bitcrush(8, 0, &[0]).is_err());
     }
 
-    /// Stop audio playback and reset position
-    pub fn stop(&mut self)
+    #[test]
+    fn stereo_width_scales_the_side_signal()
     {
-        if let Some(ref mut playback) = self.playback
+        let data: Vec<f32> = (0..4410)
+            .map(|i| i as f32 * 0.05)
+            .flat_map(|t| [0.5 * t.sin(), 0.3 * (t + 1.0).sin()])
+            .collect();
+        let side_energy = |samples: &[f32]| samples.chunks(2).map(|frame| (frame[0] - frame[1]).powi(2)).sum::<f32>();
+
+        let mut engine = AudioEngine::new();
+        for _ in 0..2
         {
-            playback.stop();
+            engine.add_track_from_samples(data.clone(), 44100, 2, "stereo").unwrap();
         }
+        engine.add_tone_track(440.0, 0.1, 0.5, 44100, 1).unwrap();
+        let mono = engine.tracks[2].audio_data.to_vec();
+
+        engine.apply_stereo_width(0.0, &[0, 2]).unwrap();
+        assert!(engine.tracks[0].audio_data.chunks(2).all(|frame| frame[0] == frame[1]));
+        assert_eq!(engine.tracks[2].audio_data.to_vec(), mono);
+
+        engine.apply_stereo_width(1.5, &[1]).unwrap();
+        assert!(side_energy(&engine.tracks[1].audio_data) > 2.0 * side_energy(&data));
+
+        assert!(engine.apply_stereo_width(-1.0, &[1]).is_err());
     }
 
-    /// Check if audio is currently playing
-    ///
-    /// # Returns
-    /// `bool` - true if playing
-    pub fn is_playing(&self) -> bool
+    #[test]
+    fn remove_hum_notches_mains_and_keeps_the_tone()
     {
-        self.playback.as_ref().map(|p| p.is_playing()).unwrap_or(false)
+        let rate = 44100;
+        let data: Vec<f32> = (0..rate as usize * 2)
+            .map(|i| i as f32 / rate as f32)
+            .map(|t| 0.4 * (2.0 * std::f32::consts::PI * 1000.0 * t).sin() + 0.2 * (2.0 * std::f32::consts::PI * 60.0 * t).sin())
+            .collect();
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(data.clone(), rate, 1, "hum").unwrap();
+
+        engine.remove_hum(60.0, 3, &[0]).unwrap();
+        // skip the filters' settling time
+        let settled = &engine.tracks[0].audio_data[rate as usize / 2..];
+        let reference = &data[rate as usize / 2..];
+
+        let hum_before = band_energy(reference, 1, rate, 55.0, 65.0);
+        let hum_after = band_energy(settled, 1, rate, 55.0, 65.0);
+        assert!(hum_after < hum_before / 100.0, "hum {} -> {}", hum_before, hum_after);
+
+        let tone_before = band_energy(reference, 1, rate, 950.0, 1050.0);
+        let tone_after = band_energy(settled, 1, rate, 950.0, 1050.0);
+        assert!((tone_after / tone_before - 1.0).abs() < 0.05, "tone {} -> {}", tone_before, tone_after);
     }
 
-    /// Get current playback position
-    ///
-    /// # Returns
-    /// `f64` - position in seconds
-    pub fn get_playback_position(&self) -> f64
+    #[test]
+    fn saturation_adds_odd_harmonics_within_bounds()
     {
-        self.playback
-            .as_ref()
-            .map(|p| p.get_position())
-            .unwrap_or(0.0)
+        // (second, third) harmonic energy relative to the fundamental
+        let harmonics = |drive: f64|
+        {
+            let mut engine = AudioEngine::new();
+            engine.add_tone_track(1000.0, 1.0, 0.8, 44100, 1).unwrap();
+            engine.apply_saturation(drive, 1.0, &[0]).unwrap();
+            let data = &engine.tracks[0].audio_data;
+            assert!(data.iter().all(|s| (-1.0..=1.0).contains(s)));
+            let fundamental = band_energy(data, 1, 44100, 950.0, 1050.0);
+            (band_energy(data, 1, 44100, 1950.0, 2050.0) / fundamental, band_energy(data, 1, 44100, 2950.0, 3050.0) / fundamental)
+        };
+
+        let (_, gentle) = harmonics(1.0);
+        let (even, driven) = harmonics(8.0);
+        assert!(driven > gentle * 10.0, "third harmonic {} -> {}", gentle, driven);
+        // tanh is symmetric, so the distortion stays in the odd harmonics
+        assert!(even < driven / 100.0, "second harmonic {}, third {}", even, driven);
+
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(1000.0, 0.1, 0.8, 44100, 1).unwrap();
+        assert!(engine.apply_saturation(0.0, 1.0, &[0]).is_err());
     }
 
-    /// Set playback position
-    ///
-    /// # Parameters
-    /// * `position` - new position in seconds
-    pub fn set_playback_position(&mut self, position: f64)
+    #[test]
+    fn tremolo_envelope_oscillates_at_the_rate()
     {
-        if let Some(ref mut playback) = self.playback
+        for rate in [44100, 48000]
         {
-            playback.set_position(position);
+            let mut engine = AudioEngine::new();
+            engine.add_tone_track(1000.0, 2.0, 0.5, rate, 1).unwrap();
+            engine.apply_tremolo(5.0, 0.8, &[0]).unwrap();
+
+            // the tone's peak per 10 ms, an envelope sampled at 100 Hz
+            let envelope: Vec<f32> = engine.tracks[0]
+                .audio_data
+                .chunks(rate as usize / 100)
+                .map(|window| window.iter().fold(0.0f32, |peak, s| peak.max(s.abs())))
+                .collect();
+            let mean = envelope.iter().sum::<f32>() / envelope.len() as f32;
+            let swing: Vec<f32> = envelope.iter().map(|level| level - mean).collect();
+            // within one bin of the 128-point spectrum
+            assert!((dominant_frequency(&swing, 1, 100) - 5.0).abs() < 0.8, "rate {}", rate);
+
+            let loudest = envelope.iter().cloned().fold(0.0, f32::max);
+            let quietest = envelope.iter().cloned().fold(1.0, f32::min);
+            assert!((loudest - 0.5).abs() < 0.01 && (quietest - 0.1).abs() < 0.02, "{} to {}", quietest, loudest);
         }
     }
 
-    /// Delete a region of audio from specified tracks
-    ///
-    /// # Parameters
-    /// * `start_time` - start of region in seconds
-    /// * `end_time` - end of region in seconds
-    /// * `track_indices` - slice of track indices to delete from
-    ///
-    /// # Returns
-    /// `Result<(), String>` - Ok if successful
-    pub fn delete_region(&mut self, start_time: f64, end_time: f64, track_indices: &[usize]) -> Result<(), String>
+    #[test]
+    fn vibrato_modulates_frequency_at_the_rate()
     {
-        for &track_idx in track_indices
-        {
-            if track_idx >= self.tracks.len()
-            {
-                continue;
-            }
+        let rate = 44100;
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(1000.0, 2.0, 0.5, rate, 2).unwrap();
+        engine.apply_vibrato(4.0, 2.0, &[0]).unwrap();
+        let data = &engine.tracks[0].audio_data;
+        assert!(data.chunks(2).all(|frame| frame[0] == frame[1]));
+
+        // instantaneous frequency from the spacing of interpolated upward zero crossings
+        let left: Vec<f32> = data.chunks(2).map(|frame| frame[0]).collect();
+        let crossings: Vec<f64> = left
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[0] < 0.0 && pair[1] >= 0.0)
+            .map(|(i, pair)| i as f64 + (pair[0] / (pair[0] - pair[1])) as f64)
+            .collect();
+        let frequencies: Vec<f32> = crossings.windows(2).map(|pair| (rate as f64 / (pair[1] - pair[0])) as f32).collect();
+
+        let mean = frequencies.iter().sum::<f32>() / frequencies.len() as f32;
+        assert!((mean - 1000.0).abs() < 5.0, "carrier {}", mean);
+        // a 2 ms swing at 4 Hz bends a 1 kHz tone by about 50 Hz either way
+        let highest = frequencies.iter().cloned().fold(0.0, f32::max);
+        let lowest = frequencies.iter().cloned().fold(f32::MAX, f32::min);
+        assert!(highest > 1040.0 && lowest < 960.0, "{} to {}", lowest, highest);
+
+        // one frequency reading per carrier cycle, so the readings arrive at about 1 kHz
+        let swing: Vec<f32> = frequencies.iter().map(|f| f - mean).collect();
+        assert!((dominant_frequency(&swing, 1, 1000) - 4.0).abs() < 1.0);
+    }
 
-            let track = &mut self.tracks[track_idx];
-            let start_frame = (start_time * track.sample_rate as f64) as usize;
-            let end_frame = (end_time * track.sample_rate as f64) as usize;
+    #[test]
+    fn chorus_changes_the_sound_without_growing_it()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 1.0, 0.9, 44100, 2).unwrap();
+        let dry = engine.tracks[0].audio_data.to_vec();
+
+        engine.apply_chorus(3, 0.8, 5.0, 0.5, &[0]).unwrap();
+        let wet = engine.tracks[0].audio_data.to_vec();
+        assert_eq!(wet.len(), dry.len());
+        assert!(wet.iter().all(|s| (-1.0..=1.0).contains(s)));
+        let difference = wet.iter().zip(&dry).map(|(w, d)| (w - d).powi(2)).sum::<f32>() / dry.len() as f32;
+        assert!(difference.sqrt() > 0.05, "rms difference {}", difference.sqrt());
+
+        assert!(engine.apply_chorus(0, 0.8, 5.0, 0.5, &[0]).is_err());
+        assert!(engine.apply_chorus(3, 0.0, 5.0, 0.5, &[0]).is_err());
+        assert!(engine.apply_chorus(3, 0.8, 1000.0, 0.5, &[0]).is_err());
+    }
 
-            let start_sample = start_frame * track.channels;
-            let end_sample = end_frame * track.channels;
+    #[test]
+    fn click_removal_interpolates_a_spike()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 1.0, 0.5, 44100, 1).unwrap();
+        let clean = engine.tracks[0].audio_data.to_vec();
+        let spike = 22050;
+        engine.tracks[0].audio_data.to_mut()[spike] = 0.95;
+
+        assert_eq!(engine.remove_clicks(&[0], 0.5), Ok(1));
+        let repaired = &engine.tracks[0].audio_data;
+        let neighbours = (repaired[spike - 1] + repaired[spike + 1]) / 2.0;
+        assert!((repaired[spike] - neighbours).abs() < 0.01);
+        assert!((repaired[spike] - clean[spike]).abs() < 0.01);
+        // the rest of the tone is left alone
+        let changed: Vec<usize> = (0..clean.len()).filter(|&i| repaired[i] != clean[i]).collect();
+        assert!(changed.iter().all(|&i| i.abs_diff(spike) <= 2), "{:?}", changed);
+    }
 
-            if start_sample >= track.audio_data.len()
-            {
-                continue;
-            }
+    #[test]
+    fn noise_reduction_lowers_the_noise_floor()
+    {
+        let rate = 44100;
+        let mut rng = crate::generators::NoiseRng::new(21);
+        // one second of noise alone, then the same noise under a tone
+        let data: Vec<f32> = (0..rate as usize * 3)
+            .map(|i| if i < rate as usize { 0.0 } else { 0.5 * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / rate as f32).sin() })
+            .map(|tone| tone + 0.05 * rng.next_sample())
+            .collect();
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(data.clone(), rate, 1, "noisy").unwrap();
+        assert!(engine.apply_noise_reduction(&[0], 1.0).is_err());
+
+        engine.learn_noise_profile(0, 0.0, 1.0).unwrap();
+        engine.apply_noise_reduction(&[0], 1.0).unwrap();
+
+        let before = &data[rate as usize * 2..];
+        let after = &engine.tracks[0].audio_data[rate as usize * 2..];
+        let floor_before = band_energy(before, 1, rate, 3000.0, 15000.0);
+        let floor_after = band_energy(after, 1, rate, 3000.0, 15000.0);
+        assert!(floor_after < floor_before / 4.0, "noise floor {} -> {}", floor_before, floor_after);
+
+        let tone_before = band_energy(before, 1, rate, 950.0, 1050.0);
+        let tone_after = band_energy(after, 1, rate, 950.0, 1050.0);
+        assert!(tone_after > tone_before * 0.8, "tone {} -> {}", tone_before, tone_after);
+    }
 
-            let end_sample = end_sample.min(track.audio_data.len());
-            track.audio_data.drain(start_sample..end_sample);
+    #[test]
+    fn graphic_eq_boosts_only_its_band()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_noise_track(2.0, "white", 8, 44100, 1).unwrap();
+        // leave headroom so the boost is not clamped
+        engine.tracks[0].audio_data.iter_mut().for_each(|sample| *sample *= 0.25);
+        let noise = engine.tracks[0].audio_data.to_vec();
+
+        let mut gains_db = vec![0.0; filters::GRAPHIC_EQ_BANDS_HZ.len()];
+        let band = filters::GRAPHIC_EQ_BANDS_HZ.iter().position(|&hz| hz == 1000.0).unwrap();
+        gains_db[band] = 12.0;
+        engine.apply_graphic_eq(&gains_db, &[0]).unwrap();
+        let boosted = &engine.tracks[0].audio_data;
+
+        let change = |low_hz, high_hz| band_energy(boosted, 1, 44100, low_hz, high_hz) / band_energy(&noise, 1, 44100, low_hz, high_hz);
+        assert!(change(900.0, 1100.0) > 10.0, "1 kHz x{}", change(900.0, 1100.0));
+        for (low_hz, high_hz) in [(100.0, 150.0), (8000.0, 12000.0)]
+        {
+            let ratio = change(low_hz, high_hz);
+            assert!((0.8..1.25).contains(&ratio), "{}-{} Hz x{}", low_hz, high_hz, ratio);
         }
 
-        Ok(())
+        assert!(engine.apply_graphic_eq(&[0.0; 3], &[0]).is_err());
     }
 
-    /// Export audio to a file
-    ///
-    /// # Parameters
-    /// * `path` - output file path with extension (.wav, .flac, or .mp3)
-    /// * `start_time` - optional start time in seconds (None for beginning)
-    /// * `end_time` - optional end time in seconds (None for end)
-    /// * `compression_level` - optional FLAC compression level 0-8 (None for default 5)
-    /// * `bitrate_kbps` - optional MP3 bitrate in kbps (None for default 192)
-    /// * `channel_mode` - optional channel mode ('stereo', 'mono', 'split', 'mono_to_stereo')
-    ///
-    /// # Returns
-    /// `Result<(), String>` - Ok if successful
-    ///
-    /// # Notes
-    /// Format is determined by file extension. All tracks are mixed together for export.
-    /// Split mode creates multiple files with _L and _R suffixes.
-    pub fn export_audio(&self, path: &str, start_time: Option<f64>, end_time: Option<f64>,
-                        compression_level: Option<u8>, bitrate_kbps: Option<u32>,
-                        channel_mode: Option<String>) -> Result<(), String>
+    #[test]
+    fn exported_loop_repeats_without_a_click()
     {
-        let duration = self.get_duration();
-        let start = start_time.unwrap_or(0.0);
-        let end = end_time.unwrap_or(duration);
+        let dir = TempDir::new().unwrap();
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(437.0, 3.0, 0.5, 44100, 1).unwrap();
+        let tone_step = max_step(&engine.tracks[0].audio_data, 1);
 
-        let mode = channel_mode.as_deref().unwrap_or("auto");
-        let export_items = if mode == "auto"
+        // a region that ends mid-cycle, so cutting it plainly leaves a jump at the seam
+        let seam_step = |crossfade_ms: f64|
         {
-            let (data, rate, channels) = self.mix_tracks_for_playback(start, end);
-            vec![(data, rate, channels, String::new())]
-        }
-        else
-        {
-            self.mix_tracks_for_export(start, end, mode)
+            let path = dir.path().join(format!("loop_{}.wav", crossfade_ms));
+            let items = engine.render_loop(0.5, 1.8317, crossfade_ms).unwrap();
+            let options = ExportOptions { bit_depth: Some(32), ..Default::default() };
+            AudioEngine::write_export(path.to_str().unwrap(), items, &options, None).unwrap();
+            let looped: Vec<f32> = hound::WavReader::open(path).unwrap().samples::<f32>().map(Result::unwrap).collect();
+            let twice: Vec<f32> = looped.iter().chain(&looped).cloned().collect();
+            max_step(&twice, 1)
         };
 
-        let path_lower = path.to_lowercase();
-        let (base_path, extension) = if let Some(pos) = path.rfind('.')
+        assert!(seam_step(0.0) > tone_step * 3.0);
+        assert!(seam_step(20.0) < tone_step * 1.5, "seam {} against {}", seam_step(20.0), tone_step);
+    }
+
+    #[test]
+    fn track_md5_survives_a_flac_round_trip()
+    {
+        let dir = TempDir::new().unwrap();
+        let mut source = AudioEngine::new();
+        source.add_tone_track(440.0, 0.5, 0.9, 44100, 2).unwrap();
+
+        for bit_depth in [16u8, 24]
         {
-            (&path[..pos], &path[pos..])
+            let path = dir.path().join(format!("round_trip_{}.flac", bit_depth));
+            let options = ExportOptions { bit_depth: Some(bit_depth as u16), ..Default::default() };
+            let items = source.render_export(None, None, None, MonoFold::default(), None);
+            AudioEngine::write_export(path.to_str().unwrap(), items, &options, None).unwrap();
+
+            let mut reloaded = AudioEngine::new();
+            reloaded.load_file(path.to_str().unwrap(), false).unwrap();
+            // None picks up the bit depth the file was loaded at
+            let digest = reloaded.get_track_md5(0, None).unwrap();
+            assert_eq!(digest, source.get_track_md5(0, Some(bit_depth)).unwrap(), "{} bits", bit_depth);
+
+            // STREAMINFO's MD5 follows 18 bytes of stream parameters
+            let bytes = std::fs::read(&path).unwrap();
+            let stored: String = bytes[26..42].iter().map(|byte| format!("{:02x}", byte)).collect();
+            assert_eq!(digest, stored, "{} bits", bit_depth);
         }
-        else
-        {
-            (path, "")
-        };
 
-        for (export_data, sample_rate, channels, suffix) in export_items
-        {
-            let final_path = if suffix.is_empty()
-            {
-                path.to_string()
-            }
-            else
-            {
-                format!("{}{}{}", base_path, suffix, extension)
-            };
+        assert!(source.get_track_md5(0, Some(8)).is_err());
+        assert!(source.get_track_md5(1, None).is_err());
+    }
 
-            if path_lower.ends_with(".wav")
-            {
-                self.export_wav(&final_path, &export_data, sample_rate, channels)?;
-            }
-            else if path_lower.ends_with(".flac")
-            {
-                self.export_flac(&final_path, &export_data, sample_rate, channels, compression_level.unwrap_or(5))?;
-            }
-            else if path_lower.ends_with(".mp3")
-            {
-                self.export_mp3(&final_path, &export_data, sample_rate, channels, bitrate_kbps.unwrap_or(192))?;
-            }
-            else
+    #[test]
+    fn ab_loop_wraps_from_b_to_a()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 1.0, 0.5, 1000, 1).unwrap();
+        let mut playback = headless(1000, 1);
+        playback.play(engine.tracks[0].audio_data.to_vec(), 0.0).unwrap();
+        engine.playback = Some(playback);
+
+        // markers given backwards are swapped
+        engine.set_ab_loop(0.6, 0.3).unwrap();
+        assert_eq!(engine.get_ab_loop(), Some((0.3, 0.6)));
+
+        let mut wraps = 0;
+        let mut previous = 0.0;
+        for _ in 0..200
+        {
+            render_frames(engine.playback.as_ref().unwrap(), 10);
+            let position = engine.get_playback_position();
+            assert!(position < 0.6, "position {}", position);
+            if position < previous
             {
-                return Err("Unsupported format. Use .wav, .flac, or .mp3".to_string());
+                assert!((0.3..0.31).contains(&position), "wrapped to {}", position);
+                wraps += 1;
             }
+            previous = position;
         }
+        // two seconds of playback go round the 0.3 s loop several times
+        assert!(wraps >= 5);
 
-        Ok(())
+        engine.clear_ab_loop();
+        render_frames(engine.playback.as_ref().unwrap(), 1000);
+        assert!(engine.playback.as_ref().unwrap().has_finished());
+        assert!(engine.set_ab_loop(0.5, 0.5).is_err());
     }
 
-    /// Export audio as WAV file
-    ///
-    /// # Parameters
-    /// * `path` - output file path
-    /// * `data` - audio sample data
-    /// * `sample_rate` - sample rate in Hz
-    /// * `channels` - number of channels
-    ///
-    /// # Returns
-    /// `Result<(), String>` - Ok if successful
-    fn export_wav(&self, path: &str, data: &[f32], sample_rate: u32, channels: usize) -> Result<(), String>
+    #[test]
+    fn normalize_mix_brings_a_clipping_sum_to_target()
     {
-        let spec = hound::WavSpec
-        {
-            channels: channels as u16,
-            sample_rate,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 1.0, 0.7, 44100, 2).unwrap();
+        engine.add_tone_track(440.0, 1.0, 0.6, 44100, 2).unwrap();
+        let balance = engine.tracks[0].audio_data[100] / engine.tracks[1].audio_data[100];
+
+        // the tones are in phase, so the sum peaks at 1.3 before normalizing
+        let gain_db = engine.normalize_mix(-1.0, None, None).unwrap();
+        assert!((gain_db - 20.0 * (10f64.powf(-1.0 / 20.0) / 1.3).log10()).abs() < 0.01);
+
+        let (mix, _, _) = AudioEngine::sum_tracks(&engine.tracks, 0.0, 1.0);
+        let peak = mix.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!((20.0 * peak.log10() + 1.0).abs() < 0.01, "peak {}", peak);
+        assert!((engine.tracks[0].audio_data[100] / engine.tracks[1].audio_data[100] - balance).abs() < 1e-5);
+
+        assert!(engine.normalize_mix(1.0, None, None).is_err());
+        assert!(engine.normalize_mix(-1.0, Some(5.0), Some(6.0)).is_err());
+    }
 
-        let mut writer = hound::WavWriter::create(path, spec)
-            .map_err(|e| format!("Failed to create WAV file: {}", e))?;
+    #[test]
+    fn bus_limiter_keeps_an_over_level_mix_clean()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(1000.0, 2.0, 0.8, 44100, 1).unwrap();
+        engine.add_tone_track(1000.0, 2.0, 0.8, 44100, 1).unwrap();
 
-        for &sample in data
-        {
-            let sample_i16 = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-            writer.write_sample(sample_i16)
-                  .map_err(|e| format!("Failed to write sample: {}", e))?;
-        }
+        let render = |bus_limiter| engine.render_export(None, None, None, MonoFold::default(), bus_limiter).remove(0).0;
+        let clamped = render(None);
+        let limited = render(Some(BusLimiter::new(-1.0, 200.0).unwrap()));
 
-        writer.finalize()
-              .map_err(|e| format!("Failed to finalize WAV: {}", e))?;
+        let ceiling = 10f32.powf(-1.0 / 20.0);
+        assert!(limited.iter().all(|s| s.abs() <= ceiling + 1e-4));
 
-        Ok(())
+        // odd harmonic energy against the fundamental, once the limiter has settled
+        let distortion = |data: &[f32]|
+        {
+            let settled = &data[22050..];
+            band_energy(settled, 1, 44100, 2900.0, 3100.0) / band_energy(settled, 1, 44100, 900.0, 1100.0)
+        };
+        assert!(distortion(&limited) < distortion(&clamped) / 100.0, "{} against {}", distortion(&limited), distortion(&clamped));
+
+        assert!(BusLimiter::new(1.0, 200.0).is_err());
     }
 
-    /// Export audio as FLAC file
-    ///
-    /// # Parameters
-    /// * `path` - output file path
-    /// * `data` - audio sample data
-    /// * `sample_rate` - sample rate in Hz
-    /// * `channels` - number of channels
-    /// * `compression_level` - compression level 0-8
-    ///
-    /// # Returns
-    /// `Result<(), String>` - Ok if successful
-    fn export_flac(&self, path: &str, data: &[f32], sample_rate: u32, channels: usize, compression_level: u8) -> Result<(), String>
+    #[test]
+    fn track_details_report_source_bit_depth_and_codec()
     {
-        use std::path::Path;
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("sixteen.wav");
+        write_wav(&path, &vec![0.25; 4410 * 2], 44100, 2);
+
+        let mut engine = AudioEngine::new();
+        engine.load_file(path.to_str().unwrap(), false).unwrap();
+        let (name, sample_rate, channels, duration, offset, bits_per_sample, codec) = engine.get_track_details(0).unwrap();
+        assert_eq!((name.as_str(), sample_rate, channels, offset), ("sixteen.wav", 44100, 2, 0.0));
+        assert!((duration - 0.1).abs() < 1e-9);
+        assert_eq!(bits_per_sample, Some(16));
+        assert!(codec.is_some_and(|codec| codec.to_lowercase().contains("pcm")));
+
+        // synthesized audio has no source format
+        engine.add_tone_track(440.0, 0.1, 0.5, 44100, 1).unwrap();
+        let details = engine.get_track_details(1).unwrap();
+        assert_eq!((details.5, details.6), (None, None));
+    }
 
-        crate::flac::export_to_flac_with_level(
-            Path::new(path),
-            data,
-            sample_rate,
-            channels as u16,
-            compression_level,
-        )
-            .map_err(|e| format!("Failed to export FLAC: {}", e))?;
+    #[test]
+    fn crossfaded_delete_joins_smoothly()
+    {
+        // the region ends mid-cycle, so a butt join jumps
+        let deleted = |crossfade_ms: f64, offset: f64|
+        {
+            let mut engine = AudioEngine::new();
+            engine.add_tone_track(437.0, 2.0, 0.5, 44100, 1).unwrap();
+            engine.set_track_offset(0, offset).unwrap();
+            engine.delete_region(offset + 0.5, offset + 0.7313, &[0], crossfade_ms).unwrap();
+            engine.tracks[0].audio_data.to_vec()
+        };
 
-        Ok(())
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(437.0, 2.0, 0.5, 44100, 1).unwrap();
+        let tone_step = max_step(&engine.tracks[0].audio_data, 1);
+
+        let butt = deleted(0.0, 0.0);
+        let faded = deleted(10.0, 0.0);
+        // frames 22050 to 32250 are removed
+        assert_eq!(butt.len(), 88200 - (32250 - 22050));
+        assert_eq!(faded.len(), butt.len());
+        assert!(max_step(&butt, 1) > tone_step * 3.0);
+        assert!(max_step(&faded, 1) < tone_step * 1.5, "join step {} against {}", max_step(&faded, 1), tone_step);
+
+        // the region is found on the timeline, so an offset track loses the same audio
+        assert_eq!(deleted(10.0, 1.0), faded);
+        assert!(engine.delete_region(0.5, 0.7, &[0], -1.0).is_err());
     }
 
-    /// Export audio as MP3 file
-    ///
-    /// # Parameters
-    /// * `path` - output file path
-    /// * `data` - audio sample data
-    /// * `sample_rate` - sample rate in Hz
-    /// * `channels` - number of channels
-    /// * `bitrate_kbps` - bitrate in kbps (128, 160, 192, 256, or 320)
-    ///
-    /// # Returns
-    /// `Result<(), String>` - Ok if successful
-    fn export_mp3(&self, path: &str, data: &[f32], sample_rate: u32, channels: usize, bitrate_kbps: u32) -> Result<(), String>
+    #[test]
+    fn appended_file_continues_the_take()
     {
-        use mp3lame_encoder::{Builder, InterleavedPcm, FlushNoGap, Bitrate};
-        use std::mem::MaybeUninit;
-
-        // convert to i16 samples
-        let mut samples_i16 = Vec::with_capacity(data.len());
-        for &sample in data
-        {
-            let sample_i16 = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-            samples_i16.push(sample_i16);
-        }
+        let dir = TempDir::new().unwrap();
+        let take: Vec<f32> = (0..66150).map(|i| 0.5 * (i as f32 * 0.0623).sin()).flat_map(|s| [s, s]).collect();
+        // split mid-cycle, the way a recorder splits at a size limit
+        let (first, second) = take.split_at(33075 * 2);
+        write_wav(&dir.path().join("take_1.wav"), first, 44100, 2);
+        write_wav(&dir.path().join("take_2.wav"), second, 44100, 2);
+
+        let mut engine = AudioEngine::new();
+        engine.load_file(dir.path().join("take_1.wav").to_str().unwrap(), false).unwrap();
+        let appended = engine.load_file_append(dir.path().join("take_2.wav").to_str().unwrap(), 0).unwrap();
+        assert!((appended - 0.75).abs() < 1e-9);
+        assert!((engine.get_duration() - 1.5).abs() < 1e-9);
+
+        let joined = &engine.tracks[0].audio_data;
+        assert!(joined.iter().zip(&take).all(|(a, b)| (a - b).abs() < 1e-4));
+        let boundary = &joined[(33075 - 10) * 2..(33075 + 10) * 2];
+        // no step larger than the tone's own steepest slope
+        assert!(max_step(boundary, 2) < 0.5 * 0.0623 * 1.01);
+
+        assert!(engine.load_file_append(dir.path().join("take_2.wav").to_str().unwrap(), 1).is_err());
+    }
 
-        let mut mp3_encoder = Builder::new()
-            .ok_or("Failed to create MP3 encoder")?;
+    #[test]
+    fn agc_lifts_the_quiet_passage_up_to_the_cap()
+    {
+        let rms_db = |data: &[f32]| 10.0 * (data.iter().map(|s| s * s).sum::<f32>() / data.len() as f32).log10();
+        let data: Vec<f32> = (0..44100 * 4)
+            .map(|i| if i < 44100 * 2 { 0.5 } else { 0.02 } * (i as f32 * 0.0627).sin())
+            .collect();
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(data.clone(), 44100, 1, "speech").unwrap();
+        engine.apply_agc(-20.0, 12.0, 400.0, &[0]).unwrap();
+        let levelled = &engine.tracks[0].audio_data;
+
+        // well inside each passage, away from the window straddling the change
+        let loud = 22050..66150;
+        let quiet = 110250..154350;
+        let quiet_boost = rms_db(&levelled[quiet.clone()]) - rms_db(&data[quiet]);
+        assert!(quiet_boost > 11.5 && quiet_boost <= 12.01, "quiet passage boosted {} dB", quiet_boost);
+        assert!((rms_db(&levelled[loud]) + 20.0).abs() < 1.0);
+        assert!(levelled.iter().all(|s| s.abs() <= 1.0));
+
+        assert!(engine.apply_agc(-20.0, -1.0, 400.0, &[0]).is_err());
+    }
 
-        mp3_encoder.set_sample_rate(sample_rate)
-                   .map_err(|e| format!("Failed to set sample rate: {:?}", e))?;
+    #[test]
+    fn deesser_turns_down_only_the_sibilance_band()
+    {
+        let rate = 44100;
+        let data: Vec<f32> = (0..rate as usize * 2)
+            .map(|i| (i, i as f32 / rate as f32))
+            .map(|(i, t)|
+            {
+                let voice = 0.3 * (2.0 * std::f32::consts::PI * 300.0 * t).sin();
+                let burst = if (22050..66150).contains(&i) { 0.5 * (2.0 * std::f32::consts::PI * 6500.0 * t).sin() } else { 0.0 };
+                voice + burst
+            })
+            .collect();
+        let mut engine = AudioEngine::new();
+        engine.add_track_from_samples(data.clone(), rate, 1, "vocal").unwrap();
+        engine.apply_deesser(6500.0, -30.0, 8.0, &[0]).unwrap();
+
+        // the middle of the burst, after the detector has reacted
+        let before = &data[33075..55125];
+        let after = &engine.tracks[0].audio_data[33075..55125];
+        let sibilance = band_energy(after, 1, rate, 6000.0, 7000.0) / band_energy(before, 1, rate, 6000.0, 7000.0);
+        assert!(sibilance < 0.25, "sibilance x{}", sibilance);
+        let voice = band_energy(after, 1, rate, 250.0, 350.0) / band_energy(before, 1, rate, 250.0, 350.0);
+        assert!((voice - 1.0).abs() < 0.05, "voice x{}", voice);
+
+        assert!(engine.apply_deesser(6500.0, -30.0, 0.5, &[0]).is_err());
+    }
 
-        mp3_encoder.set_num_channels(channels as u8)
-                   .map_err(|e| format!("Failed to set channels: {:?}", e))?;
+    #[test]
+    fn loaded_bytes_match_loaded_file()
+    {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("memory.wav");
+        let data: Vec<f32> = generators::white_noise(4410, 2, 12).iter().map(|&s| s * 0.5).collect();
+        write_wav(&path, &data, 48000, 2);
+
+        let mut engine = AudioEngine::new();
+        let from_file = engine.load_file(path.to_str().unwrap(), false).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(engine.load_bytes(&bytes, Some(".wav")).unwrap(), from_file);
+        // symphonia probes the content, so the hint is optional
+        assert_eq!(engine.load_bytes(&bytes, None).unwrap(), from_file);
+
+        assert_eq!(engine.tracks[1].audio_data.to_vec(), engine.tracks[0].audio_data.to_vec());
+        assert_eq!(engine.tracks[2].audio_data.to_vec(), engine.tracks[0].audio_data.to_vec());
+        assert_eq!(engine.get_track_details(1).unwrap().0, "Untitled.wav");
+        assert_eq!(engine.get_track_details(1).unwrap().5, Some(16));
+
+        assert!(engine.load_bytes(b"not audio", Some("wav")).is_err());
+    }
 
-        let bitrate = match bitrate_kbps
+    #[test]
+    fn sinc_best_aliases_less_than_linear()
+    {
+        // a 15 kHz tone is above the new Nyquist frequency and folds down to 7.05 kHz
+        let alias_energy = |quality: ResampleQuality|
         {
-            128 => Bitrate::Kbps128,
-            160 => Bitrate::Kbps160,
-            192 => Bitrate::Kbps192,
-            256 => Bitrate::Kbps256,
-            320 => Bitrate::Kbps320,
-            _ => Bitrate::Kbps192,
+            let mut engine = AudioEngine::new();
+            engine.set_resample_quality(quality);
+            engine.add_tone_track(15000.0, 1.0, 0.5, 44100, 1).unwrap();
+            engine.add_tone_track(1000.0, 1.0, 0.5, 44100, 1).unwrap();
+            engine.resample_track(0, 22050).unwrap();
+            engine.resample_track(1, 22050).unwrap();
+            // against a tone that survives, so both qualities are measured on the same scale
+            band_energy(&engine.tracks[0].audio_data, 1, 22050, 6950.0, 7150.0) / band_energy(&engine.tracks[1].audio_data, 1, 22050, 950.0, 1050.0)
         };
 
-        mp3_encoder.set_brate(bitrate)
-                   .map_err(|e| format!("Failed to set bitrate: {:?}", e))?;
-
-        mp3_encoder.set_quality(mp3lame_encoder::Quality::Good)
-                   .map_err(|e| format!("Failed to set quality: {:?}", e))?;
-
-        let mut mp3_encoder = mp3_encoder.build()
-                                         .map_err(|e| format!("Failed to build encoder: {:?}", e))?;
+        let linear = alias_energy(ResampleQuality::Linear);
+        let sinc_best = alias_energy(ResampleQuality::SincBest);
+        assert!(linear > 0.01, "linear alias {}", linear);
+        assert!(sinc_best < linear / 1000.0, "sinc {} against linear {}", sinc_best, linear);
+    }
 
-        let input = InterleavedPcm(&samples_i16);
-        let mut mp3_out = Vec::new();
+    #[test]
+    fn flac_24_bit_keeps_detail_of_a_quiet_signal()
+    {
+        let dir = TempDir::new().unwrap();
+        // about -70 dBFS, only a few 16-bit steps tall
+        let quiet: Vec<f32> = (0..44100).map(|i| 0.0003 * (i as f32 * 0.0627).sin()).collect();
 
-        // calculate proper buffer size: 1.25 * num_samples + 7200
-        let buffer_size = (samples_i16.len() * 5 / 4 + 7200).max(16384);
-        let mut output: Vec<MaybeUninit<u8>> = vec![MaybeUninit::uninit(); buffer_size];
+        let error_db = |bit_depth: u16, flac_dither: bool|
+        {
+            let path = dir.path().join(format!("quiet_{}_{}.flac", bit_depth, flac_dither));
+            let options = ExportOptions { bit_depth: Some(bit_depth), flac_dither, ..Default::default() };
+            AudioEngine::write_export(path.to_str().unwrap(), vec![(quiet.clone(), 44100, 1, String::new())], &options, None).unwrap();
+
+            let mut engine = AudioEngine::new();
+            engine.load_file(path.to_str().unwrap(), false).unwrap();
+            assert_eq!(engine.get_track_details(0).unwrap().5, Some(bit_depth as u32));
+            let restored = &engine.tracks[0].audio_data;
+            let error = restored.iter().zip(&quiet).map(|(r, q)| (r - q).powi(2)).sum::<f32>() / quiet.len() as f32;
+            10.0 * error.log10()
+        };
 
-        let encoded_size = mp3_encoder.encode(input, &mut output[..])
-                                      .map_err(|e| format!("Failed to encode MP3: {:?}", e))?;
+        let sixteen = error_db(16, false);
+        let twenty_four = error_db(24, false);
+        // each extra bit halves the quantization step, about 6 dB
+        assert!(twenty_four < sixteen - 40.0, "24-bit error {} dB against 16-bit {} dB", twenty_four, sixteen);
+        assert!(error_db(24, true) < sixteen - 40.0);
+    }
 
-        // safely convert MaybeUninit to initialized bytes
-        for i in 0..encoded_size
+    #[test]
+    fn frame_and_time_conversions_round_trip()
+    {
+        let mut engine = AudioEngine::new();
+        engine.add_tone_track(440.0, 1.0, 0.5, 44100, 2).unwrap();
+        engine.add_tone_track(440.0, 1.0, 0.5, 48000, 1).unwrap();
+        engine.set_track_offset(1, 0.3).unwrap();
+        assert_eq!(engine.get_track_frame_count(0), Ok(44100));
+        assert_eq!(engine.get_track_frame_count(1), Ok(48000));
+
+        for track_index in [0, 1]
         {
-            unsafe
+            let frames = engine.get_track_frame_count(track_index).unwrap();
+            let offset = engine.tracks[track_index].start_offset;
+            let frame_seconds = 1.0 / engine.tracks[track_index].sample_rate as f64;
+
+            // every frame maps to a time that maps back to the same frame
+            for frame in (0..=frames).step_by(997).chain([frames])
             {
-                mp3_out.push(output[i].assume_init());
+                let time = engine.frame_to_time(track_index, frame).unwrap();
+                assert_eq!(engine.time_to_frame(track_index, time), Ok(frame));
+            }
+            // and any time lands within one frame of where it started
+            for step in 0..1000
+            {
+                let time = offset + step as f64 * 0.000731;
+                let frame = engine.time_to_frame(track_index, time).unwrap();
+                let back = engine.frame_to_time(track_index, frame).unwrap();
+                assert!((back - time).abs() < frame_seconds, "{} -> {} -> {}", time, frame, back);
             }
         }
 
-        let _flushed_size = mp3_encoder.flush_to_vec::<FlushNoGap>(&mut mp3_out)
-                                       .map_err(|e| format!("Failed to flush MP3: {:?}", e))?;
-
-        let mut file = File::create(path)
-            .map_err(|e| format!("Failed to create MP3 file: {}", e))?;
-        file.write_all(&mp3_out)
-            .map_err(|e| format!("Failed to write MP3 file: {}", e))?;
-
-        Ok(())
+        // times before and after a track clamp to its ends
+        assert_eq!(engine.time_to_frame(1, 0.0), Ok(0));
+        assert_eq!(engine.time_to_frame(1, 5.0), Ok(48000));
+        assert!(engine.time_to_frame(0, f64::NAN).is_err());
+        assert!(engine.frame_to_time(2, 0).is_err());
     }
-}
\ No newline at end of file
+}
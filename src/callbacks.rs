@@ -0,0 +1,92 @@
+//! Python callbacks driven from background threads
+
+use pyo3::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use crate::playback::PositionProbe;
+
+/// Periodically reports the playback position to a Python callable
+///
+/// # Notes
+/// Runs on its own timer thread rather than the realtime audio callback, so the
+/// GIL is only taken while the callable runs. Exceptions raised by the callable
+/// are printed and do not affect playback. The thread exits when this is dropped.
+pub struct PositionCallback
+{
+    probe: Arc<Mutex<Option<PositionProbe>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl PositionCallback
+{
+    /// Start reporting the position
+    ///
+    /// # Parameters
+    /// * `callback` - Python callable taking the position in seconds
+    /// * `interval_ms` - time between updates in milliseconds
+    /// * `probe` - probe for the current output stream, if any
+    ///
+    /// # Returns
+    /// `PositionCallback` - handle that stops the timer thread when dropped
+    pub fn start(callback: PyObject, interval_ms: u64, probe: Option<PositionProbe>) -> Self
+    {
+        let probe = Arc::new(Mutex::new(probe));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread_probe = probe.clone();
+        let thread_stopped = stopped.clone();
+        let interval = Duration::from_millis(interval_ms.max(1));
+
+        thread::spawn(move ||
+        {
+            while !thread_stopped.load(Ordering::Relaxed)
+            {
+                thread::sleep(interval);
+
+                let position = match thread_probe.lock().unwrap().as_ref()
+                {
+                    Some(probe) if probe.is_playing() => probe.position(),
+                    _ => continue,
+                };
+
+                Python::with_gil(|py|
+                {
+                    // the callback may have been cleared while waiting for the GIL
+                    if thread_stopped.load(Ordering::Relaxed)
+                    {
+                        return;
+                    }
+                    if let Err(e) = callback.call1(py, (position,))
+                    {
+                        e.print(py);
+                    }
+                });
+            }
+        });
+
+        PositionCallback
+        {
+            probe,
+            stopped,
+        }
+    }
+
+    /// Point the timer thread at a new output stream
+    ///
+    /// # Parameters
+    /// * `probe` - probe for the current output stream, if any
+    pub fn set_probe(&self, probe: Option<PositionProbe>)
+    {
+        *self.probe.lock().unwrap() = probe;
+    }
+}
+
+impl Drop for PositionCallback
+{
+    fn drop(&mut self)
+    {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,164 @@
+//! Spectral noise reduction against a learned noise profile
+
+use crate::fft::{fft, hann_window};
+
+/// STFT frame length in samples
+const FRAME_SIZE: usize = 2048;
+
+/// Hop between STFT frames in samples (75% overlap)
+const HOP_SIZE: usize = FRAME_SIZE / 4;
+
+/// Lowest gain applied to a frequency bin, limiting "musical noise" artifacts (about -26 dB)
+const MIN_BIN_GAIN: f64 = 0.05;
+
+/// Average magnitude spectrum of a stretch of background noise
+pub struct NoiseProfile
+{
+    sample_rate: u32,  // rate of the audio the profile was learned from
+    magnitudes: Vec<f64>,  // mean windowed magnitude of bins 0..=FRAME_SIZE/2
+}
+
+impl NoiseProfile
+{
+    /// Learn a profile from audio containing only noise
+    ///
+    /// # Parameters
+    /// * `data` - interleaved samples
+    /// * `channels` - number of interleaved channels
+    /// * `sample_rate` - sample rate in Hz
+    ///
+    /// # Returns
+    /// `Result<NoiseProfile, String>` - Ok with the profile, Err if the audio is shorter than one STFT frame
+    ///
+    /// # Notes
+    /// Magnitudes are averaged over every frame of every channel
+    pub fn learn(data: &[f32], channels: usize, sample_rate: u32) -> Result<Self, String>
+    {
+        let frames = data.len() / channels;
+        if frames < FRAME_SIZE
+        {
+            return Err(format!("Noise region must be at least {} samples long, got {}", FRAME_SIZE, frames));
+        }
+
+        let window = hann_window(FRAME_SIZE);
+        let mut magnitudes = vec![0.0; FRAME_SIZE / 2 + 1];
+        let mut count = 0;
+        let mut re = vec![0.0; FRAME_SIZE];
+        let mut im = vec![0.0; FRAME_SIZE];
+
+        for ch in 0..channels
+        {
+            for start in (0..=frames - FRAME_SIZE).step_by(HOP_SIZE)
+            {
+                for (i, (r, w)) in re.iter_mut().zip(&window).enumerate()
+                {
+                    *r = data[(start + i) * channels + ch] as f64 * w;
+                }
+                im.fill(0.0);
+                fft(&mut re, &mut im, false);
+
+                for (k, magnitude) in magnitudes.iter_mut().enumerate()
+                {
+                    *magnitude += re[k].hypot(im[k]);
+                }
+                count += 1;
+            }
+        }
+
+        for magnitude in &mut magnitudes
+        {
+            *magnitude /= count as f64;
+        }
+
+        Ok(NoiseProfile { sample_rate, magnitudes })
+    }
+
+    /// Sample rate of the audio the profile was learned from
+    ///
+    /// # Returns
+    /// `u32` - sample rate in Hz
+    pub fn sample_rate(&self) -> u32
+    {
+        self.sample_rate
+    }
+
+    /// Subtract the noise spectrum from audio
+    ///
+    /// # Parameters
+    /// * `data` - interleaved samples to process in place, at the profile's sample rate
+    /// * `channels` - number of interleaved channels
+    /// * `amount` - fraction of the noise estimate to subtract, 0.0-1.0
+    ///
+    /// # Notes
+    /// Each Hann-windowed STFT frame has every bin scaled by 1 - amount * noise / magnitude,
+    /// floored at `MIN_BIN_GAIN`, then is windowed again and overlap-added. The sum is
+    /// divided by the accumulated squared window, and frames start before the buffer so
+    /// the first and last samples are fully covered too. Channels are processed separately.
+    pub fn reduce(&self, data: &mut [f32], channels: usize, amount: f64)
+    {
+        let frames = data.len() / channels;
+        if frames == 0
+        {
+            return;
+        }
+
+        let window = hann_window(FRAME_SIZE);
+        let mut re = vec![0.0; FRAME_SIZE];
+        let mut im = vec![0.0; FRAME_SIZE];
+
+        for ch in 0..channels
+        {
+            let input: Vec<f32> = data.iter().skip(ch).step_by(channels).copied().collect();
+            let mut output = vec![0.0f64; frames];
+            let mut window_sum = vec![0.0f64; frames];
+
+            let mut start = -((FRAME_SIZE - HOP_SIZE) as isize);
+            while start < frames as isize
+            {
+                let index = |i: usize| usize::try_from(start + i as isize).ok().filter(|&n| n < frames);
+
+                for (i, (r, w)) in re.iter_mut().zip(&window).enumerate()
+                {
+                    *r = index(i).map_or(0.0, |n| input[n] as f64 * w);
+                }
+                im.fill(0.0);
+                fft(&mut re, &mut im, false);
+
+                for k in 0..FRAME_SIZE
+                {
+                    // bins above Nyquist mirror those below
+                    let noise = self.magnitudes[k.min(FRAME_SIZE - k)];
+                    let magnitude = re[k].hypot(im[k]);
+                    let gain = if magnitude > 0.0
+                    {
+                        (1.0 - amount * noise / magnitude).max(MIN_BIN_GAIN)
+                    }
+                    else
+                    {
+                        0.0
+                    };
+                    re[k] *= gain;
+                    im[k] *= gain;
+                }
+                fft(&mut re, &mut im, true);
+
+                for (i, w) in window.iter().enumerate()
+                {
+                    if let Some(n) = index(i)
+                    {
+                        output[n] += re[i] * w;
+                        window_sum[n] += w * w;
+                    }
+                }
+
+                start += HOP_SIZE as isize;
+            }
+
+            for (n, (value, weight)) in output.iter().zip(&window_sum).enumerate()
+            {
+                let sample = if *weight > 1e-9 { value / weight } else { 0.0 };
+                data[n * channels + ch] = sample as f32;
+            }
+        }
+    }
+}
@@ -0,0 +1,1205 @@
+//! Audio effects and processing operating on interleaved sample buffers
+
+use crate::filters::{BiquadCoefficients, BiquadState};
+use crate::resample::{self, ResampleQuality};
+
+/// Remove DC offset from each channel
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+///
+/// # Notes
+/// The mean of each channel is computed over the whole buffer and subtracted,
+/// so stereo channels are corrected independently.
+pub fn remove_dc_offset(data: &mut [f32], channels: usize)
+{
+    let frames = data.len() / channels;
+    if frames == 0
+    {
+        return;
+    }
+
+    for ch in 0..channels
+    {
+        let sum: f64 = data.iter().skip(ch).step_by(channels).map(|&s| s as f64).sum();
+        let mean = (sum / frames as f64) as f32;
+
+        for sample in data.iter_mut().skip(ch).step_by(channels)
+        {
+            *sample -= mean;
+        }
+    }
+}
+
+/// Invert the polarity of samples
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `channel` - optional single channel to invert (None inverts all channels)
+pub fn invert(data: &mut [f32], channels: usize, channel: Option<usize>)
+{
+    match channel
+    {
+        Some(ch) =>
+        {
+            for sample in data.iter_mut().skip(ch).step_by(channels)
+            {
+                *sample = -*sample;
+            }
+        }
+        None =>
+        {
+            for sample in data.iter_mut()
+            {
+                *sample = -*sample;
+            }
+        }
+    }
+}
+
+/// Default analysis window length for time stretching in milliseconds
+pub const DEFAULT_STRETCH_WINDOW_MS: f64 = 40.0;
+
+/// Change the length of audio without changing its pitch
+///
+/// # Parameters
+/// * `data` - interleaved input samples
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `ratio` - length ratio (> 1 lengthens, < 1 shortens)
+/// * `window_ms` - analysis window length in milliseconds
+///
+/// # Returns
+/// `Vec<f32>` - interleaved samples, `ratio` times as long as the input
+///
+/// # Notes
+/// Implements WSOLA (waveform similarity overlap-add). Hann-windowed frames are
+/// placed at a fixed output hop; each frame is read from near its nominal input
+/// position, shifted within a tolerance to best match the natural continuation of
+/// the previous frame. The search runs on the channel sum and the same offset is
+/// used for every channel, keeping stereo phase-coherent.
+pub fn time_stretch(data: &[f32], channels: usize, sample_rate: u32, ratio: f64, window_ms: f64) -> Vec<f32>
+{
+    let in_frames = data.len() / channels;
+    let out_frames = (in_frames as f64 * ratio).round() as usize;
+    if in_frames == 0 || out_frames == 0
+    {
+        return Vec::new();
+    }
+
+    let frame_len = ((window_ms * sample_rate as f64 / 1000.0) as usize).max(16) & !1;
+    let hop_out = frame_len / 2;
+    let hop_in = hop_out as f64 / ratio;
+    let tolerance = frame_len / 4;
+
+    // channel sum used for the similarity search
+    let mono: Vec<f32> = data.chunks_exact(channels).map(|frame| frame.iter().sum()).collect();
+    let mono_at = |i: usize| if i < in_frames { mono[i] } else { 0.0 };
+
+    let window: Vec<f32> = (0..frame_len)
+        .map(|n| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * n as f64 / frame_len as f64).cos() as f32)
+        .collect();
+
+    let mut output = vec![0.0f32; (out_frames + frame_len) * channels];
+    let mut norm = vec![0.0f32; out_frames + frame_len];
+    let mut prev_pos = 0usize;
+    let mut frame_idx = 0usize;
+
+    loop
+    {
+        let out_pos = frame_idx * hop_out;
+        if out_pos >= out_frames
+        {
+            break;
+        }
+
+        let nominal = (frame_idx as f64 * hop_in).round() as usize;
+        let best = if frame_idx == 0
+        {
+            0
+        }
+        else
+        {
+            // find the candidate that best continues the previously placed frame
+            let target = prev_pos + hop_out;
+            let lo = nominal.saturating_sub(tolerance);
+            let hi = (nominal + tolerance).min(in_frames.saturating_sub(1));
+
+            let mut best = nominal.min(in_frames.saturating_sub(1));
+            let mut best_score = f32::MIN;
+            for candidate in lo..=hi.max(lo)
+            {
+                let mut score = 0.0f32;
+                for n in 0..hop_out
+                {
+                    score += mono_at(candidate + n) * mono_at(target + n);
+                }
+                if score > best_score
+                {
+                    best_score = score;
+                    best = candidate;
+                }
+            }
+            best
+        };
+
+        // overlap-add the windowed frame
+        for n in 0..frame_len
+        {
+            let in_frame = best + n;
+            if in_frame >= in_frames
+            {
+                break;
+            }
+            let w = window[n];
+            let out_base = (out_pos + n) * channels;
+            let in_base = in_frame * channels;
+            for ch in 0..channels
+            {
+                output[out_base + ch] += data[in_base + ch] * w;
+            }
+            norm[out_pos + n] += w;
+        }
+
+        prev_pos = best;
+        frame_idx += 1;
+    }
+
+    output.truncate(out_frames * channels);
+    for (frame, &weight) in output.chunks_exact_mut(channels).zip(norm.iter())
+    {
+        if weight > 1e-3
+        {
+            for sample in frame.iter_mut()
+            {
+                *sample /= weight;
+            }
+        }
+    }
+
+    output
+}
+
+/// Shift the pitch of audio without changing its length
+///
+/// # Parameters
+/// * `data` - interleaved input samples
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `semitones` - pitch shift in semitones (positive raises pitch)
+/// * `window_ms` - time stretch analysis window length in milliseconds
+/// * `quality` - sample rate conversion quality
+///
+/// # Returns
+/// `Vec<f32>` - interleaved samples with the same frame count as the input
+///
+/// # Notes
+/// Time-stretches by the pitch factor, then resamples back to the original length
+pub fn pitch_shift(data: &[f32], channels: usize, sample_rate: u32, semitones: f64, window_ms: f64,
+                   quality: ResampleQuality) -> Vec<f32>
+{
+    let in_frames = data.len() / channels;
+    let factor = 2f64.powf(semitones / 12.0);
+
+    let stretched = time_stretch(data, channels, sample_rate, factor, window_ms);
+    let source_rate = (sample_rate as f64 * factor).round() as u32;
+    let mut shifted = resample::resample(&stretched, channels, source_rate, sample_rate, quality);
+
+    shifted.resize(in_frames * channels, 0.0);
+    shifted
+}
+
+/// Change playback speed, altering both pitch and length
+///
+/// # Parameters
+/// * `data` - interleaved input samples
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `factor` - speed factor (2.0 plays twice as fast and an octave higher)
+/// * `quality` - sample rate conversion quality
+///
+/// # Returns
+/// `Vec<f32>` - interleaved samples, 1 / `factor` times as long as the input
+pub fn change_speed(data: &[f32], channels: usize, sample_rate: u32, factor: f64, quality: ResampleQuality) -> Vec<f32>
+{
+    let source_rate = (sample_rate as f64 * factor).round() as u32;
+    resample::resample(data, channels, source_rate, sample_rate, quality)
+}
+
+/// Join the audio either side of a point with an equal-power crossfade
+///
+/// # Parameters
+/// * `data` - interleaved samples to modify in place
+/// * `channels` - number of interleaved channels
+/// * `join_frame` - frame where the outgoing audio ends and the incoming audio begins
+/// * `fade_frames` - crossfade length in frames
+///
+/// # Returns
+/// `usize` - number of frames actually overlapped (and removed from the buffer)
+///
+/// # Notes
+/// The `fade_frames` before the join are faded out with a cosine curve while the
+/// `fade_frames` after it are faded in with a sine curve and summed on top, so the
+/// buffer becomes `fade_frames` shorter. The fade is shortened if there is not
+/// enough audio on either side of the join.
+pub fn crossfade_join(data: &mut Vec<f32>, channels: usize, join_frame: usize, fade_frames: usize) -> usize
+{
+    let total_frames = data.len() / channels;
+    let join_frame = join_frame.min(total_frames);
+    let fade_frames = fade_frames.min(join_frame).min(total_frames - join_frame);
+    if fade_frames == 0
+    {
+        return 0;
+    }
+
+    let out_start = (join_frame - fade_frames) * channels;
+    let in_start = join_frame * channels;
+
+    for i in 0..fade_frames
+    {
+        let x = (i as f64 + 0.5) / fade_frames as f64 * std::f64::consts::FRAC_PI_2;
+        let gain_out = x.cos() as f32;
+        let gain_in = x.sin() as f32;
+
+        for ch in 0..channels
+        {
+            let out_idx = out_start + i * channels + ch;
+            let in_idx = in_start + i * channels + ch;
+            data[out_idx] = data[out_idx] * gain_out + data[in_idx] * gain_in;
+        }
+    }
+
+    data.drain(in_start..in_start + fade_frames * channels);
+    fade_frames
+}
+
+/// Crossfade the end of a buffer into its start so it repeats seamlessly
+///
+/// # Parameters
+/// * `data` - interleaved samples to modify in place
+/// * `channels` - number of interleaved channels
+/// * `fade_frames` - crossfade length in frames, at most half the buffer
+///
+/// # Notes
+/// The last `fade_frames` are blended over the first with `crossfade_join`, so the
+/// buffer becomes `fade_frames` shorter and its last frame runs straight into the
+/// frame that originally followed it.
+pub fn make_loop(data: &mut Vec<f32>, channels: usize, fade_frames: usize)
+{
+    let total_frames = data.len() / channels;
+    let fade_frames = fade_frames.min(total_frames / 2);
+    if fade_frames == 0
+    {
+        return;
+    }
+
+    // move the head after the tail so the join fades the tail out into the head,
+    // then move the blended head back to the front
+    data.rotate_left(fade_frames * channels);
+    let faded = crossfade_join(data, channels, total_frames - fade_frames, fade_frames);
+    data.rotate_right(faded * channels);
+}
+
+/// Convert decibels to a linear gain factor
+///
+/// # Parameters
+/// * `db` - level in decibels
+///
+/// # Returns
+/// `f64` - linear gain
+pub fn db_to_gain(db: f64) -> f64
+{
+    10f64.powf(db / 20.0)
+}
+
+/// Convert a linear amplitude to decibels
+///
+/// # Parameters
+/// * `gain` - linear amplitude
+///
+/// # Returns
+/// `f64` - level in decibels (floored at -200 dB for silence)
+pub fn gain_to_db(gain: f64) -> f64
+{
+    20.0 * gain.max(1e-10).log10()
+}
+
+/// Calculate a one-pole smoothing coefficient for a time constant
+///
+/// # Parameters
+/// * `time_ms` - time constant in milliseconds
+/// * `sample_rate` - sample rate in Hz
+///
+/// # Returns
+/// `f64` - coefficient in [0, 1), where 0 means no smoothing
+pub fn time_constant_coeff(time_ms: f64, sample_rate: u32) -> f64
+{
+    let samples = time_ms * sample_rate as f64 / 1000.0;
+    if samples <= 0.0
+    {
+        0.0
+    }
+    else
+    {
+        (-1.0 / samples).exp()
+    }
+}
+
+/// Dynamic range compressor settings
+pub struct CompressorParams
+{
+    pub threshold_db: f64,
+    pub ratio: f64,
+    pub attack_ms: f64,
+    pub release_ms: f64,
+    pub makeup_db: f64,
+}
+
+/// Apply dynamic range compression
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `params` - compressor settings
+///
+/// # Notes
+/// Feed-forward design with a peak detector. The detector takes the loudest channel
+/// of each frame and all channels share one smoothed gain envelope, so the stereo
+/// image does not shift. Attack and release are converted to per-sample coefficients
+/// from the sample rate. Output is clamped to [-1.0, 1.0].
+pub fn compress(data: &mut [f32], channels: usize, sample_rate: u32, params: &CompressorParams)
+{
+    let attack = time_constant_coeff(params.attack_ms, sample_rate);
+    let release = time_constant_coeff(params.release_ms, sample_rate);
+    let makeup = db_to_gain(params.makeup_db);
+    let slope = 1.0 - 1.0 / params.ratio.max(1.0);
+
+    // current gain reduction in dB (positive values reduce level)
+    let mut reduction_db = 0.0f64;
+
+    for frame in data.chunks_exact_mut(channels)
+    {
+        let peak = frame.iter().fold(0.0f32, |peak, &s| peak.max(s.abs())) as f64;
+        let over_db = gain_to_db(peak) - params.threshold_db;
+        let target_db = if over_db > 0.0 { over_db * slope } else { 0.0 };
+
+        let coeff = if target_db > reduction_db { attack } else { release };
+        reduction_db = target_db + coeff * (reduction_db - target_db);
+
+        let gain = (db_to_gain(-reduction_db) * makeup) as f32;
+        for sample in frame.iter_mut()
+        {
+            *sample = (*sample * gain).clamp(-1.0, 1.0);
+        }
+    }
+}
+
+/// Limiter lookahead time in milliseconds
+pub const LIMITER_LOOKAHEAD_MS: f64 = 5.0;
+
+/// Apply a brickwall limiter
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `ceiling_db` - maximum output level in dBFS
+/// * `release_ms` - gain recovery time in milliseconds
+///
+/// # Notes
+/// Gain is computed from a lookahead window so it has fully ramped down by the time
+/// a peak arrives; no output sample exceeds the ceiling. Because the whole buffer is
+/// available, the detector reads ahead instead of delaying the audio, so the output
+/// stays aligned with the input. Near the end of the buffer the lookahead window
+/// shrinks. All channels share one gain envelope.
+pub fn limit(data: &mut [f32], channels: usize, sample_rate: u32, ceiling_db: f64, release_ms: f64)
+{
+    let frames = data.len() / channels;
+    if frames == 0
+    {
+        return;
+    }
+
+    let ceiling = db_to_gain(ceiling_db);
+    let lookahead = ((LIMITER_LOOKAHEAD_MS * sample_rate as f64 / 1000.0) as usize).max(1);
+    let release = time_constant_coeff(release_ms, sample_rate);
+
+    // gain each frame needs on its own to stay under the ceiling
+    let required: Vec<f64> = data
+        .chunks_exact(channels)
+        .map(|frame|
+        {
+            let peak = frame.iter().fold(0.0f32, |peak, &s| peak.max(s.abs())) as f64;
+            if peak > ceiling { ceiling / peak } else { 1.0 }
+        })
+        .collect();
+
+    // minimum required gain over the upcoming lookahead window
+    let mut window_min = vec![1.0f64; frames];
+    let mut deque = std::collections::VecDeque::new();
+    for i in 0..frames + lookahead - 1
+    {
+        if i < frames
+        {
+            while deque.back().is_some_and(|&j: &usize| required[j] >= required[i])
+            {
+                deque.pop_back();
+            }
+            deque.push_back(i);
+        }
+        if i + 1 >= lookahead
+        {
+            let start = i + 1 - lookahead;
+            while deque.front().is_some_and(|&j| j < start)
+            {
+                deque.pop_front();
+            }
+            if let Some(&j) = deque.front()
+            {
+                window_min[start] = required[j];
+            }
+        }
+    }
+
+    // averaging the window minimum over the lookahead length gives a smooth
+    // attack ramp that still reaches each peak's required gain in time
+    let mut running_sum = window_min[0] * lookahead as f64;
+    let mut gain = 1.0f64;
+
+    for n in 0..frames
+    {
+        let leaving = if n >= lookahead { window_min[n - lookahead] } else { window_min[0] };
+        running_sum += window_min[n] - leaving;
+        let attack_gain = (running_sum / lookahead as f64).min(1.0);
+
+        gain = if attack_gain < gain
+        {
+            attack_gain
+        }
+        else
+        {
+            attack_gain + release * (gain - attack_gain)
+        };
+
+        let limit = ceiling as f32;
+        for sample in &mut data[n * channels..(n + 1) * channels]
+        {
+            *sample = (*sample * gain as f32).clamp(-limit, limit);
+        }
+    }
+}
+
+/// Level below which automatic gain control holds its gain, so pauses and noise are not boosted
+pub const AGC_GATE_DB: f64 = -60.0;
+
+/// Apply slow automatic gain control towards a target level
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `target_db` - RMS level to move the signal towards, in dBFS
+/// * `max_gain_db` - largest boost applied, in dB
+/// * `window_ms` - length of the RMS window in milliseconds
+///
+/// # Notes
+/// The level is measured over a window centred on each frame. Where it falls
+/// below `AGC_GATE_DB` the gain of the last louder passage is held instead.
+/// The window already makes the gain slow; the gain curve is then smoothed
+/// forwards and backwards with a time constant of an eighth of the window to
+/// round off its corners without lagging behind the level. Loud passages are turned down as well as quiet
+/// ones up. All channels share one gain and output is clamped to [-1.0, 1.0].
+pub fn auto_gain(data: &mut [f32], channels: usize, sample_rate: u32, target_db: f64, max_gain_db: f64, window_ms: f64)
+{
+    let frames = data.len() / channels;
+    if frames == 0
+    {
+        return;
+    }
+
+    // running sum of the mean square of each frame
+    let mut energy = Vec::with_capacity(frames + 1);
+    energy.push(0.0f64);
+    for frame in data.chunks_exact(channels)
+    {
+        let power = frame.iter().map(|&s| s as f64 * s as f64).sum::<f64>() / channels as f64;
+        energy.push(energy[energy.len() - 1] + power);
+    }
+
+    // gain each frame needs to reach the target, None where the window is gated
+    let half_window = ((window_ms * sample_rate as f64 / 2000.0) as usize).max(1);
+    let required: Vec<Option<f64>> = (0..frames)
+        .map(|n|
+        {
+            let start = n.saturating_sub(half_window);
+            let end = (n + half_window + 1).min(frames);
+            let mean_square = (energy[end] - energy[start]) / (end - start) as f64;
+            let level_db = 10.0 * mean_square.max(1e-20).log10();
+            if level_db > AGC_GATE_DB { Some((target_db - level_db).min(max_gain_db)) } else { None }
+        })
+        .collect();
+
+    // hold through gated stretches, starting from the first measured gain
+    let mut held = required.iter().flatten().next().copied().unwrap_or(0.0);
+    let mut gain_db: Vec<f64> = required
+        .iter()
+        .map(|&gain|
+        {
+            if let Some(gain) = gain
+            {
+                held = gain;
+            }
+            held
+        })
+        .collect();
+
+    let coeff = time_constant_coeff(window_ms / 8.0, sample_rate);
+    for n in 1..frames
+    {
+        gain_db[n] += coeff * (gain_db[n - 1] - gain_db[n]);
+    }
+    for n in (0..frames - 1).rev()
+    {
+        gain_db[n] += coeff * (gain_db[n + 1] - gain_db[n]);
+    }
+
+    for (frame, &gain_db) in data.chunks_exact_mut(channels).zip(&gain_db)
+    {
+        let gain = db_to_gain(gain_db) as f32;
+        for sample in frame.iter_mut()
+        {
+            *sample = (*sample * gain).clamp(-1.0, 1.0);
+        }
+    }
+}
+
+/// Q of the de-esser's sibilance band, a little over an octave wide
+pub const DEESSER_Q: f64 = 1.2;
+
+/// De-esser detector attack time in milliseconds
+const DEESSER_ATTACK_MS: f64 = 1.0;
+
+/// De-esser detector release time in milliseconds
+const DEESSER_RELEASE_MS: f64 = 60.0;
+
+/// Reduce sibilance by compressing a band around a frequency
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `freq_hz` - centre of the sibilance band in Hz
+/// * `threshold_db` - band level above which it is turned down, in dBFS
+/// * `ratio` - compression ratio applied to the band
+///
+/// # Notes
+/// Each channel is split into a band-pass band around `freq_hz` and the rest of
+/// the signal. A peak detector on the band drives a gain envelope, shared by all
+/// channels, that only scales the band before the two are summed again. With no
+/// reduction the sum is the input unchanged, so frequencies away from the band
+/// are left alone.
+pub fn deess(data: &mut [f32], channels: usize, sample_rate: u32, freq_hz: f64, threshold_db: f64, ratio: f64)
+{
+    let coeffs = BiquadCoefficients::bandpass(sample_rate, freq_hz, DEESSER_Q);
+    let mut states = vec![BiquadState::default(); channels];
+    let mut band = vec![0.0f64; channels];
+
+    let attack = time_constant_coeff(DEESSER_ATTACK_MS, sample_rate);
+    let release = time_constant_coeff(DEESSER_RELEASE_MS, sample_rate);
+    let slope = 1.0 - 1.0 / ratio.max(1.0);
+
+    // current gain reduction of the band in dB (positive values reduce level)
+    let mut reduction_db = 0.0f64;
+
+    for frame in data.chunks_exact_mut(channels)
+    {
+        let mut peak = 0.0f64;
+        for ((value, state), &sample) in band.iter_mut().zip(states.iter_mut()).zip(frame.iter())
+        {
+            *value = state.process(&coeffs, sample as f64);
+            peak = peak.max(value.abs());
+        }
+
+        let over_db = gain_to_db(peak) - threshold_db;
+        let target_db = if over_db > 0.0 { over_db * slope } else { 0.0 };
+        let coeff = if target_db > reduction_db { attack } else { release };
+        reduction_db = target_db + coeff * (reduction_db - target_db);
+
+        let cut = db_to_gain(-reduction_db) - 1.0;
+        for (sample, &value) in frame.iter_mut().zip(band.iter())
+        {
+            *sample = (*sample as f64 + value * cut) as f32;
+        }
+    }
+}
+
+/// Freeverb comb filter delay lengths at 44.1 kHz
+const REVERB_COMB_TUNING: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+
+/// Freeverb all-pass filter delay lengths at 44.1 kHz
+const REVERB_ALLPASS_TUNING: [usize; 4] = [556, 441, 341, 225];
+
+/// Extra delay for the right channel at 44.1 kHz, decorrelating the stereo outputs
+const REVERB_STEREO_SPREAD: usize = 23;
+
+/// Input attenuation applied before the comb filters
+const REVERB_FIXED_GAIN: f32 = 0.015;
+
+/// Gain applied to the wet reverb output
+const REVERB_WET_SCALE: f32 = 3.0;
+
+/// Lowpass-feedback comb filter used by the reverb
+struct CombFilter
+{
+    buffer: Vec<f32>,
+    index: usize,
+    filter_store: f32,
+}
+
+impl CombFilter
+{
+    /// Create a comb filter with the given delay length
+    ///
+    /// # Parameters
+    /// * `length` - delay length in samples
+    ///
+    /// # Returns
+    /// `CombFilter` - filter with an empty delay line
+    fn new(length: usize) -> Self
+    {
+        CombFilter
+        {
+            buffer: vec![0.0; length.max(1)],
+            index: 0,
+            filter_store: 0.0,
+        }
+    }
+
+    /// Process one sample
+    ///
+    /// # Parameters
+    /// * `input` - input sample
+    /// * `feedback` - feedback amount (room size)
+    /// * `damping` - high frequency damping amount
+    ///
+    /// # Returns
+    /// `f32` - delayed output sample
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32
+    {
+        let output = self.buffer[self.index];
+        self.filter_store = output * (1.0 - damping) + self.filter_store * damping;
+        self.buffer[self.index] = input + self.filter_store * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+/// Schroeder all-pass filter used by the reverb
+struct AllPassFilter
+{
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl AllPassFilter
+{
+    /// Create an all-pass filter with the given delay length
+    ///
+    /// # Parameters
+    /// * `length` - delay length in samples
+    ///
+    /// # Returns
+    /// `AllPassFilter` - filter with an empty delay line
+    fn new(length: usize) -> Self
+    {
+        AllPassFilter
+        {
+            buffer: vec![0.0; length.max(1)],
+            index: 0,
+        }
+    }
+
+    /// Process one sample
+    ///
+    /// # Parameters
+    /// * `input` - input sample
+    ///
+    /// # Returns
+    /// `f32` - filtered output sample
+    fn process(&mut self, input: f32) -> f32
+    {
+        let buffered = self.buffer[self.index];
+        self.buffer[self.index] = input + buffered * 0.5;
+        self.index = (self.index + 1) % self.buffer.len();
+        buffered - input
+    }
+}
+
+/// Apply a Freeverb-style reverb
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `room_size` - room size 0.0-1.0 (longer decay when larger)
+/// * `damping` - high frequency damping 0.0-1.0
+/// * `wet` - wet/dry balance 0.0-1.0
+///
+/// # Notes
+/// Eight parallel comb filters feed four series all-pass filters per channel. Delay
+/// lengths are scaled from the classic 44.1 kHz tuning to the track's sample rate,
+/// and odd channels get the usual stereo spread. The tail is rendered within the
+/// existing buffer length. Output is clamped to [-1.0, 1.0].
+pub fn reverb(data: &mut [f32], channels: usize, sample_rate: u32, room_size: f64, damping: f64, wet: f64)
+{
+    let scale = |length: usize| (length as f64 * sample_rate as f64 / 44100.0).round() as usize;
+    let feedback = (room_size.clamp(0.0, 1.0) * 0.28 + 0.7) as f32;
+    let damping = (damping.clamp(0.0, 1.0) * 0.4) as f32;
+    let wet = wet.clamp(0.0, 1.0) as f32;
+    let dry = 1.0 - wet;
+
+    let mut tanks: Vec<(Vec<CombFilter>, Vec<AllPassFilter>)> = (0..channels)
+        .map(|ch|
+        {
+            let spread = if ch % 2 == 1 { REVERB_STEREO_SPREAD } else { 0 };
+            let combs = REVERB_COMB_TUNING.iter().map(|&len| CombFilter::new(scale(len + spread))).collect();
+            let allpasses = REVERB_ALLPASS_TUNING.iter().map(|&len| AllPassFilter::new(scale(len + spread))).collect();
+            (combs, allpasses)
+        })
+        .collect();
+
+    for frame in data.chunks_exact_mut(channels)
+    {
+        let input = frame.iter().sum::<f32>() / channels as f32 * REVERB_FIXED_GAIN;
+
+        for (sample, (combs, allpasses)) in frame.iter_mut().zip(tanks.iter_mut())
+        {
+            let mut output: f32 = combs.iter_mut().map(|comb| comb.process(input, feedback, damping)).sum();
+            for allpass in allpasses.iter_mut()
+            {
+                output = allpass.process(output);
+            }
+            *sample = (*sample * dry + output * wet * REVERB_WET_SCALE).clamp(-1.0, 1.0);
+        }
+    }
+}
+
+/// Reduce bit depth and sample rate for a lo-fi sound
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `bits` - effective bit depth
+/// * `downsample_factor` - number of frames each held sample lasts
+///
+/// # Notes
+/// Samples are rounded to the 2^bits levels of a signed integer of that width,
+/// from -1.0 up to one step below 1.0. Every `downsample_factor`th frame is then
+/// held over the frames after it, with no filtering.
+pub fn bitcrush(data: &mut [f32], channels: usize, bits: u32, downsample_factor: usize)
+{
+    let steps = (1u32 << (bits - 1)) as f32;
+    let quantize = |sample: f32| (sample * steps).round().clamp(-steps, steps - 1.0) / steps;
+
+    for block in data.chunks_mut(channels * downsample_factor.max(1))
+    {
+        let (held, rest) = block.split_at_mut(channels.min(block.len()));
+        for sample in held.iter_mut()
+        {
+            *sample = quantize(*sample);
+        }
+        for frame in rest.chunks_mut(channels)
+        {
+            frame.copy_from_slice(&held[..frame.len()]);
+        }
+    }
+}
+
+/// Scale the side component of a stereo signal
+///
+/// # Parameters
+/// * `data` - interleaved stereo samples to process in place
+/// * `width` - side level relative to the original (0.0 = mono, 1.0 = unchanged, > 1.0 = wider)
+///
+/// # Notes
+/// Each frame is split into mid (L+R)/2 and side (L-R)/2, the side is scaled,
+/// and the pair is recombined. Output is clamped to [-1.0, 1.0].
+pub fn stereo_width(data: &mut [f32], width: f64)
+{
+    let width = width as f32;
+    for frame in data.chunks_exact_mut(2)
+    {
+        let mid = (frame[0] + frame[1]) * 0.5;
+        let side = (frame[0] - frame[1]) * 0.5 * width;
+        frame[0] = (mid + side).clamp(-1.0, 1.0);
+        frame[1] = (mid - side).clamp(-1.0, 1.0);
+    }
+}
+
+/// Soft-clip samples with a tanh waveshaper
+///
+/// # Parameters
+/// * `data` - samples to process in place (any channel layout)
+/// * `drive` - input gain into the waveshaper
+/// * `mix` - wet/dry balance 0.0-1.0
+///
+/// # Notes
+/// The shaped signal is divided by tanh(drive) so a full-scale input still peaks
+/// at full scale, keeping the level roughly constant as drive changes. The curve
+/// is symmetric, so it adds odd harmonics. Output is clamped to [-1.0, 1.0].
+pub fn saturate(data: &mut [f32], drive: f64, mix: f64)
+{
+    let mix = mix.clamp(0.0, 1.0);
+    let normalize = 1.0 / drive.tanh();
+
+    for sample in data.iter_mut()
+    {
+        let dry = *sample as f64;
+        let wet = (dry * drive).tanh() * normalize;
+        *sample = ((dry * (1.0 - mix) + wet * mix) as f32).clamp(-1.0, 1.0);
+    }
+}
+
+/// Modulate amplitude with a sine LFO
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `rate_hz` - modulation rate in Hz
+/// * `depth` - modulation depth 0.0-1.0
+///
+/// # Notes
+/// Gain swings between 1.0 and 1.0 - depth, starting at 1.0. The LFO phase is taken
+/// from the frame index, so it runs continuously across the whole buffer and all
+/// channels share it.
+pub fn tremolo(data: &mut [f32], channels: usize, sample_rate: u32, rate_hz: f64, depth: f64)
+{
+    let depth = depth.clamp(0.0, 1.0);
+    let phase_step = 2.0 * std::f64::consts::PI * rate_hz / sample_rate as f64;
+
+    for (n, frame) in data.chunks_exact_mut(channels).enumerate()
+    {
+        let lfo = (1.0 - (phase_step * n as f64).cos()) * 0.5;
+        let gain = (1.0 - depth * lfo) as f32;
+        for sample in frame.iter_mut()
+        {
+            *sample *= gain;
+        }
+    }
+}
+
+/// Read one channel at a fractional frame position with linear interpolation
+///
+/// # Parameters
+/// * `data` - interleaved samples
+/// * `channels` - number of interleaved channels
+/// * `channel` - channel to read
+/// * `position` - frame position, clamped to the start of the buffer
+///
+/// # Returns
+/// `f32` - interpolated sample, 0.0 past the end of the buffer
+fn read_interpolated(data: &[f32], channels: usize, channel: usize, position: f64) -> f32
+{
+    let position = position.max(0.0);
+    let frame = position as usize;
+    let fraction = (position - frame as f64) as f32;
+    let at = |frame: usize| data.get(frame * channels + channel).copied().unwrap_or(0.0);
+    at(frame) * (1.0 - fraction) + at(frame + 1) * fraction
+}
+
+/// Modulate pitch with an LFO-driven delay line
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `rate_hz` - modulation rate in Hz
+/// * `depth_ms` - peak delay deviation in milliseconds
+///
+/// # Notes
+/// The delay swings sinusoidally between 0 and 2 * depth around a centre of `depth_ms`,
+/// read with linear interpolation. Every channel of a frame uses the same delay so
+/// stereo stays in phase. The output lags the input by `depth_ms` on average.
+pub fn vibrato(data: &mut [f32], channels: usize, sample_rate: u32, rate_hz: f64, depth_ms: f64)
+{
+    let input = data.to_vec();
+    let depth_frames = depth_ms * sample_rate as f64 / 1000.0;
+    let phase_step = 2.0 * std::f64::consts::PI * rate_hz / sample_rate as f64;
+
+    for (n, frame) in data.chunks_exact_mut(channels).enumerate()
+    {
+        let delay = depth_frames * (1.0 + (phase_step * n as f64).sin());
+        let position = n as f64 - delay;
+        for (ch, sample) in frame.iter_mut().enumerate()
+        {
+            *sample = read_interpolated(&input, channels, ch, position);
+        }
+    }
+}
+
+/// Maximum number of chorus voices
+pub const CHORUS_MAX_VOICES: usize = 8;
+
+/// Centre delay of each chorus voice in milliseconds
+pub const CHORUS_BASE_DELAY_MS: f64 = 20.0;
+
+/// Rate spread between successive chorus voices, as a fraction of the base rate
+const CHORUS_RATE_SPREAD: f64 = 0.1;
+
+/// Thicken a sound with several modulated delay lines
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `voices` - number of delay lines
+/// * `rate_hz` - modulation rate in Hz
+/// * `depth_ms` - peak delay deviation of each voice in milliseconds
+/// * `mix` - wet/dry balance 0.0-1.0
+///
+/// # Notes
+/// Each voice's delay swings around `CHORUS_BASE_DELAY_MS`. Voices start at evenly
+/// spread LFO phases and run slightly faster than the one before, so they drift
+/// against each other instead of moving together. The wet signal is the average
+/// of the voices. Output is clamped to [-1.0, 1.0].
+pub fn chorus(data: &mut [f32], channels: usize, sample_rate: u32, voices: usize, rate_hz: f64, depth_ms: f64, mix: f64)
+{
+    let input = data.to_vec();
+    let mix = mix.clamp(0.0, 1.0) as f32;
+    let to_frames = |ms: f64| ms * sample_rate as f64 / 1000.0;
+    let base_frames = to_frames(CHORUS_BASE_DELAY_MS);
+    let depth_frames = to_frames(depth_ms);
+
+    // (phase step per frame, starting phase) of each voice
+    let lfos: Vec<(f64, f64)> = (0..voices)
+        .map(|v|
+        {
+            let rate = rate_hz * (1.0 + CHORUS_RATE_SPREAD * v as f64);
+            let step = 2.0 * std::f64::consts::PI * rate / sample_rate as f64;
+            (step, 2.0 * std::f64::consts::PI * v as f64 / voices as f64)
+        })
+        .collect();
+
+    for (n, frame) in data.chunks_exact_mut(channels).enumerate()
+    {
+        for (ch, sample) in frame.iter_mut().enumerate()
+        {
+            let wet: f32 = lfos
+                .iter()
+                .map(|&(step, phase)|
+                {
+                    let delay = base_frames + depth_frames * (phase + step * n as f64).sin();
+                    read_interpolated(&input, channels, ch, n as f64 - delay)
+                })
+                .sum::<f32>() / voices as f32;
+            *sample = (*sample * (1.0 - mix) + wet * mix).clamp(-1.0, 1.0);
+        }
+    }
+}
+
+/// Window over which the typical deviation is measured for click detection, in milliseconds
+const CLICK_WINDOW_MS: f64 = 10.0;
+
+/// Longest run of samples treated as one click, in milliseconds
+const CLICK_MAX_MS: f64 = 2.0;
+
+/// Deviation below which a sample is never treated as a click (-60 dBFS)
+const CLICK_FLOOR: f32 = 0.001;
+
+/// Detect clicks and interpolate across them
+///
+/// # Parameters
+/// * `data` - interleaved samples to process in place
+/// * `channels` - number of interleaved channels
+/// * `sample_rate` - sample rate in Hz
+/// * `sensitivity` - detection sensitivity 0.0-1.0
+///
+/// # Returns
+/// `usize` - number of clicks repaired
+///
+/// # Notes
+/// Each sample's deviation from the midpoint of its neighbours is compared with the
+/// mean deviation over the surrounding `CLICK_WINDOW_MS`. Samples more than 20x the
+/// mean away (4x at full sensitivity) are flagged, and each run of flagged samples up
+/// to `CLICK_MAX_MS` long is replaced by a straight line between the good samples on
+/// either side. Longer runs are treated as real transients and kept. Channels are
+/// processed separately.
+pub fn remove_clicks(data: &mut [f32], channels: usize, sample_rate: u32, sensitivity: f64) -> usize
+{
+    let frames = data.len() / channels;
+    if frames < 3
+    {
+        return 0;
+    }
+
+    let ratio = (20.0 - 16.0 * sensitivity.clamp(0.0, 1.0)) as f32;
+    let half_window = ((CLICK_WINDOW_MS * sample_rate as f64 / 2000.0) as usize).max(1);
+    let max_run = ((CLICK_MAX_MS * sample_rate as f64 / 1000.0) as usize).max(1);
+    let mut repaired = 0;
+
+    for ch in 0..channels
+    {
+        let x: Vec<f32> = data.iter().skip(ch).step_by(channels).copied().collect();
+
+        // deviation of each sample from the line through its neighbours
+        let mut deviation = vec![0.0f32; frames];
+        for n in 1..frames - 1
+        {
+            deviation[n] = (x[n] - (x[n - 1] + x[n + 1]) * 0.5).abs();
+        }
+
+        let mut prefix = vec![0.0f64; frames + 1];
+        for n in 0..frames
+        {
+            prefix[n + 1] = prefix[n] + deviation[n] as f64;
+        }
+
+        let flagged = |n: usize|
+        {
+            let start = n.saturating_sub(half_window);
+            let end = (n + half_window + 1).min(frames);
+            let mean = ((prefix[end] - prefix[start]) / (end - start) as f64) as f32;
+            deviation[n] > CLICK_FLOOR && deviation[n] > ratio * mean
+        };
+
+        let mut n = 1;
+        while n < frames - 1
+        {
+            if !flagged(n)
+            {
+                n += 1;
+                continue;
+            }
+
+            let run_start = n;
+            while n < frames - 1 && flagged(n)
+            {
+                n += 1;
+            }
+            let run_end = n;
+
+            if run_end - run_start > max_run
+            {
+                continue;
+            }
+
+            // straight line from the last good sample before the run to the first after it
+            let before = x[run_start - 1];
+            let after = x[run_end];
+            let span = (run_end - run_start + 1) as f32;
+            for (i, frame) in (run_start..run_end).enumerate()
+            {
+                data[frame * channels + ch] = before + (after - before) * (i + 1) as f32 / span;
+            }
+            repaired += 1;
+        }
+    }
+
+    repaired
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::fft::dominant_frequency;
+    use crate::generators;
+
+    /// Mean of one channel of interleaved samples
+    fn channel_mean(data: &[f32], channels: usize, ch: usize) -> f64
+    {
+        let frames = data.len() / channels;
+        data.iter().skip(ch).step_by(channels).map(|&s| s as f64).sum::<f64>() / frames as f64
+    }
+
+    /// Largest absolute sample value
+    fn peak(data: &[f32]) -> f32
+    {
+        data.iter().fold(0.0f32, |peak, &s| peak.max(s.abs()))
+    }
+
+    #[test]
+    fn remove_dc_offset_keeps_ac_component()
+    {
+        // a whole number of cycles, so the tone itself has no DC
+        let tone = generators::sine_tone(100.0, 44100, 0.5, 44100, 2);
+        let mut biased: Vec<f32> = tone.iter().map(|&s| s + 0.1).collect();
+
+        remove_dc_offset(&mut biased, 2);
+
+        for ch in 0..2
+        {
+            assert!(channel_mean(&biased, 2, ch).abs() < 1e-6);
+        }
+        assert!(biased.iter().zip(&tone).all(|(a, b)| (a - b).abs() < 1e-5));
+    }
+
+    #[test]
+    fn time_stretch_keeps_pitch()
+    {
+        let tone = generators::sine_tone(440.0, 44100, 0.5, 44100, 1);
+
+        let stretched = time_stretch(&tone, 1, 44100, 1.5, DEFAULT_STRETCH_WINDOW_MS);
+
+        assert!((stretched.len() as f64 - 1.5 * 44100.0).abs() <= 1.0);
+        assert!((dominant_frequency(&stretched, 1, 44100) - 440.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn compress_reduces_loud_section_and_spares_quiet_one()
+    {
+        let mut signal = generators::sine_tone(440.0, 22050, 0.9, 44100, 1);
+        signal.extend(generators::sine_tone(440.0, 22050, 0.05, 44100, 1));
+        let params = CompressorParams
+        {
+            threshold_db: -20.0,
+            ratio: 20.0,
+            attack_ms: 1.0,
+            release_ms: 50.0,
+            makeup_db: 0.0,
+        };
+
+        compress(&mut signal, 1, 44100, &params);
+
+        // skip the attack and release transients at the start of each section
+        let loud = peak(&signal[4410..22050]) as f64;
+        let quiet = peak(&signal[33075..]) as f64;
+        let threshold = db_to_gain(params.threshold_db);
+        assert!(loud < 1.5 * threshold && loud > 0.8 * threshold);
+        assert!((quiet - 0.05).abs() < 0.0025);
+    }
+
+    #[test]
+    fn limit_never_exceeds_ceiling()
+    {
+        // +6 dBFS peaks, well over the ceiling
+        let mut signal = generators::sine_tone(440.0, 44100, db_to_gain(6.0), 44100, 2);
+
+        limit(&mut signal, 2, 44100, -1.0, 50.0);
+
+        assert!(peak(&signal) as f64 <= db_to_gain(-1.0));
+        // the limiter should reduce gain, not flatten the tone to silence
+        assert!(peak(&signal) as f64 > 0.9 * db_to_gain(-1.0));
+    }
+
+    #[test]
+    fn reverb_impulse_leaves_decaying_tail()
+    {
+        let mut signal = vec![0.0f32; 2 * 88200];
+        signal[0] = 1.0;
+        signal[1] = 1.0;
+
+        reverb(&mut signal, 2, 44100, 0.8, 0.5, 0.5);
+
+        let energy = |range: &[f32]| range.iter().map(|&s| s as f64 * s as f64).sum::<f64>();
+        let early = energy(&signal[2..44100]);
+        let late = energy(&signal[88200..]);
+        assert!(peak(&signal[44100..]) > 1e-4);
+        assert!(late > 0.0 && late < early);
+        assert!(signal.iter().all(|s| (-1.0..=1.0).contains(s)));
+    }
+}
@@ -0,0 +1,30 @@
+//! Python exception types raised by the editor
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+
+create_exception!(soundly, SoundlyError, PyRuntimeError, "Base class for all soundly errors.");
+create_exception!(soundly, LoadError, SoundlyError, "An audio file could not be read or decoded.");
+create_exception!(soundly, ExportError, SoundlyError, "Audio could not be encoded or written.");
+create_exception!(soundly, PlaybackError, SoundlyError, "Playback could not be started or configured.");
+
+/// Add the exception types to the Python module
+///
+/// # Parameters
+/// * `py` - Python interpreter token
+/// * `m` - module to register the types on
+///
+/// # Returns
+/// `PyResult<()>` - Ok if successful
+///
+/// # Notes
+/// All types derive from `RuntimeError`, so existing `except RuntimeError` handlers keep working
+pub fn register(py: Python, m: &PyModule) -> PyResult<()>
+{
+    m.add("SoundlyError", py.get_type::<SoundlyError>())?;
+    m.add("LoadError", py.get_type::<LoadError>())?;
+    m.add("ExportError", py.get_type::<ExportError>())?;
+    m.add("PlaybackError", py.get_type::<PlaybackError>())?;
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Radix-2 fast Fourier transform for spectral processing
+
+use std::f64::consts::PI;
+
+/// Transform complex data in place
+///
+/// # Parameters
+/// * `re` - real parts, length a power of two
+/// * `im` - imaginary parts, same length as `re`
+/// * `inverse` - run the inverse transform, including the 1/n scaling
+///
+/// # Notes
+/// Iterative Cooley-Tukey: a bit-reversal permutation followed by log2(n) butterfly passes
+pub fn fft(re: &mut [f64], im: &mut [f64], inverse: bool)
+{
+    let n = re.len();
+    debug_assert!(n.is_power_of_two() && im.len() == n);
+
+    // bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n
+    {
+        let mut bit = n >> 1;
+        while j & bit != 0
+        {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j
+        {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n
+    {
+        let angle = sign * 2.0 * PI / len as f64;
+        let (step_re, step_im) = (angle.cos(), angle.sin());
+
+        for start in (0..n).step_by(len)
+        {
+            let (mut w_re, mut w_im) = (1.0, 0.0);
+            for k in 0..len / 2
+            {
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+
+                let next_re = w_re * step_re - w_im * step_im;
+                w_im = w_re * step_im + w_im * step_re;
+                w_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+
+    if inverse
+    {
+        let scale = 1.0 / n as f64;
+        for (r, i) in re.iter_mut().zip(im.iter_mut())
+        {
+            *r *= scale;
+            *i *= scale;
+        }
+    }
+}
+
+/// Periodic Hann window
+///
+/// # Parameters
+/// * `size` - window length
+///
+/// # Returns
+/// `Vec<f64>` - window coefficients
+///
+/// # Notes
+/// The periodic form overlaps to a constant at hops of size/2 and size/4
+pub fn hann_window(size: usize) -> Vec<f64>
+{
+    (0..size)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / size as f64).cos())
+        .collect()
+}
+
+/// Magnitude spectrum of the first channel of a signal
+///
+/// # Returns
+/// `(Vec<f64>, f64)` - magnitudes of the bins up to Nyquist, and the bin width in Hz
+#[cfg(test)]
+pub(crate) fn magnitude_spectrum(data: &[f32], channels: usize, sample_rate: u32) -> (Vec<f64>, f64)
+{
+    let frames = data.len() / channels;
+    let size = (1usize << frames.ilog2()).min(1 << 16);
+    let window = hann_window(size);
+    let mut re: Vec<f64> = (0..size).map(|i| data[i * channels] as f64 * window[i]).collect();
+    let mut im = vec![0.0; size];
+    fft(&mut re, &mut im, false);
+
+    let magnitudes = (0..size / 2).map(|bin| re[bin].hypot(im[bin])).collect();
+    (magnitudes, sample_rate as f64 / size as f64)
+}
+
+/// Find the strongest frequency in the first channel of a signal, ignoring DC
+#[cfg(test)]
+pub(crate) fn dominant_frequency(data: &[f32], channels: usize, sample_rate: u32) -> f64
+{
+    let (magnitudes, bin_hz) = magnitude_spectrum(data, channels, sample_rate);
+    let peak_bin = (1..magnitudes.len())
+        .max_by(|&a, &b| magnitudes[a].total_cmp(&magnitudes[b]))
+        .unwrap();
+    peak_bin as f64 * bin_hz
+}
+
+/// Energy of the first channel of a signal between two frequencies, in Hz
+#[cfg(test)]
+pub(crate) fn band_energy(data: &[f32], channels: usize, sample_rate: u32, low_hz: f64, high_hz: f64) -> f64
+{
+    let (magnitudes, bin_hz) = magnitude_spectrum(data, channels, sample_rate);
+    magnitudes.iter()
+        .enumerate()
+        .filter(|&(bin, _)| (low_hz..=high_hz).contains(&(bin as f64 * bin_hz)))
+        .map(|(_, m)| m * m)
+        .sum()
+}
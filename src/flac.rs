@@ -1,9 +1,11 @@
 //! Pure Rust FLAC encoder implementation based on RFC 9639
-//! Currently supports all compression levels with 16-bit samples
+//! Currently supports all compression levels with 16- or 24-bit samples, either in
+//! one shot or incrementally through `FlacStreamEncoder`
 
 use anyhow::{anyhow, Result};
-use std::io::Write;
+use std::io::{Cursor, Seek, SeekFrom, Write};
 use std::path::Path;
+use crate::generators::NoiseRng;
 
 /// FLAC file signature
 const FLAC_SIGNATURE: [u8; 4] = [0x66, 0x4C, 0x61, 0x43]; // "fLaC"
@@ -11,9 +13,153 @@ const FLAC_SIGNATURE: [u8; 4] = [0x66, 0x4C, 0x61, 0x43]; // "fLaC"
 /// Maximum Rice parameter value for 4-bit encoding
 const MAX_RICE_PARAM_4BIT: u32 = 14;
 
+/// Maximum Rice parameter value for 5-bit encoding
+const MAX_RICE_PARAM_5BIT: u32 = 30;
+
+/// Seed of the dither noise, fixed so repeated exports are identical
+const DITHER_SEED: u64 = 0x464C4143;
+
 /// Frame sync code
 const FRAME_SYNC_CODE: u16 = 0x3FFE;
 
+/// Bytes a frame adds beyond its samples: header, one subframe header per channel, and CRC-16
+const MAX_FRAME_OVERHEAD: usize = 32;
+
+/// Bytes of a cue sheet track entry, excluding its index points
+const CUE_TRACK_BYTES: usize = 36;
+
+/// Samples per CD sector at 44.1 kHz; CD-DA cue sheet offsets must be multiples of this
+const CD_SECTOR_SAMPLES: u64 = 588;
+
+/// Encoder settings for `encode_flac_with_level` and `export_to_flac_with_level`
+#[derive(Clone, Copy)]
+pub struct FlacOptions<'a>
+{
+    pub bits_per_sample: u8,  // 16 or 24
+    pub dither: bool,  // add TPDF dither when quantizing to bits_per_sample
+    pub compression_level: u8,  // 0 (fastest) to 8 (best)
+    pub block_size: Option<usize>,  // samples per channel in each frame, 16-65535, None for the level's default
+    pub comments: &'a [(String, String)],  // vorbis comment (field name, value) pairs, none to omit the block
+    pub cue_sheet: Option<&'a CueSheet>,  // cue sheet block describing tracks within the stream
+    pub padding_bytes: usize,  // length of a trailing PADDING block, 0 to omit it
+}
+
+impl Default for FlacOptions<'_>
+{
+    fn default() -> Self
+    {
+        FlacOptions
+        {
+            bits_per_sample: 16,
+            dither: false,
+            compression_level: 5,
+            block_size: None,
+            comments: &[],
+            cue_sheet: None,
+            padding_bytes: 0,
+        }
+    }
+}
+
+/// A CUESHEET metadata block describing the tracks within a stream
+///
+/// # Notes
+/// The lead-out track is added when the block is written, at the end of the stream
+#[derive(Clone, Default)]
+pub struct CueSheet
+{
+    pub catalog_number: String,  // media catalog number, up to 128 ASCII characters
+    pub lead_in_samples: u64,  // samples of lead-in, for CD-DA only
+    pub is_cd: bool,  // whether the cue sheet describes a compact disc
+    pub tracks: Vec<CueTrack>,
+}
+
+/// One track of a cue sheet
+#[derive(Clone, Default)]
+pub struct CueTrack
+{
+    pub offset: u64,  // first sample of the track, counted from the start of the stream
+    pub number: u8,  // track number, 1-99 for CD-DA
+    pub isrc: String,  // 12 character ISRC, or empty
+    pub indices: Vec<CueIndex>,  // at least one index point
+}
+
+/// An index point within a cue sheet track
+#[derive(Clone, Copy, Default)]
+pub struct CueIndex
+{
+    pub offset: u64,  // sample offset from the start of the track
+    pub number: u8,  // index number, 1 for the start of the track and 0 for a pregap
+}
+
+impl CueSheet
+{
+    /// Check the cue sheet against the limits of the CUESHEET block
+    ///
+    /// # Returns
+    /// `Result<()>` - Ok if the cue sheet can be written
+    ///
+    /// # Errors
+    /// Returns error if a field is too long, there are no or too many tracks, track
+    /// numbers clash or are reserved, a track has no index points, offsets are out
+    /// of order, or a CD-DA offset is not a whole sector
+    fn validate(&self) -> Result<()>
+    {
+        if !self.catalog_number.is_ascii() || self.catalog_number.len() > 128
+        {
+            return Err(anyhow!("Cue sheet catalog number must be at most 128 ASCII characters"));
+        }
+        if self.tracks.is_empty() || self.tracks.len() > 254
+        {
+            return Err(anyhow!("Cue sheet must have 1-254 tracks, got {}", self.tracks.len()));
+        }
+
+        let lead_out_number = if self.is_cd { 170 } else { 255 };
+        let mut previous_offset = None;
+        for (i, track) in self.tracks.iter().enumerate()
+        {
+            if track.number == 0 || track.number == lead_out_number || (self.is_cd && track.number > 99)
+            {
+                return Err(anyhow!("Invalid cue sheet track number {}", track.number));
+            }
+            if self.tracks[..i].iter().any(|t| t.number == track.number)
+            {
+                return Err(anyhow!("Duplicate cue sheet track number {}", track.number));
+            }
+            if !track.isrc.is_empty() && (track.isrc.len() != 12 || !track.isrc.is_ascii())
+            {
+                return Err(anyhow!("ISRC of cue sheet track {} must be 12 ASCII characters", track.number));
+            }
+            if track.indices.is_empty() || track.indices.len() > 255
+            {
+                return Err(anyhow!("Cue sheet track {} must have 1-255 index points", track.number));
+            }
+            if previous_offset.is_some_and(|previous| track.offset < previous)
+            {
+                return Err(anyhow!("Cue sheet track {} starts before the track ahead of it", track.number));
+            }
+            previous_offset = Some(track.offset);
+
+            for (j, index) in track.indices.iter().enumerate()
+            {
+                if j > 0 && index.offset <= track.indices[j - 1].offset
+                {
+                    return Err(anyhow!("Index points of cue sheet track {} must be in increasing order", track.number));
+                }
+                if self.is_cd && ((track.offset + index.offset) % CD_SECTOR_SAMPLES != 0)
+                {
+                    return Err(anyhow!(
+                        "CD-DA cue sheet offsets must be multiples of {} samples (track {})",
+                        CD_SECTOR_SAMPLES,
+                        track.number
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Build CRC-8 lookup table at runtime
 ///
 /// # Returns
@@ -135,47 +281,49 @@ impl MD5Context
     /// * `data` - data to add to hash
     fn update(&mut self, data: &[u8])
     {
-        let mut input_index = 0;
         let len = data.len();
 
-        // compute number of bytes mod 64
-        let index = (self.count[0] >> 3) as usize & 0x3F;
+        // compute number of bytes already buffered, mod 64
+        let mut buffer_index = (self.count[0] >> 3) as usize & 0x3F;
 
-        // update number of bits
-        self.count[0] = self.count[0].wrapping_add((len as u32) << 3);
-        if self.count[0] < ((len as u32) << 3)
-        {
-            self.count[1] = self.count[1].wrapping_add(1);
-        }
-        self.count[1] = self.count[1].wrapping_add((len as u32) >> 29);
+        // update number of bits as one 64-bit counter so large inputs carry correctly
+        let bit_count = ((self.count[1] as u64) << 32 | self.count[0] as u64)
+            .wrapping_add((len as u64).wrapping_shl(3));
+        self.count[0] = bit_count as u32;
+        self.count[1] = (bit_count >> 32) as u32;
 
-        let part_len = 64 - index;
+        let mut input_index = 0;
 
-        // transform as many times as possible
-        if len >= part_len
+        // complete a partially filled buffer first
+        if buffer_index > 0
         {
-            self.buffer[index..index + part_len].copy_from_slice(&data[0..part_len]);
-            self.transform(&self.buffer.clone());
+            let take = (64 - buffer_index).min(len);
+            self.buffer[buffer_index..buffer_index + take].copy_from_slice(&data[..take]);
+            buffer_index += take;
+            input_index = take;
 
-            let mut i = part_len;
-            while i + 63 < len
+            if buffer_index < 64
             {
-                let mut chunk = [0u8; 64];
-                chunk.copy_from_slice(&data[i..i + 64]);
-                self.transform(&chunk);
-                i += 64;
+                // not enough input to fill a block, the bytes stay appended in the buffer
+                return;
             }
-            input_index = i;
+
+            let block = self.buffer;
+            self.transform(&block);
         }
 
-        // buffer remaining input
-        if input_index < len
+        // transform whole blocks directly from the input
+        while input_index + 64 <= len
         {
-            let remaining = len - input_index;
-            let buffer_index = if input_index == 0 { index } else { 0 };
-            self.buffer[buffer_index..buffer_index + remaining]
-                .copy_from_slice(&data[input_index..]);
+            let mut chunk = [0u8; 64];
+            chunk.copy_from_slice(&data[input_index..input_index + 64]);
+            self.transform(&chunk);
+            input_index += 64;
         }
+
+        // buffer remaining input at the start of the now empty buffer
+        let remaining = len - input_index;
+        self.buffer[..remaining].copy_from_slice(&data[input_index..]);
     }
 
     /// Perform MD5 transform on a 64-byte block
@@ -376,31 +524,6 @@ impl MD5Context
     }
 }
 
-/// Compute MD5 checksum of audio samples
-///
-/// # Parameters
-/// * `samples` - audio samples as i16 values
-///
-/// # Returns
-/// `[u8; 16]` - MD5 digest of audio data
-///
-/// # Notes
-/// Samples are processed in little-endian byte order as required by FLAC spec
-fn compute_md5(samples: &[i16]) -> [u8; 16]
-{
-    let mut ctx = MD5Context::new();
-
-    // process samples in little-endian byte order
-    // for FLAC, samples are interleaved and sign-extended if needed
-    for &sample in samples
-    {
-        let bytes = sample.to_le_bytes();
-        ctx.update(&bytes);
-    }
-
-    ctx.finalize()
-}
-
 /// Bit writer for FLAC encoding
 struct BitWriter
 {
@@ -425,6 +548,23 @@ impl BitWriter
         }
     }
 
+    /// Create new bit writer with room for a number of bytes
+    ///
+    /// # Parameters
+    /// * `capacity` - bytes to reserve up front
+    ///
+    /// # Returns
+    /// `BitWriter` - initialized bit writer
+    fn with_capacity(capacity: usize) -> Self
+    {
+        BitWriter
+        {
+            buffer: Vec::with_capacity(capacity),
+            current_byte: 0,
+            bit_count: 0,
+        }
+    }
+
     /// Write specified number of bits to the stream
     ///
     /// # Parameters
@@ -520,18 +660,17 @@ impl BitWriter
         }
     }
 
-    /// Get all bytes written so far
+    /// Take all bytes written
     ///
     /// # Returns
     /// `Vec<u8>` - complete byte buffer including partial byte if any
-    fn get_bytes(&self) -> Vec<u8>
+    fn into_bytes(mut self) -> Vec<u8>
     {
-        let mut result = self.buffer.clone();
         if self.bit_count > 0
         {
-            result.push(self.current_byte);
+            self.buffer.push(self.current_byte);
         }
-        result
+        self.buffer
     }
 }
 
@@ -679,14 +818,15 @@ fn apply_fixed_predictor(samples: &[i32], order: usize) -> Vec<i32>
 ///
 /// # Parameters
 /// * `residual` - residual values to analyze
+/// * `max_param` - largest parameter the coding method can store
 ///
 /// # Returns
-/// `u32` - optimal Rice parameter (0-14)
+/// `u32` - optimal Rice parameter (0 to `max_param`)
 ///
 /// # Notes
 /// Estimates the parameter based on mean absolute deviation. The optimal
 /// parameter is roughly log2(mean * 0.75)
-fn calculate_rice_parameter(residual: &[i32]) -> u32
+fn calculate_rice_parameter(residual: &[i32], max_param: u32) -> u32
 {
     if residual.is_empty()
     {
@@ -705,7 +845,7 @@ fn calculate_rice_parameter(residual: &[i32]) -> u32
     let mut param = 0u32;
     let mut test_mean = mean;
 
-    while test_mean > 0 && param < MAX_RICE_PARAM_4BIT
+    while test_mean > 0 && param < max_param
     {
         test_mean >>= 1;
         if test_mean > 0
@@ -720,7 +860,55 @@ fn calculate_rice_parameter(residual: &[i32]) -> u32
         param -= 1;
     }
 
-    param.min(MAX_RICE_PARAM_4BIT)
+    param.min(max_param)
+}
+
+/// Zigzag encode (fold) a residual, mapping signed values to unsigned
+///
+/// # Parameters
+/// * `sample` - residual value
+///
+/// # Returns
+/// `u32` - 0, -1, 1, -2, 2, ... mapped to 0, 1, 2, 3, 4, ...
+fn fold_residual(sample: i32) -> u32
+{
+    ((sample << 1) ^ (sample >> 31)) as u32
+}
+
+/// Count the bits a partition takes with Rice coding
+///
+/// # Parameters
+/// * `residual` - residual values in the partition
+/// * `rice_param` - Rice parameter to use
+///
+/// # Returns
+/// `u64` - encoded size in bits, excluding the 4-bit parameter
+fn rice_partition_bits(residual: &[i32], rice_param: u32) -> u64
+{
+    residual
+        .iter()
+        .map(|&sample| (fold_residual(sample) >> rice_param) as u64 + 1 + rice_param as u64)
+        .sum()
+}
+
+/// Find the width needed to store a partition as raw signed integers
+///
+/// # Parameters
+/// * `residual` - residual values in the partition
+///
+/// # Returns
+/// `u32` - smallest two's-complement width in bits (at least 1) that holds every value
+///
+/// # Notes
+/// A width of n holds -2^(n-1) through 2^(n-1) - 1, so for example -128 fits in
+/// 8 bits while 128 needs 9
+fn escape_bits_per_sample(residual: &[i32]) -> u32
+{
+    residual
+        .iter()
+        .map(|&sample| 33 - (sample ^ (sample >> 31)).leading_zeros())
+        .max()
+        .unwrap_or(1)
 }
 
 /// Encode residual using Rice coding
@@ -741,14 +929,7 @@ fn encode_rice_partition(writer: &mut BitWriter, residual: &[i32], rice_param: u
     for &sample in residual
     {
         // zigzag encode (fold) the residual - mapping signed to unsigned
-        let folded = if sample >= 0
-        {
-            (sample as u32) << 1
-        }
-        else
-        {
-            (((-(sample + 1)) as u32) << 1) | 1
-        };
+        let folded = fold_residual(sample);
 
         // split into MSB and LSB parts
         let msb = folded >> rice_param;
@@ -775,14 +956,17 @@ fn encode_rice_partition(writer: &mut BitWriter, residual: &[i32], rice_param: u
 /// * `predictor_order` - order of predictor used
 /// * `block_size` - size of audio block
 /// * `compression_level` - compression level (0-8)
+/// * `bits_per_sample` - bits per sample of the stream
 ///
 /// # Returns
 /// `Result<()>` - Ok if successful
 ///
 /// # Notes
 /// Higher compression levels use more partitions for better compression at
-/// the cost of encoding speed
-fn encode_residual(writer: &mut BitWriter, residual: &[i32], predictor_order: usize, block_size: usize, compression_level: u8) -> Result<()>
+/// the cost of encoding speed. Streams wider than 16 bits use 5-bit Rice
+/// parameters, since their residuals often need parameters above 14.
+fn encode_residual(writer: &mut BitWriter, residual: &[i32], predictor_order: usize, block_size: usize, compression_level: u8,
+                   bits_per_sample: u8) -> Result<()>
 {
     // calculate partition order based on compression level
     let mut partition_order = match compression_level
@@ -805,8 +989,16 @@ fn encode_residual(writer: &mut BitWriter, residual: &[i32], predictor_order: us
         partition_order -= 1;
     }
 
-    // write coding method (0b00 for 4-bit Rice parameters)
-    writer.write_bits(0, 2);
+    // write coding method (0b00 for 4-bit Rice parameters, 0b01 for 5-bit)
+    let (method, param_bits, max_param) = if bits_per_sample > 16
+    {
+        (0b01, 5, MAX_RICE_PARAM_5BIT)
+    }
+    else
+    {
+        (0b00, 4, MAX_RICE_PARAM_4BIT)
+    };
+    writer.write_bits(method, 2);
 
     // write partition order
     writer.write_bits(partition_order as u64, 4);
@@ -836,42 +1028,30 @@ fn encode_residual(writer: &mut BitWriter, residual: &[i32], predictor_order: us
         sample_idx += partition_samples;
 
         // calculate best Rice parameter for this partition
-        let rice_param = calculate_rice_parameter(partition_residual);
-
-        if rice_param > MAX_RICE_PARAM_4BIT
-        {
-            // use escape code for incompressible data
-            writer.write_bits(0xF, 4); // escape code (all ones)
+        let rice_param = calculate_rice_parameter(partition_residual, max_param);
 
-            // calculate bits needed for raw samples
-            let mut max_val = 0u32;
-            for &sample in partition_residual
-            {
-                max_val = max_val.max(sample.unsigned_abs());
-            }
+        // escape to raw samples when that is smaller than Rice coding, which happens
+        // for incompressible partitions whose residuals have a wide spread
+        let bits_needed = escape_bits_per_sample(partition_residual);
+        let escape_bits = 5 + bits_needed as u64 * partition_samples as u64;
 
-            let mut bits_needed = 1u32; // at least 1 bit for sign
-            while (1u32 << bits_needed) <= max_val && bits_needed < 32
-            {
-                bits_needed += 1;
-            }
-            bits_needed += 1; // add sign bit
-            bits_needed = bits_needed.max(1).min(32);
+        if bits_needed < 32 && escape_bits < rice_partition_bits(partition_residual, rice_param)
+        {
+            writer.write_bits((1 << param_bits) - 1, param_bits); // escape code (all ones)
 
-            // write bits per sample minus 1
-            writer.write_bits((bits_needed - 1) as u64, 5);
+            // the 5-bit field holds the width of each raw sample
+            writer.write_bits(bits_needed as u64, 5);
 
-            // write samples as raw signed integers
+            // write samples as two's complement, truncated to the width
             for &sample in partition_residual
             {
-                // write as signed value with calculated bit width
                 writer.write_bits(sample as u32 as u64, bits_needed as u8);
             }
         }
         else
         {
             // write Rice parameter
-            writer.write_bits(rice_param as u64, 4);
+            writer.write_bits(rice_param as u64, param_bits);
 
             // encode partition with Rice coding
             encode_rice_partition(writer, partition_residual, rice_param)?;
@@ -949,23 +1129,31 @@ fn encode_subframe(writer: &mut BitWriter, samples: &[i32], bits_per_sample: u8,
         // calculate and encode residual
         let residual = apply_fixed_predictor(samples, predictor_order);
         // pass only the residual values after warm-up samples
-        encode_residual(writer, &residual[predictor_order..], predictor_order, block_size, compression_level)?;
+        encode_residual(writer, &residual[predictor_order..], predictor_order, block_size, compression_level, bits_per_sample)?;
     }
 
     Ok(())
 }
 
+/// Stream layout shared by every frame
+#[derive(Clone, Copy)]
+struct FrameFormat
+{
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u8,
+    compression_level: u8,  // 0-8
+}
+
 /// Encode a frame
 ///
 /// # Parameters
 /// * `writer` - bit writer to write to
 /// * `samples` - interleaved audio samples
-/// * `channels` - number of channels
-/// * `sample_rate` - sample rate in Hz
-/// * `bits_per_sample` - bits per sample
+/// * `format` - channel count, sample rate, bit depth and compression level
 /// * `frame_number` - frame number for header
 /// * `block_size` - number of samples per channel in this frame
-/// * `compression_level` - compression level (0-8)
+/// * `channel_samples` - scratch for the deinterleaved channels, reused across frames
 ///
 /// # Returns
 /// `Result<()>` - Ok if successful
@@ -974,15 +1162,14 @@ fn encode_subframe(writer: &mut BitWriter, samples: &[i32], bits_per_sample: u8,
 /// Encodes a complete FLAC frame with header, subframes, and CRC
 fn encode_frame(
     writer: &mut BitWriter,
-    samples: &[i16],
-    channels: u16,
-    sample_rate: u32,
-    bits_per_sample: u8,
+    samples: &[i32],
+    format: &FrameFormat,
     frame_number: u32,
     block_size: usize,
-    compression_level: u8,
+    channel_samples: &mut Vec<Vec<i32>>,
 ) -> Result<()>
 {
+    let FrameFormat { channels, sample_rate, bits_per_sample, compression_level } = *format;
     let frame_start = writer.buffer.len();
 
     // Frame header
@@ -1098,19 +1285,15 @@ fn encode_frame(
     writer.write_byte(crc8_value);
 
     // encode subframes
-    let mut channel_samples = vec![vec![0i32; block_size]; channels as usize];
-
-    // deinterleave samples
-    for i in 0..block_size
+    // deinterleave samples into the reused per-channel buffers
+    channel_samples.resize_with(channels as usize, Vec::new);
+    for (ch, channel) in channel_samples.iter_mut().enumerate()
     {
-        for ch in 0..channels as usize
+        channel.clear();
+        channel.extend((0..block_size).map(|i|
         {
-            let sample_idx = i * channels as usize + ch;
-            if sample_idx < samples.len()
-            {
-                channel_samples[ch][i] = samples[sample_idx] as i32;
-            }
-        }
+            samples.get(i * channels as usize + ch).copied().unwrap_or(0)
+        }));
     }
 
     // encode each channel
@@ -1131,107 +1314,167 @@ fn encode_frame(
     Ok(())
 }
 
+/// Contents of a STREAMINFO metadata block
+struct StreamInfo
+{
+    min_block_size: u16,  // in samples
+    max_block_size: u16,  // in samples
+    min_frame_size: u32,  // in bytes, 0 if unknown
+    max_frame_size: u32,  // in bytes, 0 if unknown
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: u8,
+    total_samples: u64,  // samples per channel
+    md5: [u8; 16],  // MD5 digest of unencoded audio data
+}
+
 /// Write streaminfo metadata block
 ///
 /// # Parameters
 /// * `writer` - bit writer to write to
-/// * `min_block_size` - minimum block size in samples
-/// * `max_block_size` - maximum block size in samples
-/// * `min_frame_size` - minimum frame size in bytes (0 if unknown)
-/// * `max_frame_size` - maximum frame size in bytes (0 if unknown)
-/// * `sample_rate` - sample rate in Hz
-/// * `channels` - number of channels
-/// * `bits_per_sample` - bits per sample
-/// * `total_samples` - total samples per channel
-/// * `md5` - MD5 digest of unencoded audio data
-fn write_streaminfo(
-    writer: &mut BitWriter,
-    min_block_size: u16,
-    max_block_size: u16,
-    min_frame_size: u32,
-    max_frame_size: u32,
-    sample_rate: u32,
-    channels: u16,
-    bits_per_sample: u8,
-    total_samples: u64,
-    md5: [u8; 16],
-)
+/// * `info` - block sizes, frame sizes, stream format, length and MD5
+/// * `is_last` - whether this is the final metadata block
+fn write_streaminfo(writer: &mut BitWriter, info: &StreamInfo, is_last: bool)
 {
     // metadata block header
-    // last metadata block flag: 1
-    writer.write_bits(1, 1);
+    // last metadata block flag
+    writer.write_bits(is_last as u64, 1);
     // block type: 0 (streaminfo)
     writer.write_bits(0, 7);
     // length: 34 bytes
     writer.write_bits(34, 24);
 
     // streaminfo data
-    writer.write_bits(min_block_size as u64, 16);
-    writer.write_bits(max_block_size as u64, 16);
-    writer.write_bits(min_frame_size as u64, 24);
-    writer.write_bits(max_frame_size as u64, 24);
-    writer.write_bits(sample_rate as u64, 20);
-    writer.write_bits((channels - 1) as u64, 3);
-    writer.write_bits((bits_per_sample - 1) as u64, 5);
-    writer.write_bits(total_samples, 36);
+    writer.write_bits(info.min_block_size as u64, 16);
+    writer.write_bits(info.max_block_size as u64, 16);
+    writer.write_bits(info.min_frame_size as u64, 24);
+    writer.write_bits(info.max_frame_size as u64, 24);
+    writer.write_bits(info.sample_rate as u64, 20);
+    writer.write_bits((info.channels - 1) as u64, 3);
+    writer.write_bits((info.bits_per_sample - 1) as u64, 5);
+    writer.write_bits(info.total_samples, 36);
 
     // MD5 checksum
-    for byte in md5
+    for byte in info.md5
     {
         writer.write_byte(byte);
     }
 }
 
-/// Main FLAC encoding function with compression level
+/// Write vorbis comment metadata block
 ///
 /// # Parameters
-/// * `samples` - audio samples as f32 values
-/// * `sample_rate` - sample rate in Hz
-/// * `channels` - number of channels
-/// * `compression_level` - compression level (0=fastest, 8=best)
-///
-/// # Returns
-/// `Result<Vec<u8>>` - encoded FLAC data
+/// * `writer` - bit writer to write to
+/// * `comments` - (field name, value) pairs
+/// * `is_last` - whether this is the final metadata block
 ///
-/// # Errors
-/// Returns error if fewer than 16 samples per channel or invalid compression level
-pub fn encode_flac_with_level(
-    samples: &[f32],
-    sample_rate: u32,
-    channels: u16,
-    compression_level: u8,
-) -> Result<Vec<u8>>
+/// # Notes
+/// Unlike the rest of the stream, vorbis comment lengths are little-endian
+fn write_vorbis_comment(writer: &mut BitWriter, comments: &[(String, String)], is_last: bool)
 {
-    // convert f32 samples to i16
-    let i16_samples: Vec<i16> = samples
+    let vendor = concat!("soundly ", env!("CARGO_PKG_VERSION"));
+    let fields: Vec<String> = comments
         .iter()
-        .map(|&s| (s * 32767.0).clamp(-32768.0, 32767.0) as i16)
+        .map(|(name, value)| format!("{}={}", name, value))
         .collect();
 
-    let total_samples = i16_samples.len() / channels as usize;
+    let length = 4 + vendor.len() + 4 + fields.iter().map(|f| 4 + f.len()).sum::<usize>();
 
-    // FLAC requires at least 16 samples per channel
-    if total_samples < 16
+    // metadata block header
+    writer.write_bits(is_last as u64, 1);
+    // block type: 4 (vorbis comment)
+    writer.write_bits(4, 7);
+    writer.write_bits(length as u64, 24);
+
+    writer.write_bytes(&(vendor.len() as u32).to_le_bytes());
+    writer.write_bytes(vendor.as_bytes());
+    writer.write_bytes(&(fields.len() as u32).to_le_bytes());
+    for field in &fields
     {
-        return Err(anyhow!(
-            "FLAC requires at least 16 samples per channel, got {}",
-            total_samples
-        ));
+        writer.write_bytes(&(field.len() as u32).to_le_bytes());
+        writer.write_bytes(field.as_bytes());
     }
+}
 
-    // validate compression level
-    if compression_level > 8
+/// Smallest block size FLAC allows, except for the last frame of a stream
+pub const MIN_BLOCK_SIZE: usize = 16;
+
+/// Largest block size the STREAMINFO block can record
+pub const MAX_BLOCK_SIZE: usize = 65535;
+
+/// Largest PADDING block, limited by the 24-bit metadata block length
+pub const MAX_PADDING_BYTES: usize = (1 << 24) - 1;
+
+/// Write cue sheet metadata block
+///
+/// # Parameters
+/// * `writer` - bit writer to write to
+/// * `cue_sheet` - validated cue sheet
+/// * `lead_out_offset` - offset of the lead-out track, i.e. total samples per channel
+/// * `is_last` - whether this is the final metadata block
+///
+/// # Notes
+/// The lead-out track has no index points and comes last, so its offset starts
+/// `CUE_TRACK_BYTES` before the end of the block and can be patched once the stream length is known
+fn write_cue_sheet(writer: &mut BitWriter, cue_sheet: &CueSheet, lead_out_offset: u64, is_last: bool)
+{
+    let track_bytes: usize = cue_sheet.tracks.iter().map(|t| CUE_TRACK_BYTES + 12 * t.indices.len()).sum();
+    let length = 128 + 8 + 259 + 1 + track_bytes + CUE_TRACK_BYTES;
+
+    // metadata block header
+    writer.write_bits(is_last as u64, 1);
+    // block type: 5 (cuesheet)
+    writer.write_bits(5, 7);
+    writer.write_bits(length as u64, 24);
+
+    let mut catalog_number = [0u8; 128];
+    catalog_number[..cue_sheet.catalog_number.len()].copy_from_slice(cue_sheet.catalog_number.as_bytes());
+    writer.write_bytes(&catalog_number);
+    writer.write_bits(cue_sheet.lead_in_samples, 64);
+    writer.write_bits(cue_sheet.is_cd as u64, 1);
+    // reserved: 7 bits, then 258 bytes
+    writer.write_bits(0, 7);
+    writer.write_bytes(&[0; 258]);
+    writer.write_byte((cue_sheet.tracks.len() + 1) as u8);
+
+    for track in &cue_sheet.tracks
     {
-        return Err(anyhow!(
-            "Invalid compression level {}, must be 0-8",
-            compression_level
-        ));
+        let mut isrc = [0u8; 12];
+        isrc[..track.isrc.len()].copy_from_slice(track.isrc.as_bytes());
+
+        writer.write_bits(track.offset, 64);
+        writer.write_byte(track.number);
+        writer.write_bytes(&isrc);
+        // audio track, no pre-emphasis, then 6 bits and 13 bytes reserved
+        writer.write_byte(0);
+        writer.write_bytes(&[0; 13]);
+        writer.write_byte(track.indices.len() as u8);
+
+        for index in &track.indices
+        {
+            writer.write_bits(index.offset, 64);
+            writer.write_byte(index.number);
+            writer.write_bytes(&[0; 3]);
+        }
     }
 
-    let bits_per_sample = 16u8;
+    // lead-out track
+    writer.write_bits(lead_out_offset, 64);
+    writer.write_byte(if cue_sheet.is_cd { 170 } else { 255 });
+    writer.write_bytes(&[0; 12 + 14]);
+    writer.write_byte(0);
+}
 
-    // choose block size based on compression level
-    let block_size = match compression_level
+/// Choose the block size used at a compression level
+///
+/// # Parameters
+/// * `compression_level` - compression level (0=fastest, 8=best)
+///
+/// # Returns
+/// `usize` - block size in samples per channel
+fn block_size_for_level(compression_level: u8) -> usize
+{
+    match compression_level
     {
         0 => 1152,  // fast encoding
         1 => 1152,
@@ -1243,64 +1486,572 @@ pub fn encode_flac_with_level(
         7 => 4096,
         8 => 4096,  // maximum compression
         _ => 4096,
-    }.min(total_samples).max(16);
-
-
-    let mut writer = BitWriter::new();
-
-    // write FLAC signature
-    writer.write_bytes(&FLAC_SIGNATURE);
-
-    // calculate MD5 checksum of audio data
-    let md5 = compute_md5(&i16_samples);
-
-    // write streaminfo
-    write_streaminfo(
-        &mut writer,
-        block_size as u16,
-        block_size as u16,
-        0, // unknown min frame size
-        0, // unknown max frame size
-        sample_rate,
-        channels,
-        bits_per_sample,
-        total_samples as u64,
-        md5,
-    );
+    }
+}
 
-    // encode frames
-    let mut sample_offset = 0;
-    let mut frame_number = 0u32;
+/// Incremental FLAC encoder that writes frames as samples arrive
+///
+/// # Notes
+/// Only one block of samples is buffered at a time. STREAMINFO is written with
+/// placeholder values before the first frame and rewritten by `finalize` once the
+/// total length, frame sizes, and MD5 are known, so the writer must be seekable.
+pub struct FlacStreamEncoder<W: Write + Seek>
+{
+    writer: W,
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: u8,
+    compression_level: u8,
+    block_size: usize,
+    streaminfo_pos: Option<u64>,  // writer position of the STREAMINFO block header, once written
+    comments: Vec<(String, String)>,
+    cue_sheet: Option<CueSheet>,
+    lead_out_pos: Option<u64>,  // writer position of the cue sheet's lead-out offset, once written
+    padding_bytes: usize,  // length of the trailing PADDING block, 0 to omit it
+    dither: Option<NoiseRng>,  // source of TPDF dither noise, None to round without dither
+    pending: Vec<i32>,  // interleaved samples not yet encoded
+    channel_samples: Vec<Vec<i32>>,  // deinterleave scratch reused by every frame
+    md5: MD5Context,
+    total_samples: u64,  // samples per channel encoded so far
+    frame_number: u32,
+    min_frame_size: u32,
+    max_frame_size: u32,
+}
 
-    while sample_offset < i16_samples.len()
+impl<W: Write + Seek> FlacStreamEncoder<W>
+{
+    /// Create a stream encoder
+    ///
+    /// # Parameters
+    /// * `writer` - seekable output
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of channels (1-8)
+    /// * `bits_per_sample` - bits per sample (16 or 24)
+    /// * `compression_level` - compression level (0=fastest, 8=best)
+    ///
+    /// # Returns
+    /// `Result<Self>` - encoder ready for `write_samples`
+    ///
+    /// # Errors
+    /// Returns error if a parameter is unsupported
+    ///
+    /// # Notes
+    /// Nothing is written until the first frame or `finalize`
+    pub fn new(writer: W, sample_rate: u32, channels: u16, bits_per_sample: u8, compression_level: u8) -> Result<Self>
     {
-        let remaining = i16_samples.len() - sample_offset;
-        let current_block_size = block_size.min(remaining / channels as usize);
-
-        if current_block_size == 0
+        // validate compression level
+        if compression_level > 8
         {
-            break;
+            return Err(anyhow!(
+                "Invalid compression level {}, must be 0-8",
+                compression_level
+            ));
+        }
+        if !(1..=8).contains(&channels)
+        {
+            return Err(anyhow!("FLAC supports 1-8 channels, got {}", channels));
+        }
+        if bits_per_sample != 16 && bits_per_sample != 24
+        {
+            return Err(anyhow!("Unsupported bits per sample {}, expected 16 or 24", bits_per_sample));
+        }
+        if sample_rate == 0 || sample_rate >= 1 << 20
+        {
+            return Err(anyhow!("Invalid sample rate {}", sample_rate));
         }
 
-        let frame_samples = &i16_samples[sample_offset..sample_offset + current_block_size * channels as usize];
+        let block_size = block_size_for_level(compression_level);
 
-        encode_frame(
-            &mut writer,
-            frame_samples,
-            channels,
+        Ok(FlacStreamEncoder
+        {
+            writer,
             sample_rate,
+            channels,
             bits_per_sample,
-            frame_number,
-            current_block_size,
             compression_level,
-        )?;
-
-        sample_offset += current_block_size * channels as usize;
-        frame_number += 1;
+            block_size,
+            streaminfo_pos: None,
+            comments: Vec::new(),
+            cue_sheet: None,
+            lead_out_pos: None,
+            padding_bytes: 0,
+            dither: None,
+            pending: Vec::with_capacity(block_size * channels as usize),
+            channel_samples: vec![Vec::with_capacity(block_size); channels as usize],
+            md5: MD5Context::new(),
+            total_samples: 0,
+            frame_number: 0,
+            min_frame_size: 0,
+            max_frame_size: 0,
+        })
     }
 
-    Ok(writer.get_bytes())
-}
+    /// Override the block size chosen by the compression level
+    ///
+    /// # Parameters
+    /// * `block_size` - samples per channel in each frame, 16-65535
+    ///
+    /// # Returns
+    /// `Result<Self>` - the encoder
+    ///
+    /// # Errors
+    /// Returns error if the block size is out of range
+    ///
+    /// # Notes
+    /// Must be called before any samples are written. Sizes other than the standard
+    /// ones (e.g. 4096) are stored in the frame header with the uncommon block size escape.
+    pub fn with_block_size(mut self, block_size: usize) -> Result<Self>
+    {
+        if !(MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE).contains(&block_size)
+        {
+            return Err(anyhow!(
+                "Invalid block size {}, must be {}-{}",
+                block_size,
+                MIN_BLOCK_SIZE,
+                MAX_BLOCK_SIZE
+            ));
+        }
+
+        self.block_size = block_size;
+        self.pending = Vec::with_capacity(block_size * self.channels as usize);
+        self.channel_samples = vec![Vec::with_capacity(block_size); self.channels as usize];
+        Ok(self)
+    }
+
+    /// Add a vorbis comment block to the header
+    ///
+    /// # Parameters
+    /// * `comments` - vorbis comment (field name, value) pairs, none to omit the block
+    ///
+    /// # Returns
+    /// `Self` - the encoder
+    ///
+    /// # Notes
+    /// Must be called before any samples are written
+    pub fn with_comments(mut self, comments: &[(String, String)]) -> Self
+    {
+        self.comments = comments.to_vec();
+        self
+    }
+
+    /// Add a cue sheet block to the header
+    ///
+    /// # Parameters
+    /// * `cue_sheet` - tracks and index points of the stream
+    ///
+    /// # Returns
+    /// `Result<Self>` - the encoder
+    ///
+    /// # Errors
+    /// Returns error if the cue sheet is invalid
+    ///
+    /// # Notes
+    /// Must be called before any samples are written. The lead-out track is filled
+    /// in by `finalize`, which also rejects tracks that start past the end of the stream.
+    pub fn with_cue_sheet(mut self, cue_sheet: &CueSheet) -> Result<Self>
+    {
+        cue_sheet.validate()?;
+        self.cue_sheet = Some(cue_sheet.clone());
+        Ok(self)
+    }
+
+    /// Add a PADDING block after the other metadata blocks
+    ///
+    /// # Parameters
+    /// * `padding_bytes` - length of the padding, 0 to omit the block
+    ///
+    /// # Returns
+    /// `Result<Self>` - the encoder
+    ///
+    /// # Errors
+    /// Returns error if the padding is longer than a metadata block can be
+    ///
+    /// # Notes
+    /// Must be called before any samples are written. Tag editors can grow other
+    /// metadata blocks into the padding without rewriting the audio.
+    pub fn with_padding(mut self, padding_bytes: usize) -> Result<Self>
+    {
+        if padding_bytes > MAX_PADDING_BYTES
+        {
+            return Err(anyhow!("Padding must be at most {} bytes, got {}", MAX_PADDING_BYTES, padding_bytes));
+        }
+        self.padding_bytes = padding_bytes;
+        Ok(self)
+    }
+
+    /// Add TPDF dither when samples are quantized
+    ///
+    /// # Returns
+    /// `Self` - the encoder
+    ///
+    /// # Notes
+    /// Must be called before any samples are written. Adds triangular noise of one
+    /// step of the output bit depth before rounding, which turns quantization
+    /// distortion of quiet passages into a low, even noise floor. The noise is
+    /// seeded the same way every time, so the output is reproducible.
+    pub fn with_dither(mut self) -> Self
+    {
+        self.dither = Some(NoiseRng::new(DITHER_SEED));
+        self
+    }
+
+    /// Add samples to the stream
+    ///
+    /// # Parameters
+    /// * `samples` - interleaved samples as f32 values, any length
+    ///
+    /// # Returns
+    /// `Result<()>` - Ok if successful
+    ///
+    /// # Notes
+    /// Frames are encoded and written each time a full block has accumulated
+    pub fn write_samples(&mut self, samples: &[f32]) -> Result<()>
+    {
+        self.write_header()?;
+
+        let block_len = self.block_size * self.channels as usize;
+        for chunk in samples.chunks(block_len)
+        {
+            // convert f32 samples to integers of the stream's bit depth
+            match self.dither
+            {
+                Some(ref mut rng) =>
+                {
+                    let bits_per_sample = self.bits_per_sample;
+                    self.pending.extend(chunk.iter().map(|&s|
+                    {
+                        let dither = (rng.next_sample() + rng.next_sample()) * 0.5;
+                        quantize_dithered(s, bits_per_sample, dither)
+                    }));
+                }
+                None => self.pending.extend(chunk.iter().map(|&s| quantize(s, self.bits_per_sample))),
+            }
+
+            while self.pending.len() >= block_len
+            {
+                self.encode_pending(self.block_size)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode remaining samples and complete STREAMINFO
+    ///
+    /// # Returns
+    /// `Result<W>` - the writer, positioned at the end of the stream
+    ///
+    /// # Errors
+    /// Returns error if fewer than 16 samples per channel were written or writing fails
+    ///
+    /// # Notes
+    /// A trailing partial frame (fewer samples than channels) is dropped
+    pub fn finalize(mut self) -> Result<W>
+    {
+        self.write_header()?;
+
+        let remaining = self.pending.len() / self.channels as usize;
+        let total_samples = self.total_samples + remaining as u64;
+
+        // FLAC requires at least 16 samples per channel
+        if total_samples < 16
+        {
+            return Err(anyhow!(
+                "FLAC requires at least 16 samples per channel, got {}",
+                total_samples
+            ));
+        }
+
+        // a stream shorter than one block is a single frame of that length
+        let block_size = if self.frame_number == 0
+        {
+            remaining
+        }
+        else
+        {
+            self.block_size
+        };
+
+        if let Some(ref cue_sheet) = self.cue_sheet
+        {
+            if let Some(track) = cue_sheet.tracks.iter().find(|t| t.offset >= total_samples)
+            {
+                return Err(anyhow!(
+                    "Cue sheet track {} starts at sample {}, past the end of the stream ({} samples)",
+                    track.number,
+                    track.offset,
+                    total_samples
+                ));
+            }
+        }
+
+        if remaining > 0
+        {
+            self.encode_pending(remaining)?;
+        }
+
+        let md5 = self.md5.finalize();
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(self.streaminfo_pos.unwrap_or_default()))?;
+        self.write_streaminfo_block(block_size as u16, md5)?;
+        if let Some(lead_out_pos) = self.lead_out_pos
+        {
+            self.writer.seek(SeekFrom::Start(lead_out_pos))?;
+            self.writer.write_all(&total_samples.to_be_bytes())?;
+        }
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    /// Write the signature and metadata blocks if not yet written
+    ///
+    /// # Returns
+    /// `Result<()>` - Ok if successful
+    fn write_header(&mut self) -> Result<()>
+    {
+        if self.streaminfo_pos.is_some()
+        {
+            return Ok(());
+        }
+
+        // write FLAC signature
+        self.writer.write_all(&FLAC_SIGNATURE)?;
+        self.streaminfo_pos = Some(self.writer.stream_position()?);
+
+        // placeholder streaminfo, rewritten by finalize
+        self.write_streaminfo_block(self.block_size as u16, [0; 16])?;
+
+        if !self.comments.is_empty()
+        {
+            let mut header = BitWriter::new();
+            write_vorbis_comment(&mut header, &self.comments, self.cue_sheet.is_none() && self.padding_bytes == 0);
+            self.writer.write_all(&header.into_bytes())?;
+        }
+
+        if let Some(ref cue_sheet) = self.cue_sheet
+        {
+            // placeholder lead-out offset, rewritten by finalize
+            let mut header = BitWriter::new();
+            write_cue_sheet(&mut header, cue_sheet, 0, self.padding_bytes == 0);
+            self.writer.write_all(&header.into_bytes())?;
+            self.lead_out_pos = Some(self.writer.stream_position()? - CUE_TRACK_BYTES as u64);
+        }
+
+        if self.padding_bytes > 0
+        {
+            // last-metadata-block flag set, block type 1 (padding), 24-bit length
+            let header = (1u32 << 31) | (1 << 24) | self.padding_bytes as u32;
+            self.writer.write_all(&header.to_be_bytes())?;
+            self.writer.write_all(&vec![0; self.padding_bytes])?;
+        }
+        Ok(())
+    }
+
+    /// Encode the first samples of the pending buffer as one frame
+    ///
+    /// # Parameters
+    /// * `frame_samples` - samples per channel in the frame
+    ///
+    /// # Returns
+    /// `Result<()>` - Ok if successful
+    fn encode_pending(&mut self, frame_samples: usize) -> Result<()>
+    {
+        let sample_count = frame_samples * self.channels as usize;
+        let frame = &self.pending[..sample_count];
+
+        // a verbatim frame is the largest FLAC allows, so this rarely needs to grow
+        let mut frame_writer = BitWriter::with_capacity(sample_count * self.bits_per_sample as usize / 8 + MAX_FRAME_OVERHEAD);
+        let format = FrameFormat
+        {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bits_per_sample: self.bits_per_sample,
+            compression_level: self.compression_level,
+        };
+        encode_frame(&mut frame_writer, frame, &format, self.frame_number, frame_samples, &mut self.channel_samples)?;
+        let frame_bytes = frame_writer.into_bytes();
+        self.writer.write_all(&frame_bytes)?;
+
+        // MD5 covers the unencoded samples in little-endian byte order, at the stream's width
+        let bytes_per_sample = self.bits_per_sample as usize / 8;
+        let sample_bytes: Vec<u8> = frame.iter().flat_map(|s| s.to_le_bytes().into_iter().take(bytes_per_sample)).collect();
+        self.md5.update(&sample_bytes);
+
+        let frame_size = frame_bytes.len() as u32;
+        self.min_frame_size = if self.frame_number == 0 { frame_size } else { self.min_frame_size.min(frame_size) };
+        self.max_frame_size = self.max_frame_size.max(frame_size);
+
+        self.pending.drain(..sample_count);
+        self.total_samples += frame_samples as u64;
+        self.frame_number += 1;
+        Ok(())
+    }
+
+    /// Write the STREAMINFO block at the current writer position
+    ///
+    /// # Parameters
+    /// * `block_size` - block size in samples
+    /// * `md5` - MD5 digest of unencoded audio data
+    ///
+    /// # Returns
+    /// `Result<()>` - Ok if successful
+    fn write_streaminfo_block(&mut self, block_size: u16, md5: [u8; 16]) -> Result<()>
+    {
+        let mut header = BitWriter::new();
+        let info = StreamInfo
+        {
+            min_block_size: block_size,
+            max_block_size: block_size,
+            min_frame_size: self.min_frame_size,
+            max_frame_size: self.max_frame_size,
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            bits_per_sample: self.bits_per_sample,
+            total_samples: self.total_samples,
+            md5,
+        };
+        let is_last = self.comments.is_empty() && self.cue_sheet.is_none() && self.padding_bytes == 0;
+        write_streaminfo(&mut header, &info, is_last);
+        self.writer.write_all(&header.into_bytes())?;
+        Ok(())
+    }
+}
+
+/// MD5 of audio as FLAC computes it for STREAMINFO
+///
+/// # Parameters
+/// * `samples` - interleaved samples as f32 values
+/// * `bits_per_sample` - integer width the samples are quantized to (16 or 24)
+///
+/// # Returns
+/// `Result<[u8; 16]>` - MD5 digest of the quantized samples as little-endian bytes
+///
+/// # Errors
+/// Returns error if the bit depth is unsupported
+///
+/// # Notes
+/// Quantization matches the encoder, so at 16 bits the digest equals the one stored
+/// in a FLAC file exported from the same samples
+pub fn audio_md5(samples: &[f32], bits_per_sample: u8) -> Result<[u8; 16]>
+{
+    if bits_per_sample != 16 && bits_per_sample != 24
+    {
+        return Err(anyhow!("Unsupported bits per sample {}, expected 16 or 24", bits_per_sample));
+    }
+
+    let bytes_per_sample = bits_per_sample as usize / 8;
+    let mut md5 = MD5Context::new();
+    let mut bytes = Vec::new();
+    for chunk in samples.chunks(4096)
+    {
+        bytes.clear();
+        for &sample in chunk
+        {
+            bytes.extend_from_slice(&quantize(sample, bits_per_sample).to_le_bytes()[..bytes_per_sample]);
+        }
+        md5.update(&bytes);
+    }
+    Ok(md5.finalize())
+}
+
+/// Convert a sample to a signed integer of the given width
+///
+/// # Parameters
+/// * `sample` - sample as an f32 value, nominally -1.0 to 1.0
+/// * `bits_per_sample` - integer width
+///
+/// # Returns
+/// `i32` - scaled, rounded, and clamped sample
+fn quantize(sample: f32, bits_per_sample: u8) -> i32
+{
+    quantize_dithered(sample, bits_per_sample, 0.0)
+}
+
+/// Convert a sample to a signed integer of the given width, adding dither first
+///
+/// # Parameters
+/// * `sample` - sample as an f32 value, nominally -1.0 to 1.0
+/// * `bits_per_sample` - integer width
+/// * `dither` - noise to add, in steps of the output width
+///
+/// # Returns
+/// `i32` - scaled, dithered, rounded, and clamped sample
+///
+/// # Notes
+/// Scales by 2^(bits - 1), the inverse of how decoders convert integers back to
+/// floats, so decoded audio quantizes back to exactly the integers that were encoded
+fn quantize_dithered(sample: f32, bits_per_sample: u8, dither: f32) -> i32
+{
+    let scale = (1i32 << (bits_per_sample - 1)) as f32;
+    (sample * scale + dither).round().clamp(-scale, scale - 1.0) as i32
+}
+
+/// Main FLAC encoding function with compression level
+///
+/// # Parameters
+/// * `samples` - audio samples as f32 values
+/// * `sample_rate` - sample rate in Hz
+/// * `channels` - number of channels
+/// * `options` - bit depth, dither, compression level, block size, and metadata blocks
+/// * `progress` - callback receiving the fraction of samples encoded after each frame
+///
+/// # Returns
+/// `Result<Vec<u8>>` - encoded FLAC data
+///
+/// # Errors
+/// Returns error if fewer than 16 samples per channel, unsupported bit depth, invalid compression level,
+/// invalid block size, invalid cue sheet, or too much padding
+///
+/// # Notes
+/// Runs `FlacStreamEncoder` over an in-memory buffer, one block at a time
+pub fn encode_flac_with_level(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    options: &FlacOptions,
+    progress: &mut dyn FnMut(f64),
+) -> Result<Vec<u8>>
+{
+    let total_samples = samples.len() / channels.max(1) as usize;
+
+    // FLAC requires at least 16 samples per channel
+    if total_samples < 16
+    {
+        return Err(anyhow!(
+            "FLAC requires at least 16 samples per channel, got {}",
+            total_samples
+        ));
+    }
+
+    // reserve the uncompressed size, which compressed output stays under
+    let capacity = samples.len() * options.bits_per_sample as usize / 8 + MAX_FRAME_OVERHEAD;
+    let block_size = options.block_size.unwrap_or_else(|| block_size_for_level(options.compression_level));
+    let writer = Cursor::new(Vec::with_capacity(capacity));
+    let mut encoder = FlacStreamEncoder::new(writer, sample_rate, channels, options.bits_per_sample, options.compression_level)?
+        .with_block_size(block_size)?
+        .with_comments(options.comments)
+        .with_padding(options.padding_bytes)?;
+    if let Some(cue_sheet) = options.cue_sheet
+    {
+        encoder = encoder.with_cue_sheet(cue_sheet)?;
+    }
+    if options.dither
+    {
+        encoder = encoder.with_dither();
+    }
+
+    let block_len = block_size * channels as usize;
+    let mut encoded = 0;
+    for chunk in samples.chunks(block_len)
+    {
+        encoder.write_samples(chunk)?;
+        encoded += chunk.len();
+        progress(encoded as f64 / samples.len() as f64);
+    }
+
+    Ok(encoder.finalize()?.into_inner())
+}
 
 /// Export audio to FLAC file with specific compression level
 ///
@@ -1309,7 +2060,8 @@ pub fn encode_flac_with_level(
 /// * `samples` - audio samples as f32 values
 /// * `sample_rate` - sample rate in Hz
 /// * `channels` - number of channels
-/// * `compression_level` - compression level (0=fastest, 8=best)
+/// * `options` - bit depth, dither, compression level, block size, and metadata blocks
+/// * `progress` - callback receiving the fraction of samples encoded
 ///
 /// # Returns
 /// `Result<()>` - Ok if successful
@@ -1318,11 +2070,418 @@ pub fn export_to_flac_with_level(
     samples: &[f32],
     sample_rate: u32,
     channels: u16,
-    compression_level: u8,
+    options: &FlacOptions,
+    progress: &mut dyn FnMut(f64),
 ) -> Result<()>
 {
-    let flac_data = encode_flac_with_level(samples, sample_rate, channels, compression_level)?;
+    let flac_data = encode_flac_with_level(samples, sample_rate, channels, options, progress)?;
     let mut file = std::fs::File::create(path)?;
     file.write_all(&flac_data)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::DecoderOptions;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    /// MD5 of data fed to `update` in chunks of the given sizes, cycling through them, as hex
+    fn chunked_md5(data: &[u8], chunk_sizes: &[usize]) -> String
+    {
+        let mut context = MD5Context::new();
+        let mut offset = 0;
+        for &size in chunk_sizes.iter().cycle()
+        {
+            if offset >= data.len()
+            {
+                break;
+            }
+            let end = (offset + size).min(data.len());
+            context.update(&data[offset..end]);
+            offset = end;
+        }
+        context.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Check every reference digest when the data is fed in chunks of the given sizes
+    fn assert_reference_digests(chunk_sizes: &[usize])
+    {
+        // RFC 1321 test suite values, plus a longer buffer checked against Python's hashlib
+        let rfc_80 = "12345678901234567890123456789012345678901234567890123456789012345678901234567890";
+        let long: Vec<u8> = (0..1000u32).map(|i| ((i * 31 + 7) % 251) as u8).collect();
+        let vectors: [(&[u8], &str); 4] = [
+            (b"", "d41d8cd98f00b204e9800998ecf8427e"),
+            (b"abc", "900150983cd24fb0d6963f7d28e17f72"),
+            (rfc_80.as_bytes(), "57edf4a22be3c955ac49da2e2107b67a"),
+            (&long, "e722bbb0c3185d08f1a31567e66a825b"),
+        ];
+
+        for (data, expected) in vectors
+        {
+            assert_eq!(chunked_md5(data, chunk_sizes), expected, "{} bytes in chunks of {:?}", data.len(), chunk_sizes);
+        }
+    }
+
+    #[test]
+    fn md5_single_update_matches_reference()
+    {
+        assert_reference_digests(&[usize::MAX]);
+    }
+
+    #[test]
+    fn md5_one_byte_updates_match_reference()
+    {
+        assert_reference_digests(&[1]);
+    }
+
+    #[test]
+    fn md5_odd_sized_updates_match_reference()
+    {
+        assert_reference_digests(&[7, 13, 3]);
+    }
+
+    #[test]
+    fn md5_updates_straddling_blocks_match_reference()
+    {
+        // every update after the first crosses a 64-byte block boundary
+        assert_reference_digests(&[63, 2, 64]);
+        // a partial block, a fill that completes it, then several whole blocks at once
+        assert_reference_digests(&[60, 10, 129]);
+    }
+
+    /// Decode a FLAC stream with symphonia
+    ///
+    /// # Returns
+    /// `Vec<i32>` - interleaved samples at the stream's own bit depth
+    fn decode(flac_data: Vec<u8>) -> Vec<i32>
+    {
+        let stream = MediaSourceStream::new(Box::new(Cursor::new(flac_data)), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension("flac");
+        let mut format = symphonia::default::get_probe()
+            .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+            .unwrap()
+            .format;
+        let params = format.default_track().unwrap().codec_params.clone();
+        let shift = 32 - params.bits_per_sample.unwrap();
+        let mut decoder = symphonia::default::get_codecs().make(&params, &DecoderOptions { verify: true }).unwrap();
+
+        let mut samples = Vec::new();
+        while let Ok(packet) = format.next_packet()
+        {
+            let decoded = decoder.decode(&packet).unwrap();
+            let mut buffer = SampleBuffer::<i32>::new(decoded.capacity() as u64, *decoded.spec());
+            buffer.copy_interleaved_ref(decoded);
+            // symphonia left-justifies samples in the i32
+            samples.extend(buffer.samples().iter().map(|&sample| sample >> shift));
+        }
+        samples
+    }
+
+    /// Convert integer samples to floats that quantize back to exactly the same integers
+    fn exact_input(samples: &[i32], bits_per_sample: u8) -> Vec<f32>
+    {
+        let scale = (1i32 << (bits_per_sample - 1)) as f32;
+        let input: Vec<f32> = samples.iter().map(|&sample| sample as f32 / scale).collect();
+        assert!(input.iter().zip(samples).all(|(&f, &i)| quantize(f, bits_per_sample) == i));
+        input
+    }
+
+    /// Encode integer samples at every predictor order and check they decode unchanged
+    ///
+    /// # Parameters
+    /// * `samples` - interleaved integer samples
+    /// * `channels` - number of interleaved channels
+    /// * `bits_per_sample` - stream bit depth, 16 or 24
+    fn assert_round_trip(samples: &[i32], channels: u16, bits_per_sample: u8)
+    {
+        let input = exact_input(samples, bits_per_sample);
+
+        // levels 0 to 5 cover the verbatim subframe and fixed predictors of order 1 to 4
+        for compression_level in [0, 1, 2, 3, 5, 8]
+        {
+            let options = FlacOptions { bits_per_sample, compression_level, ..Default::default() };
+            let flac_data = encode_flac_with_level(&input, 44100, channels, &options, &mut |_| {}).unwrap();
+            assert_eq!(decode(flac_data), samples, "level {}", compression_level);
+        }
+    }
+
+    #[test]
+    fn escape_width_fits_boundary_values()
+    {
+        assert_eq!(escape_bits_per_sample(&[0]), 1);
+        assert_eq!(escape_bits_per_sample(&[-1]), 1);
+        assert_eq!(escape_bits_per_sample(&[-128, 127]), 8);
+        assert_eq!(escape_bits_per_sample(&[128]), 9);
+        assert_eq!(escape_bits_per_sample(&[-129]), 9);
+        assert_eq!(escape_bits_per_sample(&[i32::MIN + 1]), 32);
+    }
+
+    #[test]
+    fn incompressible_partition_takes_escape_code()
+    {
+        let mut rng = NoiseRng::new(17);
+        let residual: Vec<i32> = (0..256).map(|_| (rng.next_sample() * 32767.0) as i32).collect();
+
+        let mut writer = BitWriter::new();
+        encode_residual(&mut writer, &residual, 0, residual.len(), 0, 16).unwrap();
+        let bytes = writer.into_bytes();
+
+        // 2-bit method and 4-bit partition order, then the first partition's 4-bit parameter
+        let parameter = ((u16::from_be_bytes([bytes[0], bytes[1]]) >> 6) & 0b1111) as u8;
+        assert_eq!(parameter, 0b1111);
+    }
+
+    #[test]
+    fn extreme_residuals_round_trip()
+    {
+        // full-scale alternation gives the largest residuals each predictor order can produce
+        let alternating: Vec<i32> = (0..4096).map(|i| if i % 2 == 0 { i16::MIN as i32 } else { i16::MAX as i32 }).collect();
+        assert_round_trip(&alternating, 1, 16);
+
+        // steps between the extremes and zero land the most negative values in the residual
+        let steps: Vec<i32> = (0..4096).map(|i| [0, i16::MIN as i32, i16::MAX as i32, 0, -1, i16::MIN as i32][i % 6]).collect();
+        assert_round_trip(&steps, 1, 16);
+    }
+
+    #[test]
+    fn incompressible_residuals_round_trip()
+    {
+        let mut rng = NoiseRng::new(23);
+        let noise16: Vec<i32> = (0..2 * 4608).map(|_| (rng.next_sample() * 32768.0) as i32).collect();
+        assert_round_trip(&noise16, 2, 16);
+
+        let noise24: Vec<i32> = (0..2 * 4608).map(|_| (rng.next_sample() * 8388608.0) as i32).collect();
+        assert_round_trip(&noise24, 2, 24);
+
+        // quiet blocks between loud ones give neighbouring partitions very different widths
+        let mixed: Vec<i32> = (0..8192)
+            .map(|i| if (i / 256) % 2 == 0 { (rng.next_sample() * 32768.0) as i32 } else { (rng.next_sample() * 4.0) as i32 })
+            .collect();
+        assert_round_trip(&mixed, 1, 16);
+    }
+
+    #[test]
+    fn chunked_stream_matches_one_shot_encoder()
+    {
+        let mut rng = NoiseRng::new(31);
+        // a tone with a little noise, long enough for several blocks and a short final frame
+        let samples: Vec<f32> = crate::generators::sine_tone(440.0, 10000, 0.5, 44100, 2)
+            .iter()
+            .map(|&s| s + rng.next_sample() * 0.01)
+            .collect();
+
+        for (bits_per_sample, compression_level) in [(16, 5), (24, 0), (16, 8)]
+        {
+            let options = FlacOptions { bits_per_sample, compression_level, ..Default::default() };
+            let one_shot = encode_flac_with_level(&samples, 44100, 2, &options, &mut |_| {}).unwrap();
+
+            // single samples, an odd size that splits frames and is not a multiple of any
+            // block size, exactly one 4096-frame block, and a size larger than a block
+            for chunk_size in [1, 667, 2 * 4096, 9001]
+            {
+                let mut encoder = FlacStreamEncoder::new(Cursor::new(Vec::new()), 44100, 2, bits_per_sample, compression_level).unwrap();
+                for chunk in samples.chunks(chunk_size)
+                {
+                    encoder.write_samples(chunk).unwrap();
+                }
+                let streamed = encoder.finalize().unwrap().into_inner();
+                assert!(streamed == one_shot, "{}-bit level {} in chunks of {}", bits_per_sample, compression_level, chunk_size);
+            }
+        }
+    }
+
+    #[test]
+    fn reserved_writer_matches_growing_writer()
+    {
+        let mut growing = BitWriter::new();
+        let mut reserved = BitWriter::with_capacity(64);
+        for writer in [&mut growing, &mut reserved]
+        {
+            writer.write_bits(0b101, 3);
+            writer.write_bits(0xABCD, 16);
+            writer.write_bits(1, 1);
+        }
+        assert_eq!(growing.buffer, reserved.buffer);
+
+        // the trailing 4 bits come out as a padded final byte
+        assert_eq!(reserved.into_bytes(), vec![0b1011_0101, 0b0111_1001, 0b1011_0000]);
+        assert_eq!(growing.into_bytes().len(), 3);
+    }
+
+    #[test]
+    fn reserved_frame_writer_never_reallocates()
+    {
+        let block_size = 4096;
+        let channels = 2;
+        // a loud tone predicts well, so no level should outgrow the verbatim-sized reservation
+        let samples: Vec<i32> = (0..block_size * channels).map(|i| (((i / channels) as f32 * 0.05).sin() * 30000.0) as i32).collect();
+
+        for compression_level in [0, 5, 8]
+        {
+            let mut writer = BitWriter::with_capacity(samples.len() * 16 / 8 + MAX_FRAME_OVERHEAD);
+            let reserved = writer.buffer.as_ptr();
+            let mut channel_samples = vec![Vec::new(); channels];
+            let format = FrameFormat { channels: channels as u16, sample_rate: 44100, bits_per_sample: 16, compression_level };
+            encode_frame(&mut writer, &samples, &format, 0, block_size, &mut channel_samples).unwrap();
+            assert_eq!(writer.buffer.as_ptr(), reserved, "level {}", compression_level);
+        }
+    }
+
+    #[test]
+    fn reused_scratch_matches_fresh_scratch()
+    {
+        let channels = 2;
+        let mut rng = NoiseRng::new(9);
+        let samples: Vec<i32> = (0..10000 * channels).map(|_| (rng.next_sample() * 20000.0) as i32).collect();
+        // full blocks, a short final block, then a full block again so stale scratch would show
+        let frames = [(0, 4096), (4096, 4096), (8192, 1808), (0, 4096)];
+
+        let format = FrameFormat { channels: channels as u16, sample_rate: 44100, bits_per_sample: 16, compression_level: 5 };
+        let mut reused_writer = BitWriter::new();
+        let mut fresh_writer = BitWriter::new();
+        let mut scratch = Vec::new();
+        let mut reserved = Vec::new();
+        for (frame_number, &(start, block_size)) in frames.iter().enumerate()
+        {
+            let frame = &samples[start * channels..(start + block_size) * channels];
+            encode_frame(&mut reused_writer, frame, &format, frame_number as u32, block_size, &mut scratch).unwrap();
+            encode_frame(&mut fresh_writer, frame, &format, frame_number as u32, block_size, &mut Vec::new()).unwrap();
+
+            // after the first frame the scratch keeps its allocations
+            let pointers: Vec<*const i32> = scratch.iter().map(|channel: &Vec<i32>| channel.as_ptr()).collect();
+            if frame_number == 0
+            {
+                reserved = pointers;
+            }
+            else
+            {
+                assert_eq!(pointers, reserved, "frame {}", frame_number);
+            }
+        }
+        assert_eq!(reused_writer.into_bytes(), fresh_writer.into_bytes());
+    }
+
+    #[test]
+    fn chosen_block_size_round_trips()
+    {
+        let samples: Vec<i32> = (0..10000 * 2).map(|i| (((i / 2) as f32 * 0.03).sin() * 20000.0) as i32 + i % 2).collect();
+        let input = exact_input(&samples, 16);
+
+        // 2048 has its own header code; 1000 takes the uncommon block size escape
+        for block_size in [2048, 1000]
+        {
+            let options = FlacOptions { block_size: Some(block_size), ..Default::default() };
+            let flac_data = encode_flac_with_level(&input, 44100, 2, &options, &mut |_| {}).unwrap();
+            // STREAMINFO's minimum and maximum block sizes follow the marker and block header
+            assert_eq!(u16::from_be_bytes([flac_data[8], flac_data[9]]), block_size as u16);
+            assert_eq!(u16::from_be_bytes([flac_data[10], flac_data[11]]), block_size as u16);
+            assert_eq!(decode(flac_data), samples, "block size {}", block_size);
+        }
+
+        for block_size in [15, 65536]
+        {
+            let options = FlacOptions { block_size: Some(block_size), ..Default::default() };
+            assert!(encode_flac_with_level(&input, 44100, 2, &options, &mut |_| {}).is_err());
+        }
+    }
+
+    /// Split the metadata of a FLAC stream into (block type, is last, body) triples
+    fn metadata_blocks(flac_data: &[u8]) -> Vec<(u8, bool, &[u8])>
+    {
+        assert_eq!(&flac_data[..4], b"fLaC");
+        let mut blocks = Vec::new();
+        let mut offset = 4;
+        loop
+        {
+            let header = &flac_data[offset..offset + 4];
+            let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+            let is_last = header[0] & 0x80 != 0;
+            blocks.push((header[0] & 0x7F, is_last, &flac_data[offset + 4..offset + 4 + length]));
+            offset += 4 + length;
+            if is_last
+            {
+                return blocks;
+            }
+        }
+    }
+
+    #[test]
+    fn cue_sheet_block_parses_back()
+    {
+        let cue_sheet = CueSheet
+        {
+            catalog_number: "1234567890123".to_string(),
+            lead_in_samples: 0,
+            is_cd: false,
+            tracks: vec![
+                CueTrack { offset: 0, number: 1, isrc: "USABC1234567".to_string(), indices: vec![CueIndex { offset: 0, number: 1 }] },
+                CueTrack
+                {
+                    offset: 22050,
+                    number: 2,
+                    isrc: String::new(),
+                    indices: vec![CueIndex { offset: 0, number: 0 }, CueIndex { offset: 4410, number: 1 }],
+                },
+            ],
+        };
+        let input: Vec<f32> = (0..44100 * 2).map(|i| ((i / 2) as f32 * 0.05).sin() * 0.5).collect();
+        let options = FlacOptions { cue_sheet: Some(&cue_sheet), ..Default::default() };
+        let flac_data = encode_flac_with_level(&input, 44100, 2, &options, &mut |_| {}).unwrap();
+
+        let blocks = metadata_blocks(&flac_data);
+        let &(_, _, body) = blocks.iter().find(|(block_type, _, _)| *block_type == 5).unwrap();
+        let u64_at = |at: usize| u64::from_be_bytes(body[at..at + 8].try_into().unwrap());
+        assert_eq!(&body[..13], b"1234567890123");
+        assert!(body[13..128].iter().all(|&b| b == 0));
+        assert_eq!(u64_at(128), 0);
+        // the CD flag is the top bit of the byte after the lead-in
+        assert_eq!(body[136] & 0x80, 0);
+        assert_eq!(body[395], 3);
+
+        // each track: offset, number, ISRC, flags and reserved, index count, then 12 bytes per index
+        let mut at = 396;
+        let mut tracks = Vec::new();
+        for _ in 0..3
+        {
+            let index_count = body[at + 35] as usize;
+            let indices: Vec<(u64, u8)> = (0..index_count).map(|i| (u64_at(at + 36 + 12 * i), body[at + 36 + 12 * i + 8])).collect();
+            tracks.push((u64_at(at), body[at + 8], body[at + 9..at + 21].to_vec(), indices));
+            at += 36 + 12 * index_count;
+        }
+        assert_eq!(at, body.len());
+        assert_eq!(tracks[0], (0, 1, b"USABC1234567".to_vec(), vec![(0, 1)]));
+        assert_eq!(tracks[1], (22050, 2, vec![0; 12], vec![(0, 0), (4410, 1)]));
+        // the lead-out starts at the end of the stream
+        assert_eq!(tracks[2], (44100, 255, vec![0; 12], vec![]));
+
+        assert_eq!(decode(flac_data).len(), input.len());
+    }
+
+    #[test]
+    fn padding_block_closes_the_metadata()
+    {
+        let input: Vec<f32> = (0..4410).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let comments = [("TITLE".to_string(), "Padded".to_string())];
+        let options = FlacOptions { comments: &comments, padding_bytes: 4096, ..Default::default() };
+        let flac_data = encode_flac_with_level(&input, 44100, 1, &options, &mut |_| {}).unwrap();
+
+        // STREAMINFO, VORBIS_COMMENT, then the padding as the only block marked last
+        let blocks = metadata_blocks(&flac_data);
+        let layout: Vec<(u8, bool)> = blocks.iter().map(|&(block_type, is_last, _)| (block_type, is_last)).collect();
+        assert_eq!(layout, [(0, false), (4, false), (1, true)]);
+        let (_, _, padding) = blocks[2];
+        assert_eq!(padding.len(), 4096);
+        assert!(padding.iter().all(|&b| b == 0));
+        assert_eq!(decode(flac_data).len(), input.len());
+
+        let unpadded = encode_flac_with_level(&input, 44100, 1, &FlacOptions::default(), &mut |_| {}).unwrap();
+        assert!(metadata_blocks(&unpadded).iter().all(|&(block_type, _, _)| block_type != 1));
+    }
+}
@@ -0,0 +1,180 @@
+//! Signal generators for synthesizing test and reference tracks
+
+/// Number of random rows summed by the Voss-McCartney pink noise generator
+const PINK_NOISE_ROWS: usize = 16;
+
+/// Small seedable pseudo-random number generator (xorshift64*)
+pub struct NoiseRng
+{
+    state: u64,
+}
+
+impl NoiseRng
+{
+    /// Create a generator from a seed
+    ///
+    /// # Parameters
+    /// * `seed` - seed value (the same seed always produces the same sequence)
+    ///
+    /// # Returns
+    /// `NoiseRng` - seeded generator
+    pub fn new(seed: u64) -> Self
+    {
+        // scramble the seed with splitmix64 so that small seeds still give good state
+        let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+
+        NoiseRng
+        {
+            state: if z == 0 { 0x9E3779B97F4A7C15 } else { z },
+        }
+    }
+
+    /// Generate the next 64-bit value
+    ///
+    /// # Returns
+    /// `u64` - pseudo-random value
+    pub fn next_u64(&mut self) -> u64
+    {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    /// Generate a uniformly distributed sample
+    ///
+    /// # Returns
+    /// `f32` - value in [-1.0, 1.0)
+    pub fn next_sample(&mut self) -> f32
+    {
+        // use the top 24 bits for an exactly representable f32 mantissa
+        let value = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        value * 2.0 - 1.0
+    }
+}
+
+/// Generate white noise
+///
+/// # Parameters
+/// * `frames` - number of frames to generate
+/// * `channels` - number of interleaved channels
+/// * `seed` - random seed
+///
+/// # Returns
+/// `Vec<f32>` - interleaved samples uniformly distributed in [-1.0, 1.0)
+pub fn white_noise(frames: usize, channels: usize, seed: u64) -> Vec<f32>
+{
+    let mut rng = NoiseRng::new(seed);
+    (0..frames * channels).map(|_| rng.next_sample()).collect()
+}
+
+/// Generate pink noise
+///
+/// # Parameters
+/// * `frames` - number of frames to generate
+/// * `channels` - number of interleaved channels
+/// * `seed` - random seed
+///
+/// # Returns
+/// `Vec<f32>` - interleaved samples in [-1.0, 1.0)
+///
+/// # Notes
+/// Uses the Voss-McCartney algorithm: a set of random rows is summed, and row k is
+/// refreshed every 2^k samples, giving a spectrum that falls at about 3 dB per octave.
+/// Each channel has its own independent rows.
+pub fn pink_noise(frames: usize, channels: usize, seed: u64) -> Vec<f32>
+{
+    let mut rng = NoiseRng::new(seed);
+    let mut rows = vec![[0.0f32; PINK_NOISE_ROWS]; channels];
+    let mut sums = vec![0.0f32; channels];
+
+    for (channel_rows, sum) in rows.iter_mut().zip(sums.iter_mut())
+    {
+        for value in channel_rows.iter_mut()
+        {
+            *value = rng.next_sample();
+            *sum += *value;
+        }
+    }
+
+    let mut output = Vec::with_capacity(frames * channels);
+    for frame in 0..frames
+    {
+        // the row to refresh is given by the number of trailing zeros of the counter
+        let row = ((frame + 1).trailing_zeros() as usize).min(PINK_NOISE_ROWS - 1);
+
+        for ch in 0..channels
+        {
+            let new_value = rng.next_sample();
+            sums[ch] += new_value - rows[ch][row];
+            rows[ch][row] = new_value;
+
+            let white = rng.next_sample();
+            output.push((sums[ch] + white) / (PINK_NOISE_ROWS + 1) as f32);
+        }
+    }
+
+    output
+}
+
+/// Generate a sine tone
+///
+/// # Parameters
+/// * `frequency` - tone frequency in Hz
+/// * `frames` - number of frames to generate
+/// * `amplitude` - peak amplitude
+/// * `sample_rate` - sample rate in Hz
+/// * `channels` - number of interleaved channels
+///
+/// # Returns
+/// `Vec<f32>` - interleaved samples, identical in every channel
+pub fn sine_tone(frequency: f64, frames: usize, amplitude: f64, sample_rate: u32, channels: usize) -> Vec<f32>
+{
+    let step = 2.0 * std::f64::consts::PI * frequency / sample_rate as f64;
+    let mut output = Vec::with_capacity(frames * channels);
+    for frame in 0..frames
+    {
+        let sample = (amplitude * (step * frame as f64).sin()) as f32;
+        output.extend(std::iter::repeat_n(sample, channels));
+    }
+    output
+}
+
+/// Generate a logarithmic sine sweep
+///
+/// # Parameters
+/// * `start_hz` - starting frequency in Hz
+/// * `end_hz` - ending frequency in Hz
+/// * `frames` - number of frames to generate
+/// * `amplitude` - peak amplitude
+/// * `sample_rate` - sample rate in Hz
+/// * `channels` - number of interleaved channels
+///
+/// # Returns
+/// `Vec<f32>` - interleaved samples, identical in every channel
+///
+/// # Notes
+/// Frequency rises (or falls) exponentially, so each octave takes the same time
+pub fn log_sweep(start_hz: f64, end_hz: f64, frames: usize, amplitude: f64, sample_rate: u32, channels: usize) -> Vec<f32>
+{
+    let duration = frames as f64 / sample_rate as f64;
+    let log_ratio = (end_hz / start_hz).ln();
+    if log_ratio.abs() < 1e-12 || duration <= 0.0
+    {
+        return sine_tone(start_hz, frames, amplitude, sample_rate, channels);
+    }
+
+    let k = 2.0 * std::f64::consts::PI * start_hz * duration / log_ratio;
+    let mut output = Vec::with_capacity(frames * channels);
+    for frame in 0..frames
+    {
+        let t = frame as f64 / sample_rate as f64;
+        let phase = k * ((t / duration * log_ratio).exp() - 1.0);
+        let sample = (amplitude * phase.sin()) as f32;
+        output.extend(std::iter::repeat_n(sample, channels));
+    }
+    output
+}
@@ -1,18 +1,40 @@
+use numpy::{PyArray1, PyReadonlyArray1};
 use pyo3::prelude::*;
-use pyo3::exceptions::PyRuntimeError;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+mod analysis;
 mod audio_engine;
+mod callbacks;
+mod denoise;
+mod effects;
+mod errors;
+mod fft;
+mod filters;
+mod generators;
+mod pcm;
 mod playback;
+mod project;
 mod flac;
+mod resample;
+mod samples;
+mod waveform;
 
-use audio_engine::AudioEngine;
+use audio_engine::{AudioEngine, BusLimiter, ExportOptions, ExportTags, MonoFold, TrackDetails, EXPORT_FORMATS, IMPORT_FORMATS};
+use callbacks::PositionCallback;
+use flac::{CueIndex, CueSheet, CueTrack};
+use errors::{ExportError, LoadError, PlaybackError, SoundlyError};
+use resample::ResampleQuality;
 
 /// Python-accessible audio editor class
-#[pyclass(unsendable)]
+///
+/// # Notes
+/// Safe to share between Python threads; calls are serialized on the engine lock
+#[pyclass]
 struct AudioEditor
 {
     engine: Arc<Mutex<AudioEngine>>,
+    position_callback: Option<PositionCallback>,
 }
 
 #[pymethods]
@@ -28,244 +50,2410 @@ impl AudioEditor
         Ok(AudioEditor
         {
             engine: Arc::new(Mutex::new(AudioEngine::new())),
+            position_callback: None,
         })
     }
 
+    /// List the file extensions that can be imported
+    ///
+    /// # Returns
+    /// `Vec<String>` - lowercase extensions without the leading dot
+    #[staticmethod]
+    fn supported_import_formats() -> Vec<String>
+    {
+        IMPORT_FORMATS.iter().map(|f| f.to_string()).collect()
+    }
+
+    /// List the file extensions that can be exported
+    ///
+    /// # Returns
+    /// `Vec<String>` - lowercase extensions without the leading dot
+    #[staticmethod]
+    fn supported_export_formats() -> Vec<String>
+    {
+        EXPORT_FORMATS.iter().map(|f| f.to_string()).collect()
+    }
+
+    /// List the centre frequencies of the graphic EQ bands
+    ///
+    /// # Returns
+    /// `Vec<f64>` - band frequencies in Hz, lowest first
+    #[staticmethod]
+    fn graphic_eq_bands() -> Vec<f64>
+    {
+        filters::GRAPHIC_EQ_BANDS_HZ.to_vec()
+    }
+
+    /// Enter a `with` block
+    ///
+    /// # Returns
+    /// `AudioEditor` - this editor
+    fn __enter__(slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self>
+    {
+        slf
+    }
+
+    /// Leave a `with` block, stopping playback and releasing all tracks
+    ///
+    /// # Parameters
+    /// * `_exc_type` - exception type if the block raised, otherwise None
+    /// * `_exc_value` - exception instance if the block raised, otherwise None
+    /// * `_traceback` - traceback if the block raised, otherwise None
+    ///
+    /// # Returns
+    /// `bool` - always false so exceptions propagate
+    fn __exit__(&mut self, _exc_type: Option<&PyAny>, _exc_value: Option<&PyAny>, _traceback: Option<&PyAny>) -> PyResult<bool>
+    {
+        self.stop()?;
+        self.clear_tracks()?;
+        Ok(false)
+    }
+
     /// Load an audio file from disk as a new track
     ///
     /// # Parameters
-    /// * `path` - filesystem path to audio file (WAV, FLAC, or MP3)
+    /// * `path` - filesystem path to audio file (WAV, FLAC, or MP3)
+    /// * `resample` - resample to the first track's sample rate if they differ (default false)
+    ///
+    /// # Returns
+    /// `PyResult<(u32, usize, Option<u32>)>` - (sample_rate, channels, mismatched_sample_rate)
+    ///
+    /// # Errors
+    /// Returns error if file cannot be read or decoded
+    #[pyo3(signature = (path, resample=false))]
+    fn load_file(&mut self, path: String, resample: bool) -> PyResult<(u32, usize, Option<u32>)>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .load_file(&path, resample)
+            .map_err(|e| LoadError::new_err(format!("Failed to load file: {}", e)))
+    }
+
+    /// Load several audio files from disk as new tracks, decoding them in parallel
+    ///
+    /// # Parameters
+    /// * `paths` - filesystem paths to audio files
+    /// * `resample` - resample to the project sample rate if they differ (default false)
+    ///
+    /// # Returns
+    /// `PyResult<Vec<(u32, usize, Option<u32>)>>` - (sample_rate, channels, mismatched_sample_rate) per file, in path order
+    ///
+    /// # Errors
+    /// Returns error if any file cannot be read or decoded, in which case no tracks are added
+    #[pyo3(signature = (paths, resample=false))]
+    fn load_files(&mut self, paths: Vec<String>, resample: bool) -> PyResult<Vec<(u32, usize, Option<u32>)>>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .load_files(&paths, resample)
+            .map_err(|e| LoadError::new_err(format!("Failed to load file: {}", e)))
+    }
+
+    /// Load an audio file as a new track without decoding it into memory
+    ///
+    /// # Parameters
+    /// * `path` - filesystem path to audio file
+    ///
+    /// # Returns
+    /// `PyResult<(u32, usize, Option<u32>)>` - (sample_rate, channels, mismatched_sample_rate)
+    ///
+    /// # Errors
+    /// Returns error if file cannot be read or decoded
+    ///
+    /// # Notes
+    /// Samples are decoded to a memory-mapped temporary file, which suits very long
+    /// recordings. The track is copied into memory the first time it is edited.
+    fn load_file_streaming(&mut self, path: String) -> PyResult<(u32, usize, Option<u32>)>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .load_file_streaming(&path)
+            .map_err(|e| LoadError::new_err(format!("Failed to load file: {}", e)))
+    }
+
+    /// Load audio held in memory as a new track
+    ///
+    /// # Parameters
+    /// * `data` - encoded file contents, e.g. from a download or a zip archive
+    /// * `ext` - optional file extension hinting at the format, such as 'wav' or 'mp3'
+    ///
+    /// # Returns
+    /// `PyResult<(u32, usize, Option<u32>)>` - (sample_rate, channels, mismatched_sample_rate)
+    ///
+    /// # Errors
+    /// Returns error if the data cannot be decoded
+    #[pyo3(signature = (data, ext=None))]
+    fn load_bytes(&mut self, data: &[u8], ext: Option<String>) -> PyResult<(u32, usize, Option<u32>)>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .load_bytes(data, ext.as_deref())
+            .map_err(|e| LoadError::new_err(format!("Failed to load file: {}", e)))
+    }
+
+    /// Decode an audio file and append it to the end of an existing track
+    ///
+    /// # Parameters
+    /// * `path` - filesystem path to audio file
+    /// * `dest_index` - index of the track to extend
+    ///
+    /// # Returns
+    /// `PyResult<f64>` - duration appended in seconds
+    ///
+    /// # Errors
+    /// Returns error if the track index is invalid, the file cannot be read or
+    /// decoded, or its channels cannot be converted
+    ///
+    /// # Notes
+    /// Joins recordings split across several files into one track. The file is
+    /// resampled and channel-converted to match the track.
+    fn load_file_append(&mut self, path: String, dest_index: usize) -> PyResult<f64>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .load_file_append(&path, dest_index)
+            .map_err(|e| LoadError::new_err(format!("Failed to load file: {}", e)))
+    }
+
+    /// Synthesize a noise track
+    ///
+    /// # Parameters
+    /// * `duration` - length in seconds
+    /// * `kind` - noise colour ('white' or 'pink')
+    /// * `seed` - random seed
+    /// * `sample_rate` - sample rate in Hz (default 44100)
+    /// * `channels` - number of channels (default 2)
+    ///
+    /// # Returns
+    /// `PyResult<usize>` - index of the new track
+    ///
+    /// # Errors
+    /// Returns error if noise type or parameters are invalid
+    #[pyo3(signature = (duration, kind="white".to_string(), seed=0, sample_rate=44100, channels=2))]
+    fn add_noise_track(&mut self, duration: f64, kind: String, seed: u64, sample_rate: u32, channels: usize) -> PyResult<usize>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .add_noise_track(duration, &kind, seed, sample_rate, channels)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to generate noise: {}", e)))
+    }
+
+    /// Synthesize a sine tone track
+    ///
+    /// # Parameters
+    /// * `frequency` - tone frequency in Hz
+    /// * `duration` - length in seconds
+    /// * `amplitude` - peak amplitude 0.0-1.0 (default 0.5)
+    /// * `sample_rate` - sample rate in Hz (default 44100)
+    /// * `channels` - number of channels (default 2)
+    ///
+    /// # Returns
+    /// `PyResult<usize>` - index of the new track
+    ///
+    /// # Errors
+    /// Returns error if parameters are invalid
+    #[pyo3(signature = (frequency, duration, amplitude=0.5, sample_rate=44100, channels=2))]
+    fn add_tone_track(&mut self, frequency: f64, duration: f64, amplitude: f64, sample_rate: u32, channels: usize) -> PyResult<usize>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .add_tone_track(frequency, duration, amplitude, sample_rate, channels)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to generate tone: {}", e)))
+    }
+
+    /// Synthesize a logarithmic sine sweep track
+    ///
+    /// # Parameters
+    /// * `start_hz` - starting frequency in Hz
+    /// * `end_hz` - ending frequency in Hz
+    /// * `duration` - length in seconds
+    /// * `amplitude` - peak amplitude 0.0-1.0 (default 0.5)
+    /// * `sample_rate` - sample rate in Hz (default 44100)
+    /// * `channels` - number of channels (default 2)
+    ///
+    /// # Returns
+    /// `PyResult<usize>` - index of the new track
+    ///
+    /// # Errors
+    /// Returns error if parameters are invalid
+    #[pyo3(signature = (start_hz, end_hz, duration, amplitude=0.5, sample_rate=44100, channels=2))]
+    fn add_sweep_track(&mut self, start_hz: f64, end_hz: f64, duration: f64, amplitude: f64,
+                       sample_rate: u32, channels: usize) -> PyResult<usize>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .add_sweep_track(start_hz, end_hz, duration, amplitude, sample_rate, channels)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to generate sweep: {}", e)))
+    }
+
+    /// Add a track from a NumPy array
+    ///
+    /// # Parameters
+    /// * `data` - contiguous float32 array of interleaved samples
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of interleaved channels
+    /// * `name` - track name
+    ///
+    /// # Returns
+    /// `PyResult<usize>` - index of the new track
+    ///
+    /// # Errors
+    /// Returns error if the array is not contiguous or its length is not divisible by `channels`
+    fn add_track_from_array(&mut self, data: PyReadonlyArray1<f32>, sample_rate: u32, channels: usize, name: String) -> PyResult<usize>
+    {
+        let samples = data.as_slice()?.to_vec();
+        self.engine
+            .lock()
+            .unwrap()
+            .add_track_from_samples(samples, sample_rate, channels, &name)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to add track: {}", e)))
+    }
+
+    /// Import a headerless PCM file as a new track
+    ///
+    /// # Parameters
+    /// * `path` - input file path
+    /// * `sample_rate` - sample rate in Hz of the stored audio
+    /// * `channels` - number of interleaved channels in the stored audio
+    /// * `sample_format` - sample encoding ('s16', 's24', 's32', or 'f32')
+    /// * `endianness` - byte order ('le' or 'be')
+    ///
+    /// # Returns
+    /// `PyResult<usize>` - index of the new track
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be read or the format is unknown
+    #[pyo3(signature = (path, sample_rate, channels, sample_format="s16", endianness="le"))]
+    fn import_raw(&mut self, path: String, sample_rate: u32, channels: usize, sample_format: &str, endianness: &str) -> PyResult<usize>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .import_raw(&path, sample_rate, channels, sample_format, endianness)
+            .map_err(|e| LoadError::new_err(format!("Failed to load raw audio: {}", e)))
+    }
+
+    /// Clear all loaded tracks
+    ///
+    /// # Notes
+    /// Also clears any position callback
+    ///
+    /// # Returns
+    /// `PyResult<()>` - always Ok
+    fn clear_tracks(&mut self) -> PyResult<()>
+    {
+        self.position_callback = None;
+        self.engine.lock().unwrap().clear_tracks();
+        Ok(())
+    }
+
+    /// Save the session to a project file
+    ///
+    /// # Parameters
+    /// * `path` - output file path
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be written
+    ///
+    /// # Notes
+    /// Track samples are embedded in the file, so it does not depend on the original audio files
+    fn save_project(&self, path: String) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .save_project(&path)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to save project: {}", e)))
+    }
+
+    /// Replace the session with the contents of a project file
+    ///
+    /// # Parameters
+    /// * `path` - project file path
+    ///
+    /// # Returns
+    /// `PyResult<usize>` - number of tracks loaded
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be read or is not a valid project
+    fn load_project(&mut self, path: String) -> PyResult<usize>
+    {
+        let result = self.engine
+            .lock()
+            .unwrap()
+            .load_project(&path)
+            .map_err(|e| LoadError::new_err(format!("Failed to load project: {}", e)));
+        self.refresh_position_probe();
+        result
+    }
+
+    /// Remove a single track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to remove
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn remove_track(&mut self, track_index: usize) -> PyResult<()>
+    {
+        let result = self.engine
+            .lock()
+            .unwrap()
+            .remove_track(track_index)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to remove track: {}", e)));
+        self.refresh_position_probe();
+        result
+    }
+
+    /// Get number of loaded tracks
+    ///
+    /// # Returns
+    /// `usize` - number of tracks
+    fn get_track_count(&self) -> PyResult<usize>
+    {
+        Ok(self.engine.lock().unwrap().get_track_count())
+    }
+
+    /// Get information about all loaded tracks
+    ///
+    /// # Returns
+    /// `Vec<(String, u32, usize, f64, f64)>` - vector of (name, sample_rate, channels, duration, start_offset)
+    fn get_track_info(&self) -> PyResult<Vec<(String, u32, usize, f64, f64)>>
+    {
+        Ok(self.engine.lock().unwrap().get_track_info())
+    }
+
+    /// Get information about one track, including the format of its source file
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    ///
+    /// # Returns
+    /// `PyResult<(String, u32, usize, f64, f64, Option<u32>, Option<String>)>` - (name, sample_rate,
+    /// channels, duration, start_offset, bits_per_sample, codec); bit depth and codec are None
+    /// when unknown, e.g. for generated tracks or MP3
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn get_track_details(&self, track_index: usize) -> PyResult<TrackDetails>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .get_track_details(track_index)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to get track details: {}", e)))
+    }
+
+    /// Get the exact number of frames in a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    ///
+    /// # Returns
+    /// `PyResult<usize>` - samples per channel
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn get_track_frame_count(&self, track_index: usize) -> PyResult<usize>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .get_track_frame_count(track_index)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to get frame count: {}", e)))
+    }
+
+    /// Convert a timeline position to a frame within a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `time` - timeline position in seconds
+    ///
+    /// # Returns
+    /// `PyResult<usize>` - frame index, clamped to 0 through the track's frame count
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid or time is not finite
+    ///
+    /// # Notes
+    /// Matches the conversion edits use, so regions built from these frames land exactly on them
+    fn time_to_frame(&self, track_index: usize, time: f64) -> PyResult<usize>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .time_to_frame(track_index, time)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to convert time: {}", e)))
+    }
+
+    /// Convert a frame within a track to a timeline position
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `frame` - frame index within the track
+    ///
+    /// # Returns
+    /// `PyResult<f64>` - timeline position of the frame's start in seconds
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn frame_to_time(&self, track_index: usize, frame: usize) -> PyResult<f64>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .frame_to_time(track_index, frame)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to convert frame: {}", e)))
+    }
+
+    /// Get the tags read from a track's source file
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    ///
+    /// # Returns
+    /// `PyResult<HashMap<String, String>>` - dict of tags such as 'title' and 'artist', empty if none
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn get_track_metadata(&self, track_index: usize) -> PyResult<HashMap<String, String>>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .get_track_metadata(track_index)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to get track metadata: {}", e)))
+    }
+
+    /// Rename a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    /// * `name` - new track name
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn rename_track(&mut self, track_index: usize, name: String) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .rename_track(track_index, &name)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to rename track: {}", e)))
+    }
+
+    /// Set the start offset for a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    /// * `offset` - new start offset in seconds, negative to trim the head of the track
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if track index or offset is invalid
+    fn set_track_offset(&mut self, track_index: usize, offset: f64) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .set_track_offset(track_index, offset)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to set track offset: {}", e)))
+    }
+
+    /// Set the gain automation envelope for a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    /// * `points` - list of (time, gain_db) breakpoints, time in seconds from the track start
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if track index or a breakpoint is invalid
+    ///
+    /// # Notes
+    /// Gain is interpolated linearly in amplitude between breakpoints. Pass an empty
+    /// list to return the track to unity gain.
+    fn set_gain_envelope(&mut self, track_index: usize, points: Vec<(f64, f64)>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .set_gain_envelope(track_index, points)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to set gain envelope: {}", e)))
+    }
+
+    /// Resample a track to a new sample rate
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    /// * `target_rate` - new sample rate in Hz
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if track index or sample rate is invalid
+    fn resample_track(&mut self, track_index: usize, target_rate: u32) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .resample_track(track_index, target_rate)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to resample track: {}", e)))
+    }
+
+    /// Convert a track to mono by averaging its channels
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn track_to_mono(&mut self, track_index: usize) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .track_to_mono(track_index)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to convert track: {}", e)))
+    }
+
+    /// Convert a track to stereo
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to modify
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    ///
+    /// # Notes
+    /// Mono is duplicated; tracks with more than two channels are folded down
+    fn track_to_stereo(&mut self, track_index: usize) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .track_to_stereo(track_index)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to convert track: {}", e)))
+    }
+
+    /// Split a track into two tracks at a point in time
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to split
+    /// * `time` - split point in seconds on the timeline
+    ///
+    /// # Returns
+    /// `PyResult<(usize, usize)>` - indices of the two resulting tracks
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid or split point is outside the track
+    fn split_track(&mut self, track_index: usize, time: f64) -> PyResult<(usize, usize)>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .split_track(track_index, time)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to split track: {}", e)))
+    }
+
+    /// Duplicate a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to copy
+    ///
+    /// # Returns
+    /// `PyResult<usize>` - index of the new track
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn duplicate_track(&mut self, track_index: usize) -> PyResult<usize>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .duplicate_track(track_index)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to duplicate track: {}", e)))
+    }
+
+    /// Get waveform data for a specific time range for all tracks
+    ///
+    /// # Parameters
+    /// * `start_time` - start of range in seconds
+    /// * `end_time` - end of range in seconds
+    /// * `num_pixels` - desired number of data points
+    ///
+    /// # Returns
+    /// `Vec<Vec<(f32, f32, f32, f32)>>` - waveform data per track
+    ///
+    /// # Notes
+    /// Returns separate waveform data for each track
+    fn get_waveform_for_range(&self, start_time: f64, end_time: f64, num_pixels: usize) -> PyResult<Vec<Vec<(f32, f32, f32, f32)>>>
+    {
+        Ok(self.engine.lock().unwrap().get_waveform_for_range(start_time, end_time, num_pixels))
+    }
+
+    /// Get waveform data for a specific time range for one track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `start_time` - start of range in seconds
+    /// * `end_time` - end of range in seconds
+    /// * `num_pixels` - desired number of data points
+    ///
+    /// # Returns
+    /// `Vec<(f32, f32, f32, f32)>` - waveform data for the track
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn get_track_waveform_for_range(&self, track_index: usize, start_time: f64, end_time: f64, num_pixels: usize) -> PyResult<Vec<(f32, f32, f32, f32)>>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .get_track_waveform_for_range(track_index, start_time, end_time, num_pixels)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to get waveform: {}", e)))
+    }
+
+    /// Append one track's audio to the end of another
+    ///
+    /// # Parameters
+    /// * `dest_index` - index of the track to extend
+    /// * `src_index` - index of the track whose audio is appended
+    /// * `remove_source` - remove the source track afterwards (default false)
+    ///
+    /// # Returns
+    /// `PyResult<usize>` - index of the destination track afterwards
+    ///
+    /// # Errors
+    /// Returns error if either index is invalid, they are equal, or the channels cannot be converted
+    #[pyo3(signature = (dest_index, src_index, remove_source=false))]
+    fn append_track(&mut self, dest_index: usize, src_index: usize, remove_source: bool) -> PyResult<usize>
+    {
+        let result = self.engine
+            .lock()
+            .unwrap()
+            .append_track(dest_index, src_index, remove_source)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to append track: {}", e)));
+        self.refresh_position_probe();
+        result
+    }
+
+    /// Get the samples of a track as a NumPy array
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    ///
+    /// # Returns
+    /// `numpy.ndarray` - float32 array of interleaved samples (a copy)
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    ///
+    /// # Notes
+    /// Use `get_track_info` for the sample rate and channel count
+    fn get_track_samples<'py>(&self, py: Python<'py>, track_index: usize) -> PyResult<&'py PyArray1<f32>>
+    {
+        let engine = self.engine.lock().unwrap();
+        let samples = engine
+            .get_track_samples(track_index)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to get samples: {}", e)))?;
+        Ok(PyArray1::from_slice(py, samples))
+    }
+
+    /// Get the MD5 checksum of a track's audio
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `bits_per_sample` - integer width the samples are quantized to before hashing, 16 or 24
+    ///   (default None for the track's source bit depth, or 16 if that is neither)
+    ///
+    /// # Returns
+    /// `PyResult<String>` - lowercase hex digest, matching FLAC's STREAMINFO MD5 at the same bit depth
+    ///
+    /// # Errors
+    /// Returns error if track index or bit depth is invalid
+    #[pyo3(signature = (track_index, bits_per_sample=None))]
+    fn get_track_md5(&self, track_index: usize, bits_per_sample: Option<u8>) -> PyResult<String>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .get_track_md5(track_index, bits_per_sample)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to compute MD5: {}", e)))
+    }
+
+    /// Get the samples of a track within a region as a NumPy array
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `start_time` - start of region in seconds
+    /// * `end_time` - end of region in seconds
+    ///
+    /// # Returns
+    /// `numpy.ndarray` - float32 array of interleaved samples (a copy)
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn get_region_samples<'py>(&self, py: Python<'py>, track_index: usize, start_time: f64, end_time: f64) -> PyResult<&'py PyArray1<f32>>
+    {
+        let engine = self.engine.lock().unwrap();
+        let samples = engine
+            .get_region_samples(track_index, start_time, end_time)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to get samples: {}", e)))?;
+        Ok(PyArray1::from_slice(py, samples))
+    }
+
+    /// Get peak and RMS levels over a whole track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    ///
+    /// # Returns
+    /// `(f32, f32)` - (peak, rms) linear amplitudes
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn get_track_levels(&self, track_index: usize) -> PyResult<(f32, f32)>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .get_track_levels(track_index)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to measure levels: {}", e)))
+    }
+
+    /// Get peak and RMS levels over a region of a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `start_time` - start of region in seconds
+    /// * `end_time` - end of region in seconds
+    ///
+    /// # Returns
+    /// `(f32, f32)` - (peak, rms) linear amplitudes
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn get_region_levels(&self, track_index: usize, start_time: f64, end_time: f64) -> PyResult<(f32, f32)>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .get_region_levels(track_index, start_time, end_time)
+            .map_err(|e| SoundlyError::new_err(format!("Failed to measure levels: {}", e)))
+    }
+
+    /// Find silent regions in a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `threshold_db` - level below which audio counts as silent, in dBFS
+    /// * `min_duration` - minimum length of a silent region in seconds
+    ///
+    /// # Returns
+    /// `Vec<(f64, f64)>` - (start, end) times of each silent region in seconds
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    fn detect_silence(&self, track_index: usize, threshold_db: f64, min_duration: f64) -> PyResult<Vec<(f64, f64)>>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .detect_silence(track_index, threshold_db, min_duration)
+            .map_err(|e| SoundlyError::new_err(format!("Silence detection error: {}", e)))
+    }
+
+    /// Find clipped regions in a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `min_run` - minimum number of consecutive full-scale samples to report (default 1)
+    ///
+    /// # Returns
+    /// `Vec<(f64, f64)>` - (start, end) times of each clipped region in seconds
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid
+    #[pyo3(signature = (track_index, min_run=1))]
+    fn detect_clipping(&self, track_index: usize, min_run: usize) -> PyResult<Vec<(f64, f64)>>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .detect_clipping(track_index, min_run)
+            .map_err(|e| SoundlyError::new_err(format!("Clipping detection error: {}", e)))
+    }
+
+    /// Trim leading and trailing silence from a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track
+    /// * `threshold_db` - level below which audio counts as silent, in dBFS
+    /// * `margin` - seconds of silence to keep before and after the audio (default 0.01)
+    ///
+    /// # Returns
+    /// `(f64, f64)` - seconds removed from the start and end of the track
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid or the track contains only silence
+    #[pyo3(signature = (track_index, threshold_db, margin=0.01))]
+    fn trim_silence(&self, track_index: usize, threshold_db: f64, margin: f64) -> PyResult<(f64, f64)>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .trim_silence(track_index, threshold_db, margin)
+            .map_err(|e| SoundlyError::new_err(format!("Trim error: {}", e)))
+    }
+
+    /// Get the sample rate of the first loaded track
+    ///
+    /// # Returns
+    /// `u32` - sample rate in Hz
+    fn get_sample_rate(&self) -> PyResult<u32>
+    {
+        Ok(self.engine.lock().unwrap().get_sample_rate())
+    }
+
+    /// Get the duration of the longest track
+    ///
+    /// # Returns
+    /// `f64` - duration in seconds
+    fn get_duration(&self) -> PyResult<f64>
+    {
+        Ok(self.engine.lock().unwrap().get_duration())
+    }
+
+    /// Get the number of audio channels (maximum across all tracks)
+    ///
+    /// # Returns
+    /// `usize` - number of channels (1=mono, 2=stereo)
+    fn get_channels(&self) -> PyResult<usize>
+    {
+        Ok(self.engine.lock().unwrap().get_channels())
+    }
+
+    /// Start audio playback
+    ///
+    /// # Parameters
+    /// * `start_time` - optional start time in seconds (None to resume from current position)
+    /// * `end_time` - optional end time in seconds (None to play to end)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if playback cannot be started
+    fn play(&mut self, start_time: Option<f64>, end_time: Option<f64>) -> PyResult<()>
+    {
+        let result = self.engine
+            .lock()
+            .unwrap()
+            .play(start_time, end_time)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)));
+        self.refresh_position_probe();
+        result
+    }
+
+    /// Play a single track on its own
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to audition
+    /// * `start_time` - optional start time in seconds
+    /// * `end_time` - optional end time in seconds (defaults to the end of the track)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid or playback cannot be started
+    #[pyo3(signature = (track_index, start_time=None, end_time=None))]
+    fn play_track(&mut self, track_index: usize, start_time: Option<f64>, end_time: Option<f64>) -> PyResult<()>
+    {
+        let result = self.engine
+            .lock()
+            .unwrap()
+            .play_track(track_index, start_time, end_time)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)));
+        self.refresh_position_probe();
+        result
+    }
+
+    /// Play a region repeatedly
+    ///
+    /// # Parameters
+    /// * `start_time` - loop start in seconds
+    /// * `end_time` - loop end in seconds
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the region is empty or playback cannot be started
+    fn set_loop(&mut self, start_time: f64, end_time: f64) -> PyResult<()>
+    {
+        let result = self.engine
+            .lock()
+            .unwrap()
+            .play_loop(start_time, end_time)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)));
+        self.refresh_position_probe();
+        result
+    }
+
+    /// Play a region backwards without modifying any samples
+    ///
+    /// # Parameters
+    /// * `start_time` - optional start of the region in seconds (None for the project start)
+    /// * `end_time` - optional end of the region in seconds (None for the project end)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the region is empty or playback cannot be started
+    #[pyo3(signature = (start_time=None, end_time=None))]
+    fn play_reverse(&mut self, start_time: Option<f64>, end_time: Option<f64>) -> PyResult<()>
+    {
+        let result = self.engine
+            .lock()
+            .unwrap()
+            .play_reverse(start_time, end_time)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)));
+        self.refresh_position_probe();
+        result
+    }
+
+    /// Play several regions back to back without gaps
+    ///
+    /// # Parameters
+    /// * `regions` - list of (start, end) tuples in seconds, in play order
+    /// * `crossfade_ms` - overlap between consecutive regions in milliseconds (default 0 for a hard cut)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if no regions are given, a region is empty, or playback cannot be started
+    #[pyo3(signature = (regions, crossfade_ms=0.0))]
+    fn play_queue(&mut self, regions: Vec<(f64, f64)>, crossfade_ms: f64) -> PyResult<()>
+    {
+        let result = self.engine
+            .lock()
+            .unwrap()
+            .play_queue(&regions, crossfade_ms)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)));
+        self.refresh_position_probe();
+        result
+    }
+
+    /// Get which queued region is playing
+    ///
+    /// # Returns
+    /// `PyResult<Option<usize>>` - index into the regions passed to `play_queue`, None if no queue is playing
+    fn get_current_queue_index(&self) -> PyResult<Option<usize>>
+    {
+        Ok(self.engine.lock().unwrap().get_current_queue_index())
+    }
+
+    /// Stop looping and let playback run to the end of the current region
+    ///
+    /// # Returns
+    /// `PyResult<()>` - always Ok
+    fn clear_loop(&mut self) -> PyResult<()>
+    {
+        self.engine.lock().unwrap().clear_loop();
+        Ok(())
+    }
+
+    /// Set A/B loop markers
+    ///
+    /// # Parameters
+    /// * `a` - loop start marker in seconds
+    /// * `b` - loop end marker in seconds (swapped with `a` if earlier)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the markers are not finite or coincide
+    fn set_ab_loop(&mut self, a: f64, b: f64) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .set_ab_loop(a, b)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)))
+    }
+
+    /// Clear the A/B loop markers
+    ///
+    /// # Returns
+    /// `PyResult<()>` - always Ok
+    fn clear_ab_loop(&mut self) -> PyResult<()>
+    {
+        self.engine.lock().unwrap().clear_ab_loop();
+        Ok(())
+    }
+
+    /// Get the A/B loop markers
+    ///
+    /// # Returns
+    /// `PyResult<Option<(f64, f64)>>` - (A, B) in seconds, None if no A/B loop is set
+    fn get_ab_loop(&self) -> PyResult<Option<(f64, f64)>>
+    {
+        Ok(self.engine.lock().unwrap().get_ab_loop())
+    }
+
+    /// List the names of available output devices
+    ///
+    /// # Returns
+    /// `Vec<String>` - device names, empty if no audio devices are available
+    fn list_output_devices(&self) -> PyResult<Vec<String>>
+    {
+        Ok(self.engine.lock().unwrap().list_output_devices())
+    }
+
+    /// Select the output device used for playback
+    ///
+    /// # Parameters
+    /// * `device_name` - name of the output device, or None for the default device
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if playback cannot be restarted on the new device
+    ///
+    /// # Notes
+    /// Playing audio continues on the new device in the mode it was started in
+    #[pyo3(signature = (device_name=None))]
+    fn set_output_device(&mut self, device_name: Option<String>) -> PyResult<()>
+    {
+        let result = self.engine
+            .lock()
+            .unwrap()
+            .set_output_device(device_name)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)));
+        self.refresh_position_probe();
+        result
+    }
+
+    /// Set the playback speed
+    ///
+    /// # Parameters
+    /// * `speed` - rate multiplier, where 1.0 is normal speed
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if speed is not positive
+    ///
+    /// # Notes
+    /// This is varispeed monitoring, so pitch changes along with speed
+    fn set_playback_speed(&mut self, speed: f64) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .set_playback_speed(speed)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)))
+    }
+
+    /// Set the master stereo balance of playback
+    ///
+    /// # Parameters
+    /// * `balance` - -1.0 for full left, 0.0 for centre (default), 1.0 for full right
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if balance is outside -1.0 to 1.0
+    ///
+    /// # Notes
+    /// Only turns down the side it moves away from, and only affects stereo playback, not exports
+    fn set_balance(&mut self, balance: f32) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .set_balance(balance)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)))
+    }
+
+    /// Get the master stereo balance of playback
+    ///
+    /// # Returns
+    /// `PyResult<f32>` - -1.0 for full left, 0.0 for centre, 1.0 for full right
+    fn get_balance(&self) -> PyResult<f32>
+    {
+        Ok(self.engine.lock().unwrap().get_balance())
+    }
+
+    /// Set the quality of every sample rate conversion
+    ///
+    /// # Parameters
+    /// * `quality` - 'linear', 'cubic', 'sinc_fast' (default), or 'sinc_best'
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the quality name is unknown
+    ///
+    /// # Notes
+    /// Used when loading with resampling, resampling tracks, changing speed or
+    /// pitch, and playing on a device that runs at another rate. 'linear' and
+    /// 'cubic' are fastest but alias when downsampling.
+    fn set_resample_quality(&mut self, quality: &str) -> PyResult<()>
+    {
+        let quality = ResampleQuality::parse(quality).map_err(|e| SoundlyError::new_err(format!("Resample error: {}", e)))?;
+        self.engine.lock().unwrap().set_resample_quality(quality);
+        Ok(())
+    }
+
+    /// Get the quality of sample rate conversion
+    ///
+    /// # Returns
+    /// `PyResult<String>` - 'linear', 'cubic', 'sinc_fast', or 'sinc_best'
+    fn get_resample_quality(&self) -> PyResult<String>
+    {
+        Ok(self.engine.lock().unwrap().get_resample_quality().name().to_string())
+    }
+
+    /// Set the length of the fade applied when playback starts, pauses, or stops
+    ///
+    /// # Parameters
+    /// * `fade_ms` - fade length in milliseconds, 0 for instant changes (default 5)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the length is negative
+    fn set_fade_length(&mut self, fade_ms: f64) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .set_fade_length(fade_ms)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)))
+    }
+
+    /// Set the length of the crossfade applied when seeking during playback
+    ///
+    /// # Parameters
+    /// * `fade_ms` - crossfade length in milliseconds, 0 to jump instantly (default 10)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the length is negative
+    fn set_seek_crossfade(&mut self, fade_ms: f64) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .set_seek_crossfade(fade_ms)
+            .map_err(|e| PlaybackError::new_err(format!("Playback error: {}", e)))
+    }
+
+    /// Pause audio playback without resetting position
+    ///
+    /// # Returns
+    /// `PyResult<()>` - always Ok
+    fn pause(&mut self) -> PyResult<()>
+    {
+        self.engine.lock().unwrap().pause();
+        Ok(())
+    }
+
+    /// Stop audio playback and reset position
+    ///
+    /// # Notes
+    /// Also clears any position callback
+    ///
+    /// # Returns
+    /// `PyResult<()>` - always Ok
+    fn stop(&mut self) -> PyResult<()>
+    {
+        self.position_callback = None;
+        self.engine.lock().unwrap().stop();
+        Ok(())
+    }
+
+    /// Register a callable to receive the playback position periodically
+    ///
+    /// # Parameters
+    /// * `callback` - callable taking the position in seconds, or None to clear
+    /// * `interval_ms` - time between updates in milliseconds (default 50)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - always Ok
+    ///
+    /// # Notes
+    /// The callable runs on a background timer thread while audio is playing, not
+    /// in the audio callback. Exceptions it raises are printed and ignored. The
+    /// callback is cleared by `stop` and `clear_tracks`.
+    #[pyo3(signature = (callback, interval_ms=50))]
+    fn set_position_callback(&mut self, callback: Option<PyObject>, interval_ms: u64) -> PyResult<()>
+    {
+        // drop any previous callback first so its thread stops
+        self.position_callback = None;
+        if let Some(callback) = callback
+        {
+            let probe = self.engine.lock().unwrap().position_probe();
+            self.position_callback = Some(PositionCallback::start(callback, interval_ms, probe));
+        }
+        Ok(())
+    }
+
+    /// Check if audio is currently playing
+    ///
+    /// # Returns
+    /// `bool` - true if playing, false otherwise
+    fn is_playing(&self) -> PyResult<bool>
+    {
+        Ok(self.engine.lock().unwrap().is_playing())
+    }
+
+    /// Check if playback ran to the end on its own
+    ///
+    /// # Returns
+    /// `bool` - true once playback has reached its end, false while playing, paused, or after `stop`
+    ///
+    /// # Notes
+    /// Unlike `is_playing`, this tells a natural end apart from the user stopping playback
+    fn has_finished(&self) -> PyResult<bool>
+    {
+        Ok(self.engine.lock().unwrap().has_finished())
+    }
+
+    /// Get the number of buffer underruns on the output stream
+    ///
+    /// # Returns
+    /// `usize` - times the device ran out of samples, useful for diagnosing dropouts
+    fn get_underrun_count(&self) -> PyResult<usize>
+    {
+        Ok(self.engine.lock().unwrap().get_underrun_count())
+    }
+
+    /// Get details of the most recent buffer underrun
+    ///
+    /// # Returns
+    /// `Option<(usize, usize)>` - (frames the device was given, frames that elapsed before more arrived), None if none occurred
+    fn get_last_underrun(&self) -> PyResult<Option<(usize, usize)>>
+    {
+        Ok(self.engine.lock().unwrap().get_last_underrun())
+    }
+
+    /// Get output level meter readings
+    ///
+    /// # Returns
+    /// `Vec<f32>` - decaying peak level of each output channel (linear, 0.0-1.0)
+    fn get_output_levels(&self) -> PyResult<Vec<f32>>
+    {
+        Ok(self.engine.lock().unwrap().get_output_levels())
+    }
+
+    /// Get current playback position
+    ///
+    /// # Returns
+    /// `f64` - position in seconds
+    fn get_playback_position(&self) -> PyResult<f64>
+    {
+        Ok(self.engine.lock().unwrap().get_playback_position())
+    }
+
+    /// Set playback position
+    ///
+    /// # Parameters
+    /// * `position` - new position in seconds
+    ///
+    /// # Returns
+    /// `PyResult<()>` - always Ok
+    fn set_playback_position(&mut self, position: f64) -> PyResult<()>
+    {
+        self.engine.lock().unwrap().set_playback_position(position);
+        Ok(())
+    }
+
+    /// Delete a region of audio from specified tracks
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds
+    /// * `end_time` - end of region in seconds
+    /// * `track_indices` - list of track indices to delete from
+    /// * `crossfade_ms` - equal-power crossfade across the join in milliseconds (default 0 for a butt join)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if region or crossfade length is invalid
+    #[pyo3(signature = (start_time, end_time, track_indices, crossfade_ms=0.0))]
+    fn delete_region(&mut self, start_time: f64, end_time: f64, track_indices: Vec<usize>, crossfade_ms: f64) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .delete_region(start_time, end_time, &track_indices, crossfade_ms)
+            .map_err(|e| SoundlyError::new_err(format!("Delete error: {}", e)))
+    }
+
+    /// Keep only a region of audio in specified tracks
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds
+    /// * `end_time` - end of region in seconds
+    /// * `track_indices` - list of track indices to crop
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the region is empty or reversed
+    fn crop_to_region(&mut self, start_time: f64, end_time: f64, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .crop_to_region(start_time, end_time, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Crop error: {}", e)))
+    }
+
+    /// Overwrite audio in place with samples from a NumPy array
+    ///
+    /// # Parameters
+    /// * `position` - timeline position in seconds where the new audio starts
+    /// * `data` - contiguous float32 array of interleaved samples, e.g. from `get_region_samples`
+    /// * `sample_rate` - sample rate of `data` in Hz
+    /// * `channels` - number of interleaved channels in `data`
+    /// * `track_indices` - list of track indices to overwrite
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the array is malformed or its channels cannot be mapped onto a track
+    ///
+    /// # Notes
+    /// Track lengths never change; audio past the end of a track is dropped
+    fn overwrite_region(&mut self, position: f64, data: PyReadonlyArray1<f32>, sample_rate: u32, channels: usize, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        let samples = data.as_slice()?;
+        self.engine
+            .lock()
+            .unwrap()
+            .overwrite_region(position, samples, sample_rate, channels, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Overwrite error: {}", e)))
+    }
+
+    /// Remove DC offset from specified tracks
+    ///
+    /// # Parameters
+    /// * `track_indices` - list of track indices to process
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    fn remove_dc_offset(&mut self, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .remove_dc_offset(&track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("DC offset removal error: {}", e)))
+    }
+
+    /// Invert the polarity of a region in specified tracks
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds
+    /// * `end_time` - end of region in seconds
+    /// * `track_indices` - list of track indices to process
+    /// * `channel` - optional single channel to invert (None for all channels)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    #[pyo3(signature = (start_time, end_time, track_indices, channel=None))]
+    fn invert_region(&mut self, start_time: f64, end_time: f64, track_indices: Vec<usize>, channel: Option<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .invert_region(start_time, end_time, &track_indices, channel)
+            .map_err(|e| SoundlyError::new_err(format!("Invert error: {}", e)))
+    }
+
+    /// Change the length of a region without changing its pitch
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds
+    /// * `end_time` - end of region in seconds
+    /// * `ratio` - length ratio (> 1 lengthens, < 1 shortens)
+    /// * `track_indices` - list of track indices to process
+    /// * `window_ms` - optional analysis window length in milliseconds (None for default 40)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if ratio is not positive
+    #[pyo3(signature = (start_time, end_time, ratio, track_indices, window_ms=None))]
+    fn time_stretch(&mut self, start_time: f64, end_time: f64, ratio: f64, track_indices: Vec<usize>, window_ms: Option<f64>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .time_stretch(start_time, end_time, ratio, &track_indices, window_ms)
+            .map_err(|e| SoundlyError::new_err(format!("Time stretch error: {}", e)))
+    }
+
+    /// Shift the pitch of a region without changing its length
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds
+    /// * `end_time` - end of region in seconds
+    /// * `semitones` - pitch shift in semitones (positive raises pitch)
+    /// * `track_indices` - list of track indices to process
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    fn pitch_shift(&mut self, start_time: f64, end_time: f64, semitones: f64, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .pitch_shift(start_time, end_time, semitones, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Pitch shift error: {}", e)))
+    }
+
+    /// Change the speed of a region, altering both pitch and length
+    ///
+    /// # Parameters
+    /// * `start_time` - start of region in seconds
+    /// * `end_time` - end of region in seconds
+    /// * `factor` - speed factor (2.0 plays twice as fast and an octave higher)
+    /// * `track_indices` - list of track indices to process
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if factor is not positive
+    fn change_speed(&mut self, start_time: f64, end_time: f64, factor: f64, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .change_speed(start_time, end_time, factor, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Speed change error: {}", e)))
+    }
+
+    /// Crossfade the audio either side of a point in time
+    ///
+    /// # Parameters
+    /// * `time` - join point in seconds
+    /// * `duration` - crossfade length in seconds
+    /// * `track_indices` - list of track indices to process
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if duration is negative
+    fn crossfade_at(&mut self, time: f64, duration: f64, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .crossfade_at(time, duration, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Crossfade error: {}", e)))
+    }
+
+    /// Apply a low-pass filter to specified tracks
+    ///
+    /// # Parameters
+    /// * `cutoff_hz` - cutoff frequency in Hz
+    /// * `track_indices` - list of track indices to process
+    /// * `q` - optional filter Q (None for 0.707)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if cutoff is not positive
+    #[pyo3(signature = (cutoff_hz, track_indices, q=None))]
+    fn apply_lowpass(&mut self, cutoff_hz: f64, track_indices: Vec<usize>, q: Option<f64>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_lowpass(cutoff_hz, &track_indices, q)
+            .map_err(|e| SoundlyError::new_err(format!("Filter error: {}", e)))
+    }
+
+    /// Apply a high-pass filter to specified tracks
+    ///
+    /// # Parameters
+    /// * `cutoff_hz` - cutoff frequency in Hz
+    /// * `track_indices` - list of track indices to process
+    /// * `q` - optional filter Q (None for 0.707)
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if cutoff is not positive
+    #[pyo3(signature = (cutoff_hz, track_indices, q=None))]
+    fn apply_highpass(&mut self, cutoff_hz: f64, track_indices: Vec<usize>, q: Option<f64>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_highpass(cutoff_hz, &track_indices, q)
+            .map_err(|e| SoundlyError::new_err(format!("Filter error: {}", e)))
+    }
+
+    /// Apply a peaking EQ band to specified tracks
+    ///
+    /// # Parameters
+    /// * `freq_hz` - centre frequency in Hz
+    /// * `gain_db` - boost (positive) or cut (negative) in dB
+    /// * `q` - filter Q (bandwidth)
+    /// * `track_indices` - list of track indices to process
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if frequency or Q is not positive
+    fn apply_peaking_eq(&mut self, freq_hz: f64, gain_db: f64, q: f64, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_peaking_eq(freq_hz, gain_db, q, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("EQ error: {}", e)))
+    }
+
+    /// Apply a graphic EQ to specified tracks
+    ///
+    /// # Parameters
+    /// * `gains_db` - boost or cut in dB for each band of `graphic_eq_bands()`, lowest first
+    /// * `track_indices` - list of track indices to process
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the number of gains does not match the number of bands
+    fn apply_graphic_eq(&mut self, gains_db: Vec<f64>, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_graphic_eq(&gains_db, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Graphic EQ error: {}", e)))
+    }
+
+    /// Remove mains hum from specified tracks
+    ///
+    /// # Parameters
+    /// * `base_hz` - hum frequency in Hz, usually 50 or 60
+    /// * `harmonics` - number of harmonics above `base_hz` to notch as well
+    /// * `track_indices` - list of track indices to process
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if base frequency is not positive
+    fn remove_hum(&mut self, base_hz: f64, harmonics: usize, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .remove_hum(base_hz, harmonics, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Hum removal error: {}", e)))
+    }
+
+    /// Apply dynamic range compression to specified tracks
+    ///
+    /// # Parameters
+    /// * `threshold_db` - level above which gain is reduced, in dBFS
+    /// * `ratio` - compression ratio (e.g. 4.0 for 4:1)
+    /// * `attack_ms` - attack time in milliseconds
+    /// * `release_ms` - release time in milliseconds
+    /// * `makeup_db` - gain applied after compression in dB
+    /// * `track_indices` - list of track indices to process
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if ratio is below 1 or times are negative
+    fn apply_compressor(&mut self, threshold_db: f64, ratio: f64, attack_ms: f64, release_ms: f64,
+                        makeup_db: f64, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_compressor(threshold_db, ratio, attack_ms, release_ms, makeup_db, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Compressor error: {}", e)))
+    }
+
+    /// Reduce sibilance on specified tracks
+    ///
+    /// # Parameters
+    /// * `freq_hz` - centre of the sibilance band in Hz, typically 5-8 kHz
+    /// * `threshold_db` - band level above which it is turned down, in dBFS
+    /// * `ratio` - compression ratio applied to the band (e.g. 4.0 for 4:1)
+    /// * `track_indices` - list of track indices to process
+    ///
+    /// # Returns
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the frequency is not positive or the ratio is below 1
+    fn apply_deesser(&mut self, freq_hz: f64, threshold_db: f64, ratio: f64, track_indices: Vec<usize>) -> PyResult<()>
+    {
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_deesser(freq_hz, threshold_db, ratio, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("De-esser error: {}", e)))
+    }
+
+    /// Apply slow automatic gain control to specified tracks
+    ///
+    /// # Parameters
+    /// * `target_db` - RMS level to keep the signal near, in dBFS
+    /// * `max_gain_db` - largest boost applied, in dB
+    /// * `window_ms` - length of the RMS window in milliseconds, a few seconds for speech
+    /// * `track_indices` - list of track indices to process
     ///
     /// # Returns
-    /// `PyResult<(u32, usize, Option<u32>)>` - (sample_rate, channels, mismatched_sample_rate)
+    /// `PyResult<()>` - Ok if successful
     ///
     /// # Errors
-    /// Returns error if file cannot be read or decoded
-    fn load_file(&mut self, path: String) -> PyResult<(u32, usize, Option<u32>)>
+    /// Returns error if the target is above 0 dBFS, the gain cap is negative, or the window is not positive
+    ///
+    /// # Notes
+    /// Suited to levelling spoken word; loud passages are turned down as well as quiet ones up
+    fn apply_agc(&mut self, target_db: f64, max_gain_db: f64, window_ms: f64, track_indices: Vec<usize>) -> PyResult<()>
     {
         self.engine
             .lock()
             .unwrap()
-            .load_file(&path)
-            .map_err(|e| PyRuntimeError::new_err(format!("Failed to load file: {}", e)))
+            .apply_agc(target_db, max_gain_db, window_ms, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("AGC error: {}", e)))
     }
 
-    /// Clear all loaded tracks
+    /// Normalize the mix so its peak within a region hits a target level
+    ///
+    /// # Parameters
+    /// * `target_db` - peak level for the mix in dBFS, at most 0 (default -1)
+    /// * `start_time` - optional start of the region to measure in seconds (None for the project start)
+    /// * `end_time` - optional end of the region to measure in seconds (None for the project end)
     ///
     /// # Returns
-    /// `PyResult<()>` - always Ok
-    fn clear_tracks(&mut self) -> PyResult<()>
+    /// `PyResult<f64>` - gain applied to every track heard in the region, in dB
+    ///
+    /// # Errors
+    /// Returns error if the target or region is invalid, or the mix is silent there
+    #[pyo3(signature = (target_db=-1.0, start_time=None, end_time=None))]
+    fn normalize_mix(&mut self, target_db: f64, start_time: Option<f64>, end_time: Option<f64>) -> PyResult<f64>
     {
-        self.engine.lock().unwrap().clear_tracks();
-        Ok(())
+        self.engine
+            .lock()
+            .unwrap()
+            .normalize_mix(target_db, start_time, end_time)
+            .map_err(|e| SoundlyError::new_err(format!("Normalize error: {}", e)))
     }
 
-    /// Get number of loaded tracks
+    /// Apply a brickwall limiter to specified tracks
+    ///
+    /// # Parameters
+    /// * `ceiling_db` - maximum output level in dBFS
+    /// * `release_ms` - gain recovery time in milliseconds
+    /// * `track_indices` - list of track indices to process
     ///
     /// # Returns
-    /// `usize` - number of tracks
-    fn get_track_count(&self) -> PyResult<usize>
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if release time is negative
+    fn apply_limiter(&mut self, ceiling_db: f64, release_ms: f64, track_indices: Vec<usize>) -> PyResult<()>
     {
-        Ok(self.engine.lock().unwrap().get_track_count())
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_limiter(ceiling_db, release_ms, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Limiter error: {}", e)))
     }
 
-    /// Get information about all loaded tracks
+    /// Apply reverb to specified tracks
+    ///
+    /// # Parameters
+    /// * `room_size` - room size 0.0-1.0
+    /// * `damping` - high frequency damping 0.0-1.0
+    /// * `wet` - wet/dry balance 0.0-1.0
+    /// * `track_indices` - list of track indices to process
     ///
     /// # Returns
-    /// `Vec<(String, u32, usize, f64, f64)>` - vector of (name, sample_rate, channels, duration, start_offset)
-    fn get_track_info(&self) -> PyResult<Vec<(String, u32, usize, f64, f64)>>
+    /// `PyResult<()>` - Ok if successful
+    fn apply_reverb(&mut self, room_size: f64, damping: f64, wet: f64, track_indices: Vec<usize>) -> PyResult<()>
     {
-        Ok(self.engine.lock().unwrap().get_track_info())
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_reverb(room_size, damping, wet, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Reverb error: {}", e)))
     }
 
-    /// Set the start offset for a track
+    /// Apply a bitcrusher to specified tracks
     ///
     /// # Parameters
-    /// * `track_index` - index of the track to modify
-    /// * `offset` - new start offset in seconds
+    /// * `bits` - effective bit depth 1-24
+    /// * `downsample_factor` - number of frames each held sample lasts (1 for no rate reduction)
+    /// * `track_indices` - list of track indices to process
     ///
     /// # Returns
     /// `PyResult<()>` - Ok if successful
     ///
     /// # Errors
-    /// Returns error if track index is invalid
-    fn set_track_offset(&mut self, track_index: usize, offset: f64) -> PyResult<()>
+    /// Returns error if bits or downsample factor is out of range
+    fn apply_bitcrush(&mut self, bits: u32, downsample_factor: usize, track_indices: Vec<usize>) -> PyResult<()>
     {
         self.engine
             .lock()
             .unwrap()
-            .set_track_offset(track_index, offset)
-            .map_err(|e| PyRuntimeError::new_err(format!("Failed to set track offset: {}", e)))
+            .apply_bitcrush(bits, downsample_factor, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Bitcrush error: {}", e)))
     }
 
-    /// Get waveform data for a specific time range for all tracks
+    /// Adjust the stereo width of specified tracks
     ///
     /// # Parameters
-    /// * `start_time` - start of range in seconds
-    /// * `end_time` - end of range in seconds
-    /// * `num_pixels` - desired number of data points
+    /// * `width` - side level relative to the original (0.0 = mono, 1.0 = unchanged, > 1.0 = wider)
+    /// * `track_indices` - list of track indices to process
     ///
     /// # Returns
-    /// `Vec<Vec<(f32, f32, f32, f32)>>` - waveform data per track
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if width is negative
     ///
     /// # Notes
-    /// Returns separate waveform data for each track
-    fn get_waveform_for_range(&self, start_time: f64, end_time: f64, num_pixels: usize) -> PyResult<Vec<Vec<(f32, f32, f32, f32)>>>
+    /// Only stereo tracks are changed
+    fn apply_stereo_width(&mut self, width: f64, track_indices: Vec<usize>) -> PyResult<()>
     {
-        Ok(self.engine.lock().unwrap().get_waveform_for_range(start_time, end_time, num_pixels))
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_stereo_width(width, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Stereo width error: {}", e)))
     }
 
-    /// Get the sample rate of the first loaded track
+    /// Apply tanh saturation to specified tracks
+    ///
+    /// # Parameters
+    /// * `drive` - input gain into the waveshaper (> 0, higher is more distorted)
+    /// * `mix` - wet/dry balance 0.0-1.0
+    /// * `track_indices` - list of track indices to process
     ///
     /// # Returns
-    /// `u32` - sample rate in Hz
-    fn get_sample_rate(&self) -> PyResult<u32>
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if drive is not positive
+    fn apply_saturation(&mut self, drive: f64, mix: f64, track_indices: Vec<usize>) -> PyResult<()>
     {
-        Ok(self.engine.lock().unwrap().get_sample_rate())
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_saturation(drive, mix, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Saturation error: {}", e)))
     }
 
-    /// Get the duration of the longest track
+    /// Apply tremolo to specified tracks
+    ///
+    /// # Parameters
+    /// * `rate_hz` - modulation rate in Hz
+    /// * `depth` - modulation depth 0.0-1.0 (1.0 dips fully to silence)
+    /// * `track_indices` - list of track indices to process
     ///
     /// # Returns
-    /// `f64` - duration in seconds
-    fn get_duration(&self) -> PyResult<f64>
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if rate is not positive
+    fn apply_tremolo(&mut self, rate_hz: f64, depth: f64, track_indices: Vec<usize>) -> PyResult<()>
     {
-        Ok(self.engine.lock().unwrap().get_duration())
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_tremolo(rate_hz, depth, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Tremolo error: {}", e)))
     }
 
-    /// Get the number of audio channels (maximum across all tracks)
+    /// Apply vibrato to specified tracks
+    ///
+    /// # Parameters
+    /// * `rate_hz` - modulation rate in Hz
+    /// * `depth_ms` - peak delay deviation in milliseconds
+    /// * `track_indices` - list of track indices to process
     ///
     /// # Returns
-    /// `usize` - number of channels (1=mono, 2=stereo)
-    fn get_channels(&self) -> PyResult<usize>
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if rate is not positive or depth is negative
+    fn apply_vibrato(&mut self, rate_hz: f64, depth_ms: f64, track_indices: Vec<usize>) -> PyResult<()>
     {
-        Ok(self.engine.lock().unwrap().get_channels())
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_vibrato(rate_hz, depth_ms, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Vibrato error: {}", e)))
     }
 
-    /// Start audio playback
+    /// Apply chorus to specified tracks
     ///
     /// # Parameters
-    /// * `start_time` - optional start time in seconds (None to resume from current position)
-    /// * `end_time` - optional end time in seconds (None to play to end)
+    /// * `voices` - number of modulated delay lines 1-8
+    /// * `rate_hz` - modulation rate in Hz
+    /// * `depth_ms` - peak delay deviation of each voice in milliseconds (0-20)
+    /// * `mix` - wet/dry balance 0.0-1.0
+    /// * `track_indices` - list of track indices to process
     ///
     /// # Returns
     /// `PyResult<()>` - Ok if successful
     ///
     /// # Errors
-    /// Returns error if playback cannot be started
-    fn play(&mut self, start_time: Option<f64>, end_time: Option<f64>) -> PyResult<()>
+    /// Returns error if voices, rate, or depth is out of range
+    fn apply_chorus(&mut self, voices: usize, rate_hz: f64, depth_ms: f64, mix: f64, track_indices: Vec<usize>) -> PyResult<()>
     {
         self.engine
             .lock()
             .unwrap()
-            .play(start_time, end_time)
-            .map_err(|e| PyRuntimeError::new_err(format!("Playback error: {}", e)))
+            .apply_chorus(voices, rate_hz, depth_ms, mix, &track_indices)
+            .map_err(|e| SoundlyError::new_err(format!("Chorus error: {}", e)))
     }
 
-    /// Pause audio playback without resetting position
+    /// Remove clicks and pops from specified tracks
+    ///
+    /// # Parameters
+    /// * `track_indices` - list of track indices to process
+    /// * `sensitivity` - detection sensitivity 0.0-1.0, higher catches smaller clicks (default 0.5)
     ///
     /// # Returns
-    /// `PyResult<()>` - always Ok
-    fn pause(&mut self) -> PyResult<()>
+    /// `PyResult<usize>` - number of clicks repaired
+    ///
+    /// # Errors
+    /// Returns error if sensitivity is outside 0.0-1.0
+    #[pyo3(signature = (track_indices, sensitivity=0.5))]
+    fn remove_clicks(&mut self, track_indices: Vec<usize>, sensitivity: f64) -> PyResult<usize>
     {
-        self.engine.lock().unwrap().pause();
-        Ok(())
+        self.engine
+            .lock()
+            .unwrap()
+            .remove_clicks(&track_indices, sensitivity)
+            .map_err(|e| SoundlyError::new_err(format!("Click removal error: {}", e)))
     }
 
-    /// Stop audio playback and reset position
+    /// Learn the background noise spectrum from a region of a track
+    ///
+    /// # Parameters
+    /// * `track_index` - index of the track to learn from
+    /// * `start_time` - start of a noise-only region in seconds
+    /// * `end_time` - end of the region in seconds
     ///
     /// # Returns
-    /// `PyResult<()>` - always Ok
-    fn stop(&mut self) -> PyResult<()>
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if track index is invalid or the region is shorter than 2048 samples
+    fn learn_noise_profile(&mut self, track_index: usize, start_time: f64, end_time: f64) -> PyResult<()>
     {
-        self.engine.lock().unwrap().stop();
-        Ok(())
+        self.engine
+            .lock()
+            .unwrap()
+            .learn_noise_profile(track_index, start_time, end_time)
+            .map_err(|e| SoundlyError::new_err(format!("Noise profile error: {}", e)))
     }
 
-    /// Check if audio is currently playing
+    /// Reduce steady background noise using the profile from `learn_noise_profile`
+    ///
+    /// # Parameters
+    /// * `track_indices` - list of track indices to process
+    /// * `amount` - fraction of the noise estimate to subtract, 0.0-1.0 (default 1.0)
     ///
     /// # Returns
-    /// `bool` - true if playing, false otherwise
-    fn is_playing(&self) -> PyResult<bool>
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if no profile was learned, amount is out of range, or a track's sample rate differs
+    #[pyo3(signature = (track_indices, amount=1.0))]
+    fn apply_noise_reduction(&mut self, track_indices: Vec<usize>, amount: f64) -> PyResult<()>
     {
-        Ok(self.engine.lock().unwrap().is_playing())
+        self.engine
+            .lock()
+            .unwrap()
+            .apply_noise_reduction(&track_indices, amount)
+            .map_err(|e| SoundlyError::new_err(format!("Noise reduction error: {}", e)))
     }
 
-    /// Get current playback position
+    /// Export a region of the mix as a seamless loop
+    ///
+    /// # Parameters
+    /// * `path` - output file path with extension (.wav, .flac, .mp3, or .ogg)
+    /// * `start_time` - start of the loop in seconds
+    /// * `end_time` - end of the loop in seconds
+    /// * `crossfade_ms` - crossfade from the end back into the start in milliseconds (default 10.0)
+    /// * `compression_level` - optional FLAC compression level 0-8 (None for default 5)
+    /// * `bitrate_kbps` - optional MP3 bitrate in kbps (None for default 192)
+    /// * `quality` - optional Ogg Vorbis VBR quality -0.2 to 1.0 (None for default 0.5)
+    /// * `bit_depth` - optional bit depth: 16, 24 (integer), or 32 (float) for WAV, 16 or 24 for FLAC (None for default 16)
+    /// * `tags` - optional dict of 'title', 'artist', 'album', and 'year' for MP3 and FLAC files
+    /// * `progress` - optional callable receiving the fraction complete, 0.0 to 1.0
+    /// * `flac_block_size` - optional FLAC samples per channel in each frame, 16-65535 (None for the compression level's default)
+    /// * `flac_padding` - optional FLAC padding block length in bytes, 0 to omit it (None for 4096)
+    /// * `flac_dither` - add TPDF dither when quantizing FLAC samples to `bit_depth` (default False)
     ///
     /// # Returns
-    /// `f64` - position in seconds
-    fn get_playback_position(&self) -> PyResult<f64>
+    /// `PyResult<()>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if the region or crossfade is invalid or export fails
+    ///
+    /// # Notes
+    /// The file is `crossfade_ms` shorter than the region. Use WAV or FLAC for
+    /// sample-accurate loops, since MP3 and Ogg Vorbis add encoder padding.
+    #[pyo3(signature = (path, start_time, end_time, crossfade_ms=10.0, compression_level=None, bitrate_kbps=None, quality=None, bit_depth=None, tags=None, progress=None, flac_block_size=None, flac_padding=None, flac_dither=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn export_loop(&self, py: Python, path: String, start_time: f64, end_time: f64, crossfade_ms: f64,
+                   compression_level: Option<u8>, bitrate_kbps: Option<u32>, quality: Option<f32>,
+                   bit_depth: Option<u16>, tags: Option<HashMap<String, String>>,
+                   progress: Option<PyObject>, flac_block_size: Option<usize>, flac_padding: Option<usize>,
+                   flac_dither: bool) -> PyResult<()>
     {
-        Ok(self.engine.lock().unwrap().get_playback_position())
+        let options = export_options(compression_level, bitrate_kbps, quality, bit_depth, tags, flac_block_size, flac_padding, flac_dither)?;
+        let export_items = self.engine
+            .lock()
+            .unwrap()
+            .render_loop(start_time, end_time, crossfade_ms)
+            .map_err(|e| ExportError::new_err(format!("Export error: {}", e)))?;
+
+        py.allow_threads(move ||
+        {
+            let mut report = |fraction: f64| report_progress(progress.as_ref(), fraction);
+            AudioEngine::write_export(&path, export_items, &options, Some(&mut report))
+        })
+            .map_err(|e| ExportError::new_err(format!("Export error: {}", e)))
     }
 
-    /// Set playback position
+    /// Export several regions of the mix in one call
     ///
     /// # Parameters
-    /// * `position` - new position in seconds
+    /// * `regions` - list of (start_time, end_time, path); each path's extension selects its format
+    /// * `compression_level` - optional FLAC compression level 0-8 (None for default 5)
+    /// * `bitrate_kbps` - optional MP3 bitrate in kbps (None for default 192)
+    /// * `channel_mode` - optional channel mode ('stereo', 'mono', 'split', 'mono_to_stereo')
+    /// * `quality` - optional Ogg Vorbis VBR quality -0.2 to 1.0 (None for default 0.5)
+    /// * `bit_depth` - optional bit depth: 16, 24 (integer), or 32 (float) for WAV, 16 or 24 for FLAC (None for default 16)
+    /// * `tags` - optional dict of 'title', 'artist', 'album', and 'year' for MP3 and FLAC files
+    /// * `progress` - optional callable receiving the fraction complete, 0.0 to 1.0
+    /// * `mono_weights` - optional (left, right) weights folding stereo tracks in mono mode (None for (0.5, 0.5))
+    /// * `mono_gain_db` - optional gain of the mono mode mix in dB (None for 0.0)
+    /// * `limiter_ceiling_db` - optional ceiling of a limiter on the summed mix in dBFS (None to clamp at full scale)
+    /// * `limiter_release_ms` - release time of the mix limiter in milliseconds (default 50)
+    /// * `flac_block_size` - optional FLAC samples per channel in each frame, 16-65535 (None for the compression level's default)
+    /// * `flac_padding` - optional FLAC padding block length in bytes, 0 to omit it (None for 4096)
+    /// * `flac_dither` - add TPDF dither when quantizing FLAC samples to `bit_depth` (default False)
     ///
     /// # Returns
-    /// `PyResult<()>` - always Ok
-    fn set_playback_position(&mut self, position: f64) -> PyResult<()>
+    /// `PyResult<()>` - Ok if every region was written
+    ///
+    /// # Errors
+    /// Returns error if any region is invalid (nothing is written) or a file fails to export
+    #[pyo3(signature = (regions, compression_level=None, bitrate_kbps=None, channel_mode=None, quality=None, bit_depth=None, tags=None, progress=None, mono_weights=None, mono_gain_db=None, limiter_ceiling_db=None, limiter_release_ms=50.0, flac_block_size=None, flac_padding=None, flac_dither=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn export_regions(&self, py: Python, regions: Vec<(f64, f64, String)>, compression_level: Option<u8>,
+                      bitrate_kbps: Option<u32>, channel_mode: Option<String>, quality: Option<f32>,
+                      bit_depth: Option<u16>, tags: Option<HashMap<String, String>>,
+                      progress: Option<PyObject>, mono_weights: Option<(f32, f32)>,
+                      mono_gain_db: Option<f64>, limiter_ceiling_db: Option<f64>,
+                      limiter_release_ms: f64, flac_block_size: Option<usize>, flac_padding: Option<usize>,
+                      flac_dither: bool) -> PyResult<()>
     {
-        self.engine.lock().unwrap().set_playback_position(position);
-        Ok(())
+        let options = export_options(compression_level, bitrate_kbps, quality, bit_depth, tags, flac_block_size, flac_padding, flac_dither)?;
+        let limiter = bus_limiter(limiter_ceiling_db, limiter_release_ms)?;
+        let rendered = self.engine
+            .lock()
+            .unwrap()
+            .render_regions(&regions, channel_mode.as_deref(), mono_fold(mono_weights, mono_gain_db), limiter)
+            .map_err(|e| ExportError::new_err(format!("Export error: {}", e)))?;
+
+        py.allow_threads(move ||
+        {
+            let mut report = |fraction: f64| report_progress(progress.as_ref(), fraction);
+            AudioEngine::write_regions(rendered, &options, Some(&mut report))
+        })
+            .map_err(|e| ExportError::new_err(format!("Export error: {}", e)))
     }
 
-    /// Delete a region of audio from specified tracks
+    /// Export every track to its own file
     ///
     /// # Parameters
-    /// * `start_time` - start of region in seconds
-    /// * `end_time` - end of region in seconds
-    /// * `track_indices` - list of track indices to delete from
+    /// * `dir` - output directory (created if missing)
+    /// * `format` - output format extension ('wav', 'flac', 'mp3', or 'ogg')
+    /// * `apply_envelope` - whether each track's gain envelope is applied
+    /// * `compression_level` - optional FLAC compression level 0-8 (None for default 5)
+    /// * `bitrate_kbps` - optional MP3 bitrate in kbps (None for default 192)
+    /// * `quality` - optional Ogg Vorbis VBR quality -0.2 to 1.0 (None for default 0.5)
+    /// * `bit_depth` - optional bit depth: 16, 24 (integer), or 32 (float) for WAV, 16 or 24 for FLAC (None for default 16)
+    /// * `tags` - optional dict of 'title', 'artist', 'album', and 'year' for MP3 and FLAC files
+    /// * `progress` - optional callable receiving the fraction complete, 0.0 to 1.0
+    /// * `flac_block_size` - optional FLAC samples per channel in each frame, 16-65535 (None for the compression level's default)
+    /// * `flac_padding` - optional FLAC padding block length in bytes, 0 to omit it (None for 4096)
+    /// * `flac_dither` - add TPDF dither when quantizing FLAC samples to `bit_depth` (default False)
     ///
     /// # Returns
-    /// `PyResult<()>` - Ok if successful
+    /// `PyResult<Vec<String>>` - paths written, in track order
+    ///
+    /// # Notes
+    /// Files are named after their tracks and span the whole project, so they line up
+    /// when imported together. The GIL is released while encoding.
+    #[pyo3(signature = (dir, format="wav", apply_envelope=true, compression_level=None, bitrate_kbps=None, quality=None, bit_depth=None, tags=None, progress=None, flac_block_size=None, flac_padding=None, flac_dither=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn export_stems(&self, py: Python, dir: String, format: &str, apply_envelope: bool, compression_level: Option<u8>,
+                    bitrate_kbps: Option<u32>, quality: Option<f32>, bit_depth: Option<u16>,
+                    tags: Option<HashMap<String, String>>, progress: Option<PyObject>,
+                    flac_block_size: Option<usize>, flac_padding: Option<usize>, flac_dither: bool) -> PyResult<Vec<String>>
+    {
+        let options = export_options(compression_level, bitrate_kbps, quality, bit_depth, tags, flac_block_size, flac_padding, flac_dither)?;
+        let stems = self.engine
+            .lock()
+            .unwrap()
+            .render_stems(apply_envelope);
+        let format = format.to_string();
+
+        py.allow_threads(move ||
+        {
+            let mut report = |fraction: f64| report_progress(progress.as_ref(), fraction);
+            AudioEngine::write_stems(&dir, &format, stems, &options, Some(&mut report))
+        })
+            .map_err(|e| ExportError::new_err(format!("Export error: {}", e)))
+    }
+
+    /// Export the full mix as a headerless PCM file
+    ///
+    /// # Parameters
+    /// * `path` - output file path
+    /// * `sample_format` - sample encoding ('s16', 's24', 's32', or 'f32')
+    /// * `endianness` - byte order ('le' or 'be')
+    ///
+    /// # Returns
+    /// `PyResult<(u32, usize)>` - (sample_rate, channels) of the written audio
     ///
     /// # Errors
-    /// Returns error if region is invalid
-    fn delete_region(&mut self, start_time: f64, end_time: f64, track_indices: Vec<usize>) -> PyResult<()>
+    /// Returns error if there are no tracks, the format is unknown, or writing fails
+    #[pyo3(signature = (path, sample_format="s16", endianness="le"))]
+    fn export_raw(&self, path: String, sample_format: &str, endianness: &str) -> PyResult<(u32, usize)>
     {
         self.engine
             .lock()
             .unwrap()
-            .delete_region(start_time, end_time, &track_indices)
-            .map_err(|e| PyRuntimeError::new_err(format!("Delete error: {}", e)))
+            .export_raw(&path, sample_format, endianness)
+            .map_err(|e| ExportError::new_err(format!("Export error: {}", e)))
     }
 
     /// Export mixed audio to a file
     ///
     /// # Parameters
-    /// * `path` - output file path with extension (.wav, .flac, or .mp3)
+    /// * `path` - output file path with extension (.wav, .flac, .mp3, or .ogg)
     /// * `start_time` - optional start time in seconds (None for beginning)
     /// * `end_time` - optional end time in seconds (None for end)
     /// * `compression_level` - optional FLAC compression level 0-8 (None for default 5)
     /// * `bitrate_kbps` - optional MP3 bitrate in kbps (None for default 192)
     /// * `channel_mode` - optional channel mode ('stereo', 'mono', 'split', 'mono_to_stereo')
+    /// * `progress` - optional callable receiving the fraction complete, 0.0 to 1.0
+    /// * `quality` - optional Ogg Vorbis VBR quality -0.2 to 1.0 (None for default 0.5)
+    /// * `bit_depth` - optional bit depth: 16, 24 (integer), or 32 (float) for WAV, 16 or 24 for FLAC (None for default 16)
+    /// * `tags` - optional dict of 'title', 'artist', 'album', and 'year' for MP3 and FLAC files
+    /// * `mono_weights` - optional (left, right) weights folding stereo tracks in mono mode (None for (0.5, 0.5))
+    /// * `mono_gain_db` - optional gain of the mono mode mix in dB (None for 0.0)
+    /// * `limiter_ceiling_db` - optional ceiling of a limiter on the summed mix in dBFS (None to clamp at full scale)
+    /// * `limiter_release_ms` - release time of the mix limiter in milliseconds (default 50)
+    /// * `flac_block_size` - optional FLAC samples per channel in each frame, 16-65535 (None for the compression level's default)
+    /// * `flac_padding` - optional FLAC padding block length in bytes, 0 to omit it (None for 4096)
+    /// * `flac_dither` - add TPDF dither when quantizing FLAC samples to `bit_depth` (default False)
+    /// * `cue_points` - optional FLAC cue sheet track start times in seconds from the start of the export, in order
     ///
     /// # Returns
     /// `PyResult<()>` - Ok if successful
     ///
     /// # Errors
-    /// Returns error if export fails or format is unsupported
-    #[pyo3(signature = (path, start_time=None, end_time=None, compression_level=None, bitrate_kbps=None, channel_mode=None))]
-    fn export_audio(&self, path: String, start_time: Option<f64>, end_time: Option<f64>,
+    /// Returns error if export fails, format is unsupported, or a cue point is negative,
+    /// out of order, or past the end of the export
+    ///
+    /// # Notes
+    /// The GIL is released while encoding and only reacquired to call `progress`.
+    /// Exceptions raised by `progress` are printed and do not stop the export.
+    /// Cue points become tracks numbered from 1, each with a single index point at its start.
+    #[pyo3(signature = (path, start_time=None, end_time=None, compression_level=None, bitrate_kbps=None, channel_mode=None, progress=None, quality=None, bit_depth=None, tags=None, mono_weights=None, mono_gain_db=None, limiter_ceiling_db=None, limiter_release_ms=50.0, flac_block_size=None, flac_padding=None, cue_points=None, flac_dither=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn export_audio(&self, py: Python, path: String, start_time: Option<f64>, end_time: Option<f64>,
                     compression_level: Option<u8>, bitrate_kbps: Option<u32>,
-                    channel_mode: Option<String>) -> PyResult<()>
+                    channel_mode: Option<String>, progress: Option<PyObject>, quality: Option<f32>,
+                    bit_depth: Option<u16>, tags: Option<HashMap<String, String>>,
+                    mono_weights: Option<(f32, f32)>, mono_gain_db: Option<f64>,
+                    limiter_ceiling_db: Option<f64>, limiter_release_ms: f64,
+                    flac_block_size: Option<usize>, flac_padding: Option<usize>, cue_points: Option<Vec<f64>>,
+                    flac_dither: bool) -> PyResult<()>
     {
-        self.engine
+        let mut options = export_options(compression_level, bitrate_kbps, quality, bit_depth, tags, flac_block_size, flac_padding, flac_dither)?;
+        let limiter = bus_limiter(limiter_ceiling_db, limiter_release_ms)?;
+
+        let export_items = self.engine
             .lock()
             .unwrap()
-            .export_audio(&path, start_time, end_time, compression_level, bitrate_kbps, channel_mode)
-            .map_err(|e| PyRuntimeError::new_err(format!("Export error: {}", e)))
+            .render_export(start_time, end_time, channel_mode.as_deref(), mono_fold(mono_weights, mono_gain_db), limiter);
+        if let (Some(points), Some(item)) = (cue_points, export_items.first())
+        {
+            options.cue_sheet = Some(cue_sheet(&points, item.1)?);
+        }
+
+        py.allow_threads(move ||
+        {
+            let mut report = |fraction: f64| report_progress(progress.as_ref(), fraction);
+            AudioEngine::write_export(&path, export_items, &options, Some(&mut report))
+        })
+            .map_err(|e| ExportError::new_err(format!("Export error: {}", e)))
+    }
+}
+
+impl AudioEditor
+{
+    /// Point the position callback at the engine's current output stream
+    ///
+    /// # Notes
+    /// Called after anything that may rebuild the stream
+    fn refresh_position_probe(&self)
+    {
+        if let Some(ref callback) = self.position_callback
+        {
+            callback.set_probe(self.engine.lock().unwrap().position_probe());
+        }
+    }
+}
+
+/// Build encoder settings from export keyword arguments
+///
+/// # Parameters
+/// * `compression_level` - optional FLAC compression level
+/// * `bitrate_kbps` - optional MP3 bitrate
+/// * `quality` - optional Ogg Vorbis VBR quality
+/// * `bit_depth` - optional WAV bit depth
+/// * `tags` - optional dict of tag values
+/// * `flac_block_size` - optional FLAC block size
+/// * `flac_padding` - optional FLAC padding block length
+/// * `flac_dither` - whether to dither FLAC samples
+///
+/// # Returns
+/// `PyResult<ExportOptions>` - encoder settings, or ExportError on an invalid tag
+#[allow(clippy::too_many_arguments)]
+fn export_options(compression_level: Option<u8>, bitrate_kbps: Option<u32>, quality: Option<f32>,
+                  bit_depth: Option<u16>, tags: Option<HashMap<String, String>>,
+                  flac_block_size: Option<usize>, flac_padding: Option<usize>,
+                  flac_dither: bool) -> PyResult<ExportOptions>
+{
+    let tags = match tags
+    {
+        Some(ref map) => ExportTags::from_map(map).map_err(|e| ExportError::new_err(format!("Export error: {}", e)))?,
+        None => ExportTags::default(),
+    };
+    Ok(ExportOptions
+    {
+        compression_level,
+        bitrate_kbps,
+        quality,
+        bit_depth,
+        flac_block_size,
+        cue_sheet: None,
+        flac_padding,
+        flac_dither,
+        tags,
+    })
+}
+
+/// Build mono fold-down weights from export keyword arguments
+///
+/// # Parameters
+/// * `mono_weights` - optional (left, right) linear weights for stereo tracks
+/// * `mono_gain_db` - optional gain applied to the mono mix in dB
+///
+/// # Returns
+/// `MonoFold` - the given weights and gain, defaulting to an equal average at unity gain
+fn mono_fold(mono_weights: Option<(f32, f32)>, mono_gain_db: Option<f64>) -> MonoFold
+{
+    let mut fold = MonoFold::default();
+    if let Some((left, right)) = mono_weights
+    {
+        fold.left = left;
+        fold.right = right;
+    }
+    if let Some(gain_db) = mono_gain_db
+    {
+        fold.gain = effects::db_to_gain(gain_db) as f32;
+    }
+    fold
+}
+
+/// Build export mix bus limiter settings from export keyword arguments
+///
+/// # Parameters
+/// * `ceiling_db` - optional limiter ceiling in dBFS (None to clamp instead)
+/// * `release_ms` - limiter release time in milliseconds
+///
+/// # Returns
+/// `PyResult<Option<BusLimiter>>` - the limiter settings, None if no ceiling was given
+///
+/// # Errors
+/// Returns error if the ceiling is above 0 dBFS or the release time is negative
+fn bus_limiter(ceiling_db: Option<f64>, release_ms: f64) -> PyResult<Option<BusLimiter>>
+{
+    ceiling_db
+        .map(|ceiling_db| BusLimiter::new(ceiling_db, release_ms))
+        .transpose()
+        .map_err(|e| ExportError::new_err(format!("Export error: {}", e)))
+}
+
+/// Build a FLAC cue sheet from track start times
+///
+/// # Parameters
+/// * `points` - track start times in seconds
+/// * `sample_rate` - sample rate of the exported audio in Hz
+///
+/// # Returns
+/// `PyResult<CueSheet>` - a cue sheet with one track per start time
+///
+/// # Errors
+/// Returns error if a start time is negative or not finite
+fn cue_sheet(points: &[f64], sample_rate: u32) -> PyResult<CueSheet>
+{
+    let mut tracks = Vec::with_capacity(points.len());
+    for (i, &time) in points.iter().enumerate()
+    {
+        if time < 0.0 || !time.is_finite()
+        {
+            return Err(ExportError::new_err(format!("Export error: Invalid cue point: {}", time)));
+        }
+        tracks.push(CueTrack
+        {
+            offset: (time * sample_rate as f64).round() as u64,
+            number: (i + 1).min(u8::MAX as usize) as u8,
+            isrc: String::new(),
+            indices: vec![CueIndex { offset: 0, number: 1 }],
+        });
+    }
+    Ok(CueSheet
+    {
+        tracks,
+        ..CueSheet::default()
+    })
+}
+
+/// Call an optional Python progress callback from a thread without the GIL
+///
+/// # Parameters
+/// * `callback` - optional callable receiving the fraction complete
+/// * `fraction` - fraction complete, 0.0 to 1.0
+///
+/// # Notes
+/// Exceptions raised by the callback are printed and otherwise ignored
+fn report_progress(callback: Option<&PyObject>, fraction: f64)
+{
+    if let Some(callback) = callback
+    {
+        Python::with_gil(|py|
+        {
+            if let Err(e) = callback.call1(py, (fraction,))
+            {
+                e.print(py);
+            }
+        });
     }
 }
 
 /// Python module definition
 #[pymodule]
-fn soundly(_py: Python, m: &PyModule) -> PyResult<()>
+fn soundly(py: Python, m: &PyModule) -> PyResult<()>
 {
     m.add_class::<AudioEditor>()?;
+    errors::register(py, m)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn export_formats_include_wav_flac_and_mp3()
+    {
+        let formats = AudioEditor::supported_export_formats();
+        for format in ["wav", "flac", "mp3"]
+        {
+            assert!(formats.iter().any(|f| f == format));
+        }
+        assert!(AudioEditor::supported_import_formats().iter().any(|f| f == "flac"));
+    }
+
+    #[test]
+    fn editor_moves_to_another_thread()
+    {
+        let editor = AudioEditor::new().unwrap();
+        editor.engine.lock().unwrap().add_tone_track(440.0, 0.1, 0.5, 44100, 2).unwrap();
+
+        // only compiles while AudioEditor, and the playback it may own, are Send
+        let count = std::thread::spawn(move || editor.get_track_count().unwrap()).join().unwrap();
+
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,207 @@
+//! Headerless PCM encoding and decoding
+
+/// Sample encodings supported for raw PCM
+#[derive(Clone, Copy)]
+enum SampleFormat
+{
+    S16,
+    S24,
+    S32,
+    F32,
+}
+
+/// A sample encoding together with its byte order
+#[derive(Clone, Copy)]
+pub struct RawFormat
+{
+    sample_format: SampleFormat,
+    big_endian: bool,
+}
+
+impl RawFormat
+{
+    /// Parse a sample format and byte order
+    ///
+    /// # Parameters
+    /// * `sample_format` - 's16', 's24', 's32', or 'f32'
+    /// * `endianness` - 'le' or 'be'
+    ///
+    /// # Returns
+    /// `Result<RawFormat, String>` - Ok with the parsed format, Err if either name is unknown
+    pub fn parse(sample_format: &str, endianness: &str) -> Result<Self, String>
+    {
+        let sample_format = match sample_format.to_lowercase().as_str()
+        {
+            "s16" => SampleFormat::S16,
+            "s24" => SampleFormat::S24,
+            "s32" => SampleFormat::S32,
+            "f32" => SampleFormat::F32,
+            other => return Err(format!("Unknown sample format: {} (expected 's16', 's24', 's32', or 'f32')", other)),
+        };
+        let big_endian = match endianness.to_lowercase().as_str()
+        {
+            "le" => false,
+            "be" => true,
+            other => return Err(format!("Unknown endianness: {} (expected 'le' or 'be')", other)),
+        };
+        Ok(RawFormat { sample_format, big_endian })
+    }
+
+    /// Number of bytes used by one sample
+    ///
+    /// # Returns
+    /// `usize` - bytes per sample
+    pub fn bytes_per_sample(&self) -> usize
+    {
+        match self.sample_format
+        {
+            SampleFormat::S16 => 2,
+            SampleFormat::S24 => 3,
+            SampleFormat::S32 | SampleFormat::F32 => 4,
+        }
+    }
+}
+
+/// Encode samples as raw PCM
+///
+/// # Parameters
+/// * `data` - interleaved samples
+/// * `format` - target sample encoding and byte order
+///
+/// # Returns
+/// `Vec<u8>` - encoded bytes
+///
+/// # Notes
+/// Integer formats clamp to -1.0..1.0 before scaling
+pub fn encode(data: &[f32], format: RawFormat) -> Vec<u8>
+{
+    let mut bytes = Vec::with_capacity(data.len() * format.bytes_per_sample());
+
+    for &sample in data
+    {
+        match format.sample_format
+        {
+            SampleFormat::S16 =>
+            {
+                let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                if format.big_endian
+                {
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                else
+                {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            SampleFormat::S24 =>
+            {
+                let value = (sample.clamp(-1.0, 1.0) as f64 * 8_388_607.0) as i32;
+                if format.big_endian
+                {
+                    bytes.extend_from_slice(&value.to_be_bytes()[1..]);
+                }
+                else
+                {
+                    bytes.extend_from_slice(&value.to_le_bytes()[..3]);
+                }
+            }
+            SampleFormat::S32 =>
+            {
+                let value = (sample.clamp(-1.0, 1.0) as f64 * i32::MAX as f64) as i32;
+                if format.big_endian
+                {
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                else
+                {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            SampleFormat::F32 =>
+            {
+                if format.big_endian
+                {
+                    bytes.extend_from_slice(&sample.to_be_bytes());
+                }
+                else
+                {
+                    bytes.extend_from_slice(&sample.to_le_bytes());
+                }
+            }
+        }
+    }
+
+    bytes
+}
+
+/// Decode raw PCM into samples
+///
+/// # Parameters
+/// * `bytes` - encoded bytes
+/// * `format` - sample encoding and byte order of `bytes`
+///
+/// # Returns
+/// `Vec<f32>` - decoded samples
+///
+/// # Notes
+/// A trailing partial sample is ignored
+pub fn decode(bytes: &[u8], format: RawFormat) -> Vec<f32>
+{
+    bytes
+        .chunks_exact(format.bytes_per_sample())
+        .map(|b|
+        {
+            match format.sample_format
+            {
+                SampleFormat::S16 =>
+                {
+                    let value = if format.big_endian
+                    {
+                        i16::from_be_bytes([b[0], b[1]])
+                    }
+                    else
+                    {
+                        i16::from_le_bytes([b[0], b[1]])
+                    };
+                    value as f32 / i16::MAX as f32
+                }
+                SampleFormat::S24 =>
+                {
+                    // place the three bytes in the top of an i32 so the sign extends
+                    let value = if format.big_endian
+                    {
+                        i32::from_be_bytes([b[0], b[1], b[2], 0])
+                    }
+                    else
+                    {
+                        i32::from_le_bytes([0, b[0], b[1], b[2]])
+                    };
+                    ((value >> 8) as f64 / 8_388_607.0) as f32
+                }
+                SampleFormat::S32 =>
+                {
+                    let value = if format.big_endian
+                    {
+                        i32::from_be_bytes([b[0], b[1], b[2], b[3]])
+                    }
+                    else
+                    {
+                        i32::from_le_bytes([b[0], b[1], b[2], b[3]])
+                    };
+                    (value as f64 / i32::MAX as f64) as f32
+                }
+                SampleFormat::F32 =>
+                {
+                    if format.big_endian
+                    {
+                        f32::from_be_bytes([b[0], b[1], b[2], b[3]])
+                    }
+                    else
+                    {
+                        f32::from_le_bytes([b[0], b[1], b[2], b[3]])
+                    }
+                }
+            }
+        })
+        .collect()
+}
@@ -1,29 +1,254 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Stream, StreamConfig};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample, Stream, StreamConfig};
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Sender};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use crate::resample::{self, ResampleQuality};
+
+/// Default length of the gain ramp applied when playback starts, pauses, or stops
+pub const DEFAULT_FADE_MS: f64 = 5.0;
+
+/// Default length of the crossfade from the old to the new position when seeking during playback
+pub const DEFAULT_SEEK_CROSSFADE_MS: f64 = 10.0;
+
+/// Rate at which the output level meters fall back after a peak, in dB per second
+const METER_FALLOFF_DB_PER_SEC: f64 = 20.0;
+
+/// A buffer waiting in the playback queue
+struct QueuedBuffer
+{
+    buffer: Vec<f32>,
+    start_time_offset: f64,
+    crossfade_frames: usize,  // frames of overlap with the end of the buffer before it
+}
+
+/// Slack allowed between consecutive callbacks before a gap counts as an underrun, as a fraction of a callback
+const UNDERRUN_TOLERANCE: f64 = 0.5;
 
 /// Internal playback state shared between main thread and audio callback
 struct PlaybackState
 {
     buffer: Vec<f32>,
-    position: usize,
+    position: f64,  // fractional read position in frames
     is_playing: bool,
     is_paused: bool,
+    finished: bool,  // playback stopped by reaching the end of the buffer rather than by pause or stop
     start_time_offset: f64,
+    loop_range: Option<(usize, usize)>,  // (start, end) frame indices into buffer to repeat
+    speed: f64,  // frames of buffer consumed per output frame
+    gain: f32,  // current fade gain, 0.0 to 1.0
+    fade_target: f32,  // gain the fade is moving towards
+    fade_step: f32,  // gain change per output frame
+    stop_after_fade: bool,  // reset position once the fade out completes
+    meter: Vec<f32>,  // decaying peak level of each output channel
+    meter_decay: f32,  // meter multiplier applied per output frame
+    queue: VecDeque<QueuedBuffer>,  // buffers to play once the current one finishes
+    queue_index: Option<usize>,  // index of the current buffer within the queue, None outside a queue
+    reverse: bool,  // read head moves towards the buffer start
+    last_callback: Option<cpal::StreamInstant>,  // playback instant of the previous callback
+    last_callback_frames: usize,  // frames the previous callback supplied
+    underrun_count: usize,  // callbacks that arrived after the device had run out of samples
+    last_underrun: Option<(usize, usize)>,  // frames supplied before the latest underrun and frames that elapsed
+    seek_from: Option<f64>,  // read position before the last seek, faded out over the seek crossfade
+    seek_fade_remaining: usize,  // frames left in the seek crossfade
+    seek_fade_frames: usize,  // length of the seek crossfade in frames
+    balance: f32,  // master stereo balance, -1.0 full left to 1.0 full right
+}
+
+impl PlaybackState
+{
+    /// Create an idle playback state
+    ///
+    /// # Parameters
+    /// * `device_rate` - sample rate the device runs at
+    /// * `channels` - number of audio channels
+    fn new(device_rate: u32, channels: usize) -> Self
+    {
+        PlaybackState
+        {
+            buffer: Vec::new(),
+            position: 0.0,
+            is_playing: false,
+            is_paused: false,
+            finished: false,
+            start_time_offset: 0.0,
+            loop_range: None,
+            speed: 1.0,
+            gain: 0.0,
+            fade_target: 1.0,
+            fade_step: AudioPlayback::fade_step(DEFAULT_FADE_MS, device_rate),
+            stop_after_fade: false,
+            meter: vec![0.0; channels],
+            meter_decay: 10f64.powf(-METER_FALLOFF_DB_PER_SEC / 20.0 / device_rate as f64) as f32,
+            queue: VecDeque::new(),
+            queue_index: None,
+            reverse: false,
+            last_callback: None,
+            last_callback_frames: 0,
+            underrun_count: 0,
+            last_underrun: None,
+            seek_from: None,
+            seek_fade_remaining: 0,
+            seek_fade_frames: AudioPlayback::fade_frames(DEFAULT_SEEK_CROSSFADE_MS, device_rate),
+            balance: 0.0,
+        }
+    }
+
+    /// Finish a pause or stop once the fade out reaches silence
+    fn complete_fade_out(&mut self)
+    {
+        self.is_playing = false;
+        if self.stop_after_fade
+        {
+            self.stop_after_fade = false;
+            self.finished = false;
+            self.position = 0.0;
+            self.start_time_offset = 0.0;
+            self.loop_range = None;
+            self.queue.clear();
+            self.queue_index = None;
+            self.reverse = false;
+        }
+    }
+
+    /// Move on to the next queued buffer once the read head passes the end of the current one
+    ///
+    /// # Parameters
+    /// * `channels` - number of audio channels
+    ///
+    /// # Notes
+    /// The read head carries over into the next buffer, past any crossfade already heard
+    fn advance_queue(&mut self, channels: usize)
+    {
+        if self.loop_range.is_some()
+        {
+            return;
+        }
+
+        // loop so that empty buffers are skipped over
+        while self.position >= (self.buffer.len() / channels) as f64 && !self.queue.is_empty()
+        {
+            let next = self.queue.pop_front().unwrap();
+            let overshoot = self.position - (self.buffer.len() / channels) as f64;
+            self.buffer = next.buffer;
+            self.position = next.crossfade_frames as f64 + overshoot;
+            self.start_time_offset = next.start_time_offset;
+            self.queue_index = self.queue_index.map(|index| index + 1);
+            self.seek_from = None;
+        }
+    }
+
+    /// Gain the balance applies to an output channel
+    ///
+    /// # Parameters
+    /// * `ch` - output channel index
+    /// * `channels` - number of output channels
+    ///
+    /// # Returns
+    /// `f32` - 1.0 for the favoured side, falling linearly to 0.0 for the other at full balance
+    ///
+    /// # Notes
+    /// Only stereo output is balanced; other layouts always get 1.0
+    fn balance_gain(&self, ch: usize, channels: usize) -> f32
+    {
+        match (channels, ch)
+        {
+            (2, 0) => (1.0 - self.balance).min(1.0),
+            (2, 1) => (1.0 + self.balance).min(1.0),
+            _ => 1.0,
+        }
+    }
+
+    /// Read an interpolated sample from the buffer
+    ///
+    /// # Parameters
+    /// * `position` - fractional read position in frames
+    /// * `ch` - channel to read
+    /// * `channels` - number of audio channels
+    ///
+    /// # Returns
+    /// `f32` - linearly interpolated sample, 0.0 outside the buffer
+    fn sample_at(&self, position: f64, ch: usize, channels: usize) -> f32
+    {
+        let total_frames = self.buffer.len() / channels;
+        if position < 0.0 || position as usize >= total_frames
+        {
+            return 0.0;
+        }
+
+        let index = position as usize;
+        let next = (index + 1).min(total_frames - 1);
+        let frac = (position - index as f64) as f32;
+        let current = self.buffer[index * channels + ch];
+        let following = self.buffer[next * channels + ch];
+        current + (following - current) * frac
+    }
+
+    /// Get the timeline position of the read head
+    ///
+    /// # Parameters
+    /// * `sample_rate` - rate of the buffer in Hz
+    ///
+    /// # Returns
+    /// `f64` - position in seconds including start time offset
+    fn position_seconds(&self, sample_rate: u32) -> f64
+    {
+        self.position / sample_rate as f64 + self.start_time_offset
+    }
+}
+
+/// Thread-safe read-only view of a playback instance's position
+///
+/// # Notes
+/// Cheaper to poll than going through the engine, and never blocks on it
+#[derive(Clone)]
+pub struct PositionProbe
+{
+    state: Arc<Mutex<PlaybackState>>,
+    sample_rate: u32,
+}
+
+impl PositionProbe
+{
+    /// Get current playback position
+    ///
+    /// # Returns
+    /// `f64` - position in seconds including start time offset
+    pub fn position(&self) -> f64
+    {
+        self.state.lock().unwrap().position_seconds(self.sample_rate)
+    }
+
+    /// Check if currently playing
+    ///
+    /// # Returns
+    /// `bool` - true if the read head is advancing
+    pub fn is_playing(&self) -> bool
+    {
+        self.state.lock().unwrap().is_playing
+    }
 }
 
 /// Audio playback manager using cpal
+///
+/// # Notes
+/// A cpal stream cannot move between threads, so each instance runs its stream on
+/// a dedicated audio thread and talks to it only through the shared state. This
+/// keeps `AudioPlayback` itself `Send`. Dropping it shuts the thread down.
 pub struct AudioPlayback
 {
     state: Arc<Mutex<PlaybackState>>,
-    _stream: Stream,
-    sample_rate: u32,
+    _shutdown: Sender<()>,  // dropping this ends the audio thread and closes the stream
+    sample_rate: u32,  // rate the device runs at
+    source_rate: u32,  // rate of buffers passed to play
+    resample_quality: ResampleQuality,  // conversion used when the device runs at a different rate
     channels: usize,
 }
 
 impl AudioPlayback
 {
-    /// Create new audio playback instance
+    /// Create new audio playback instance on the default output device
     ///
     /// # Parameters
     /// * `sample_rate` - sample rate in Hz
@@ -35,88 +260,582 @@ impl AudioPlayback
     /// # Errors
     /// Returns error if no output device available or stream creation fails
     pub fn new(sample_rate: u32, channels: usize) -> Result<Self, String>
+    {
+        Self::spawn(None, sample_rate, channels)
+    }
+
+    /// List the names of available output devices
+    ///
+    /// # Returns
+    /// `Vec<String>` - device names, empty if devices cannot be enumerated
+    pub fn list_output_devices() -> Vec<String>
+    {
+        let host = cpal::default_host();
+        match host.output_devices()
+        {
+            Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Create new audio playback instance on a named output device
+    ///
+    /// # Parameters
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of audio channels
+    /// * `device_name` - name of the output device
+    ///
+    /// # Returns
+    /// `Result<Self, String>` - Ok if successful
+    ///
+    /// # Errors
+    /// Returns error if no output device available or stream creation fails
+    ///
+    /// # Notes
+    /// Falls back to the default device if no device matches the name
+    pub fn with_device(sample_rate: u32, channels: usize, device_name: &str) -> Result<Self, String>
+    {
+        Self::spawn(Some(device_name.to_string()), sample_rate, channels)
+    }
+
+    /// Start an audio thread that owns the output stream
+    ///
+    /// # Parameters
+    /// * `device_name` - optional output device name (None for the default device)
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of audio channels
+    ///
+    /// # Returns
+    /// `Result<Self, String>` - Ok once the stream is playing
+    ///
+    /// # Errors
+    /// Returns error if no output device available, stream creation fails, or the thread cannot start
+    fn spawn(device_name: Option<String>, sample_rate: u32, channels: usize) -> Result<Self, String>
+    {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>();
+
+        thread::Builder::new()
+            .name("soundly-audio".to_string())
+            .spawn(move ||
+            {
+                let built = Self::open_device(device_name.as_deref())
+                    .and_then(|device| Self::build(device, sample_rate, channels));
+
+                match built
+                {
+                    Ok((stream, state, device_rate)) =>
+                    {
+                        if ready_tx.send(Ok((state, device_rate))).is_ok()
+                        {
+                            // nothing is ever sent, so this returns once the handle is dropped
+                            let _ = shutdown_rx.recv();
+                        }
+                        drop(stream);
+                    }
+                    Err(e) =>
+                    {
+                        let _ = ready_tx.send(Err(e));
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start audio thread: {}", e))?;
+
+        let (state, device_rate) = ready_rx
+            .recv()
+            .map_err(|_| "Audio thread exited before the stream started".to_string())??;
+
+        Ok(AudioPlayback
+        {
+            state,
+            _shutdown: shutdown_tx,
+            sample_rate: device_rate,
+            source_rate: sample_rate,
+            resample_quality: ResampleQuality::default(),
+            channels: channels.max(1),
+        })
+    }
+
+    /// Find an output device by name
+    ///
+    /// # Parameters
+    /// * `device_name` - optional device name (None for the default device)
+    ///
+    /// # Returns
+    /// `Result<cpal::Device, String>` - Ok with the device
+    ///
+    /// # Notes
+    /// Falls back to the default device if no device matches the name
+    fn open_device(device_name: Option<&str>) -> Result<cpal::Device, String>
     {
         let host = cpal::default_host();
-        let device = host
-            .default_output_device()
-            .ok_or("No output device available")?;
+        let named_device = device_name.and_then(|name|
+        {
+            host.output_devices()
+                .ok()
+                .and_then(|mut devices| devices.find(|device| device.name().is_ok_and(|n| n == name)))
+        });
+
+        match named_device
+        {
+            Some(device) => Ok(device),
+            None => host.default_output_device().ok_or("No output device available".to_string()),
+        }
+    }
 
+    /// Build an output stream on a device
+    ///
+    /// # Parameters
+    /// * `device` - output device to play through
+    /// * `sample_rate` - sample rate in Hz
+    /// * `channels` - number of audio channels
+    ///
+    /// # Returns
+    /// `Result<(Stream, Arc<Mutex<PlaybackState>>, u32), String>` - Ok with the playing stream, its state, and the device rate
+    ///
+    /// # Errors
+    /// Returns error if stream creation fails
+    ///
+    /// # Notes
+    /// Must run on the thread that will own the stream
+    fn build(device: cpal::Device, sample_rate: u32, channels: usize) -> Result<(Stream, Arc<Mutex<PlaybackState>>, u32), String>
+    {
+        let sample_format = device
+            .default_output_config()
+            .map(|c| c.sample_format())
+            .unwrap_or(SampleFormat::F32);
+        let device_rate = Self::negotiate_sample_rate(&device, sample_rate, channels, sample_format);
         let config = StreamConfig
         {
-            channels: channels as u16,
-            sample_rate: cpal::SampleRate(sample_rate),
-            buffer_size: cpal::BufferSize::Default,
-        };
+            channels: channels as u16,
+            sample_rate: cpal::SampleRate(device_rate),
+            buffer_size: cpal::BufferSize::Default,
+        };
+
+        let state = Arc::new(Mutex::new(PlaybackState::new(device_rate, channels)));
+
+        let channels = channels.max(1);
+
+        // build output stream in the device's native sample format
+        let stream = match sample_format
+        {
+            SampleFormat::I16 => Self::build_stream::<i16>(&device, &config, state.clone(), channels),
+            SampleFormat::U16 => Self::build_stream::<u16>(&device, &config, state.clone(), channels),
+            _ => Self::build_stream::<f32>(&device, &config, state.clone(), channels),
+        }?;
+
+        stream.play().map_err(|e| format!("Failed to play stream: {}", e))?;
+
+        Ok((stream, state, device_rate))
+    }
+
+    /// Build an output stream that plays the shared buffer
+    ///
+    /// # Parameters
+    /// * `device` - output device to play through
+    /// * `config` - stream configuration
+    /// * `state` - playback state shared with the audio callback
+    /// * `channels` - number of audio channels
+    ///
+    /// # Returns
+    /// `Result<Stream, String>` - Ok with the stream if successful
+    ///
+    /// # Notes
+    /// The internal buffer is always f32; samples are converted to `T` as they are written
+    fn build_stream<T>(device: &cpal::Device, config: &StreamConfig, state: Arc<Mutex<PlaybackState>>, channels: usize) -> Result<Stream, String>
+    where
+        T: SizedSample + FromSample<f32>,
+    {
+        let sample_rate = config.sample_rate.0;
+        device
+            .build_output_stream(
+                config,
+                move |data: &mut [T], info: &cpal::OutputCallbackInfo|
+                {
+                    let mut state = state.lock().unwrap();
+                    let playback_instant = info.timestamp().playback;
+                    let elapsed_frames = state.last_callback
+                        .and_then(|previous| playback_instant.duration_since(&previous))
+                        .map(|gap| gap.as_secs_f64() * sample_rate as f64);
+                    state.last_callback = Some(playback_instant);
+                    Self::detect_underrun(&mut state, elapsed_frames, data.len() / channels);
+                    Self::render(&mut state, data, channels);
+                },
+                |err| eprintln!("Audio stream error: {}", err),
+                None,
+            )
+            .map_err(|e| format!("Failed to build stream: {}", e))
+    }
+
+    /// Count a callback as an underrun if the device ran dry before it was called
+    ///
+    /// # Parameters
+    /// * `state` - playback state holding the counter
+    /// * `elapsed_frames` - device clock time since the previous callback, in frames (None for the first callback)
+    /// * `frames` - number of frames this callback must supply
+    ///
+    /// # Notes
+    /// Consecutive callbacks should be spaced by the length of the previous one on
+    /// the device clock. A larger gap means the device played out everything it was
+    /// given and had to fill in silence. Gaps are only counted while playing.
+    fn detect_underrun(state: &mut PlaybackState, elapsed_frames: Option<f64>, frames: usize)
+    {
+        let previous_frames = state.last_callback_frames;
+        if let Some(elapsed_frames) = elapsed_frames
+        {
+            let allowed_frames = previous_frames as f64 * (1.0 + UNDERRUN_TOLERANCE);
+            if state.is_playing && previous_frames > 0 && elapsed_frames > allowed_frames
+            {
+                // recorded for get_last_underrun; printing here could block the audio thread
+                state.underrun_count += 1;
+                state.last_underrun = Some((previous_frames, elapsed_frames.round() as usize));
+            }
+        }
+
+        state.last_callback_frames = frames;
+    }
+
+    /// Fill an output buffer from the playback state
+    ///
+    /// # Parameters
+    /// * `state` - playback state to read from and advance
+    /// * `data` - interleaved output samples to fill
+    /// * `channels` - number of audio channels
+    fn render<T>(state: &mut PlaybackState, data: &mut [T], channels: usize)
+    where
+        T: Sample + FromSample<f32>,
+    {
+        for frame in data.chunks_mut(channels)
+        {
+            // read per frame, since the queue can swap in a new buffer mid-callback
+            let total_frames = state.buffer.len() / channels;
+            let decay = state.meter_decay;
+            state.meter.iter_mut().for_each(|level| *level *= decay);
+
+            if state.is_playing && state.position >= 0.0 && (state.position as usize) < total_frames
+            {
+                // blend in the head of the next queued buffer over the tail of this one
+                let crossfade = state.queue
+                    .front()
+                    .filter(|queued| queued.crossfade_frames > 0)
+                    .and_then(|queued|
+                    {
+                        let fade_start = total_frames - queued.crossfade_frames;
+                        let offset = state.position - fade_start as f64;
+                        (offset >= 0.0).then(||
+                        {
+                            let mix = (offset / queued.crossfade_frames as f64) as f32;
+                            (offset as usize, mix)
+                        })
+                    });
+
+                // fade out the audio from before a seek while fading in the new position
+                let seek_mix = state.seek_from.map(|from|
+                {
+                    let mix = 1.0 - state.seek_fade_remaining as f32 / state.seek_fade_frames.max(1) as f32;
+                    (from, mix)
+                });
+
+                for (ch, sample) in frame.iter_mut().enumerate()
+                {
+                    // linearly interpolate between neighbouring frames for non-unity speeds
+                    let mut value = state.sample_at(state.position, ch, channels);
+                    if let (Some((offset, mix)), Some(queued)) = (crossfade, state.queue.front())
+                    {
+                        let incoming = queued.buffer[offset * channels + ch];
+                        value = value * (1.0 - mix) + incoming * mix;
+                    }
+                    if let Some((from, mix)) = seek_mix
+                    {
+                        value = state.sample_at(from, ch, channels) * (1.0 - mix) + value * mix;
+                    }
+                    let value = value * state.gain * state.balance_gain(ch, channels);
+                    if let Some(level) = state.meter.get_mut(ch)
+                    {
+                        *level = level.max(value.abs());
+                    }
+                    *sample = T::from_sample(value);
+                }
+                let step = if state.reverse { -state.speed } else { state.speed };
+                state.position += step;
+                if let Some(from) = state.seek_from
+                {
+                    state.seek_fade_remaining = state.seek_fade_remaining.saturating_sub(1);
+                    state.seek_from = (state.seek_fade_remaining > 0).then_some(from + step);
+                }
+
+                // ramp towards the fade target, finishing a pending pause or stop at silence
+                if state.gain < state.fade_target
+                {
+                    state.gain = (state.gain + state.fade_step).min(state.fade_target);
+                }
+                else if state.gain > state.fade_target
+                {
+                    state.gain = (state.gain - state.fade_step).max(state.fade_target);
+                    if state.gain <= 0.0
+                    {
+                        state.complete_fade_out();
+                        continue;
+                    }
+                }
+
+                // wrap back to the loop start instead of running off the end
+                if let Some((loop_start, loop_end)) = state.loop_range
+                {
+                    let loop_length = (loop_end - loop_start) as f64;
+                    if state.reverse && state.position < loop_start as f64
+                    {
+                        state.position = loop_end as f64 - (loop_start as f64 - state.position) % loop_length;
+                    }
+                    else if !state.reverse && state.position >= loop_end as f64
+                    {
+                        state.position = loop_start as f64 + (state.position - loop_end as f64) % loop_length;
+                    }
+                }
+                state.advance_queue(channels);
+            }
+            else
+            {
+                frame.fill(T::EQUILIBRIUM);
+                if state.position < 0.0
+                {
+                    // reverse playback ran past the buffer start
+                    state.position = 0.0;
+                    state.finished |= state.is_playing;
+                    state.is_playing = false;
+                }
+                else if state.position as usize >= total_frames
+                {
+                    state.finished |= state.is_playing;
+                    state.is_playing = false;
+                }
+            }
+        }
+    }
+
+    /// Convert a fade length to frames
+    ///
+    /// # Parameters
+    /// * `fade_ms` - fade length in milliseconds
+    /// * `sample_rate` - output sample rate in Hz
+    ///
+    /// # Returns
+    /// `usize` - fade length in frames, 0 for an instant change
+    fn fade_frames(fade_ms: f64, sample_rate: u32) -> usize
+    {
+        (fade_ms.max(0.0) * sample_rate as f64 / 1000.0).round() as usize
+    }
+
+    /// Calculate the per-frame gain change for a fade
+    ///
+    /// # Parameters
+    /// * `fade_ms` - fade length in milliseconds
+    /// * `sample_rate` - output sample rate in Hz
+    ///
+    /// # Returns
+    /// `f32` - gain step per frame (1.0 for an instant change)
+    fn fade_step(fade_ms: f64, sample_rate: u32) -> f32
+    {
+        let fade_frames = (fade_ms.max(0.0) * sample_rate as f64 / 1000.0).round();
+        if fade_frames < 1.0
+        {
+            1.0
+        }
+        else
+        {
+            (1.0 / fade_frames) as f32
+        }
+    }
+
+    /// Choose a sample rate the device supports
+    ///
+    /// # Parameters
+    /// * `device` - output device to query
+    /// * `sample_rate` - preferred sample rate in Hz
+    /// * `channels` - number of audio channels
+    /// * `sample_format` - sample format the stream will use
+    ///
+    /// # Returns
+    /// `u32` - the preferred rate if supported, otherwise the closest supported rate
+    ///
+    /// # Notes
+    /// Returns the preferred rate unchanged if the device cannot be queried or has no
+    /// configuration for the channel count, leaving stream creation to report the error
+    fn negotiate_sample_rate(device: &cpal::Device, sample_rate: u32, channels: usize, sample_format: SampleFormat) -> u32
+    {
+        let ranges: Vec<_> = match device.supported_output_configs()
+        {
+            Ok(configs) => configs
+                .filter(|c| c.channels() as usize == channels && c.sample_format() == sample_format)
+                .collect(),
+            Err(_) => return sample_rate,
+        };
+
+        ranges
+            .iter()
+            .map(|c| sample_rate.clamp(c.min_sample_rate().0, c.max_sample_rate().0))
+            .min_by_key(|&rate| rate.abs_diff(sample_rate))
+            .unwrap_or(sample_rate)
+    }
+
+    /// Start playback with new audio buffer
+    ///
+    /// # Parameters
+    /// * `buffer` - audio samples to play
+    /// * `start_time_offset` - time offset in seconds for position calculation
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    ///
+    /// # Notes
+    /// The buffer is resampled to the device rate if the device could not run at
+    /// the requested rate
+    pub fn play(&mut self, buffer: Vec<f32>, start_time_offset: f64) -> Result<(), String>
+    {
+        let buffer = self.to_device_rate(buffer);
+
+        let mut state = self.state.lock().unwrap();
+        state.queue.clear();
+        state.queue_index = None;
+        state.reverse = false;
+        state.seek_from = None;
+        state.buffer = buffer;
+        state.position = 0.0;
+        state.is_playing = true;
+        state.is_paused = false;
+        state.finished = false;
+        state.start_time_offset = start_time_offset;
+        state.loop_range = None;
+        state.gain = 0.0;
+        state.fade_target = 1.0;
+        state.stop_after_fade = false;
+        Ok(())
+    }
+
+    /// Start playing a buffer backwards from its end
+    ///
+    /// # Parameters
+    /// * `buffer` - audio samples to play
+    /// * `start_time_offset` - time offset in seconds for position calculation
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    ///
+    /// # Notes
+    /// The read head moves towards the buffer start and stops there. The buffer
+    /// itself is not reversed, so positions keep the same timeline as `play`.
+    pub fn play_reverse(&mut self, buffer: Vec<f32>, start_time_offset: f64) -> Result<(), String>
+    {
+        self.play(buffer, start_time_offset)?;
+
+        let mut state = self.state.lock().unwrap();
+        let total_frames = state.buffer.len() / self.channels;
+        state.position = total_frames.saturating_sub(1) as f64;
+        state.reverse = true;
+        Ok(())
+    }
+
+    /// Start playback of several buffers back to back
+    ///
+    /// # Parameters
+    /// * `items` - (buffer, start time offset, crossfade into the next buffer in ms) for each buffer
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if the queue is empty
+    ///
+    /// # Notes
+    /// The audio callback moves to the next buffer as soon as the current one is
+    /// used up, so there is no gap between them. A crossfade overlaps the tail of
+    /// a buffer with the head of the next, shortened to fit the shorter of the two.
+    pub fn play_queue(&mut self, items: Vec<(Vec<f32>, f64, f64)>) -> Result<(), String>
+    {
+        if items.is_empty()
+        {
+            return Err("Playback queue is empty".to_string());
+        }
 
-        let state = Arc::new(Mutex::new(PlaybackState
+        let mut queue = VecDeque::with_capacity(items.len());
+        let mut previous_frames = 0;
+        let mut previous_crossfade_ms = 0.0f64;
+        for (buffer, start_time_offset, crossfade_ms) in items
         {
-            buffer: Vec::new(),
-            position: 0,
-            is_playing: false,
-            is_paused: false,
-            start_time_offset: 0.0,
-        }));
-
-        let state_clone = state.clone();
+            let buffer = self.to_device_rate(buffer);
+            let frames = buffer.len() / self.channels;
+            let requested = (previous_crossfade_ms.max(0.0) * self.sample_rate as f64 / 1000.0).round() as usize;
+            queue.push_back(QueuedBuffer
+            {
+                buffer,
+                start_time_offset,
+                crossfade_frames: requested.min(previous_frames).min(frames),
+            });
+            previous_frames = frames;
+            previous_crossfade_ms = crossfade_ms;
+        }
 
-        // build output stream with samples from audio buffer
-        let stream = device
-            .build_output_stream(
-                &config,
-                move |data: &mut [f32], _: &cpal::OutputCallbackInfo|
-                {
-                    let mut state = state_clone.lock().unwrap();
+        let first = queue.pop_front().unwrap();
+        self.play(first.buffer, first.start_time_offset)?;
 
-                    for sample in data.iter_mut()
-                    {
-                        if state.is_playing && state.position < state.buffer.len()
-                        {
-                            *sample = state.buffer[state.position];
-                            state.position += 1;
-                        }
-                        else
-                        {
-                            *sample = 0.0;
-                            if state.position >= state.buffer.len()
-                            {
-                                state.is_playing = false;
-                            }
-                        }
-                    }
-                },
-                |err| eprintln!("Audio stream error: {}", err),
-                None,
-            )
-            .map_err(|e| format!("Failed to build stream: {}", e))?;
+        let mut state = self.state.lock().unwrap();
+        state.queue = queue;
+        state.queue_index = Some(0);
+        Ok(())
+    }
 
-        stream.play().map_err(|e| format!("Failed to play stream: {}", e))?;
+    /// Get which queued buffer is playing
+    ///
+    /// # Returns
+    /// `Option<usize>` - index into the queue passed to `play_queue`, None if not playing a queue
+    pub fn get_current_queue_index(&self) -> Option<usize>
+    {
+        self.state.lock().unwrap().queue_index
+    }
 
-        Ok(AudioPlayback
+    /// Resample a buffer to the device rate if the device could not run at the source rate
+    ///
+    /// # Parameters
+    /// * `buffer` - interleaved samples at the source rate
+    ///
+    /// # Returns
+    /// `Vec<f32>` - samples at the device rate
+    fn to_device_rate(&self, buffer: Vec<f32>) -> Vec<f32>
+    {
+        if self.source_rate != self.sample_rate
         {
-            state,
-            _stream: stream,
-            sample_rate,
-            channels,
-        })
+            resample::resample(&buffer, self.channels, self.source_rate, self.sample_rate, self.resample_quality)
+        }
+        else
+        {
+            buffer
+        }
     }
 
-    /// Start playback with new audio buffer
+    /// Repeat a section of the current buffer
     ///
     /// # Parameters
-    /// * `buffer` - audio samples to play
-    /// * `start_time_offset` - time offset in seconds for position calculation
+    /// * `start` - loop start in seconds, on the same timeline as the position
+    /// * `end` - loop end in seconds
     ///
-    /// # Returns
-    /// `Result<(), String>` - Ok if successful
-    pub fn play(&mut self, buffer: Vec<f32>, start_time_offset: f64) -> Result<(), String>
+    /// # Notes
+    /// Bounds are clamped to the buffer. An empty range clears the loop.
+    pub fn set_loop(&mut self, start: f64, end: f64)
     {
         let mut state = self.state.lock().unwrap();
-        state.buffer = buffer;
-        state.position = 0;
-        state.is_playing = true;
-        state.is_paused = false;
-        state.start_time_offset = start_time_offset;
-        Ok(())
+        let total_frames = state.buffer.len() / self.channels;
+        let to_frame = |time: f64|
+        {
+            let frame = ((time - state.start_time_offset).max(0.0) * self.sample_rate as f64) as usize;
+            frame.min(total_frames)
+        };
+        let loop_start = to_frame(start);
+        let loop_end = to_frame(end);
+
+        state.loop_range = (loop_end > loop_start).then_some((loop_start, loop_end));
+    }
+
+    /// Stop repeating and let playback run to the end of the buffer
+    pub fn clear_loop(&mut self)
+    {
+        self.state.lock().unwrap().loop_range = None;
     }
 
     /// Resume playback from current position
@@ -125,7 +844,8 @@ impl AudioPlayback
     /// `Result<(), String>` - Ok if successful
     ///
     /// # Notes
-    /// Only resumes if playback was previously paused
+    /// Only resumes if playback was previously paused. Fades in from the current
+    /// gain, so resuming during a pause fade reverses it smoothly.
     pub fn resume(&mut self) -> Result<(), String>
     {
         let mut state = self.state.lock().unwrap();
@@ -133,38 +853,68 @@ impl AudioPlayback
         {
             state.is_playing = true;
             state.is_paused = false;
+            state.fade_target = 1.0;
+            state.stop_after_fade = false;
         }
         Ok(())
     }
 
     /// Pause playback without resetting position
+    ///
+    /// # Notes
+    /// Output fades to silence before the position stops advancing. If nothing
+    /// has been faded in yet, the pause takes effect at once.
     pub fn pause(&mut self)
     {
         let mut state = self.state.lock().unwrap();
-        if state.is_playing
+        if state.is_playing && !state.is_paused
         {
-            state.is_playing = false;
             state.is_paused = true;
+            state.fade_target = 0.0;
+            if state.gain <= 0.0
+            {
+                state.complete_fade_out();
+            }
         }
     }
 
     /// Stop playback and reset position
+    ///
+    /// # Notes
+    /// If audio is playing it fades to silence first; the position resets once the fade completes
     pub fn stop(&mut self)
     {
         let mut state = self.state.lock().unwrap();
-        state.is_playing = false;
         state.is_paused = false;
-        state.position = 0;
-        state.start_time_offset = 0.0;
+        if state.is_playing && state.gain > 0.0
+        {
+            state.fade_target = 0.0;
+            state.stop_after_fade = true;
+        }
+        else
+        {
+            state.stop_after_fade = true;
+            state.complete_fade_out();
+        }
+    }
+
+    /// Set the length of the fade applied when playback starts, pauses, or stops
+    ///
+    /// # Parameters
+    /// * `fade_ms` - fade length in milliseconds, 0 for instant changes
+    pub fn set_fade_length(&mut self, fade_ms: f64)
+    {
+        self.state.lock().unwrap().fade_step = Self::fade_step(fade_ms, self.sample_rate);
     }
 
     /// Check if currently playing
     ///
     /// # Returns
-    /// `bool` - true if playing
+    /// `bool` - true if playing and not fading out to a pause or stop
     pub fn is_playing(&self) -> bool
     {
-        self.state.lock().unwrap().is_playing
+        let state = self.state.lock().unwrap();
+        state.is_playing && state.fade_target > 0.0
     }
 
     /// Check if currently paused
@@ -176,29 +926,529 @@ impl AudioPlayback
         self.state.lock().unwrap().is_paused
     }
 
+    /// Check if playback ran to the end of the buffer
+    ///
+    /// # Returns
+    /// `bool` - true once the whole buffer has been played, false while playing, paused, or after a stop
+    ///
+    /// # Notes
+    /// Reverse playback finishes at the buffer start. Starting playback, seeking, or
+    /// stopping clears the flag.
+    pub fn has_finished(&self) -> bool
+    {
+        self.state.lock().unwrap().finished
+    }
+
+    /// Get output level meter readings
+    ///
+    /// # Returns
+    /// `Vec<f32>` - peak level of each output channel, falling back at 20 dB per second
+    ///
+    /// # Notes
+    /// Levels are taken from the samples sent to the device, after fades are applied
+    pub fn get_output_levels(&self) -> Vec<f32>
+    {
+        self.state.lock().unwrap().meter.clone()
+    }
+
     /// Get current playback position
     ///
     /// # Returns
     /// `f64` - position in seconds including start time offset
+    ///
+    /// # Notes
+    /// The position tracks the buffer read position, so it advances faster or
+    /// slower than wall-clock time when the playback speed is not 1.0
     pub fn get_position(&self) -> f64
     {
-        let state = self.state.lock().unwrap();
-        let current_sample = state.position / self.channels;
-        let current_time = current_sample as f64 / self.sample_rate as f64;
-        current_time + state.start_time_offset
+        self.state.lock().unwrap().position_seconds(self.sample_rate)
+    }
+
+    /// Get the number of buffer underruns since the stream was opened
+    ///
+    /// # Returns
+    /// `usize` - callbacks that arrived after the device had already run out of samples
+    pub fn get_underrun_count(&self) -> usize
+    {
+        self.state.lock().unwrap().underrun_count
+    }
+
+    /// Get details of the most recent buffer underrun
+    ///
+    /// # Returns
+    /// `Option<(usize, usize)>` - (frames the previous callback supplied, frames that elapsed before the next one), None if none occurred
+    pub fn get_last_underrun(&self) -> Option<(usize, usize)>
+    {
+        self.state.lock().unwrap().last_underrun
+    }
+
+    /// Get a probe for polling the position from another thread
+    ///
+    /// # Returns
+    /// `PositionProbe` - handle sharing this instance's playback state
+    pub fn position_probe(&self) -> PositionProbe
+    {
+        PositionProbe
+        {
+            state: self.state.clone(),
+            sample_rate: self.sample_rate,
+        }
     }
 
     /// Set playback position
     ///
     /// # Parameters
-    /// * `position` - new position in seconds
+    /// * `position` - new position in seconds, on the same timeline as `get_position`
     ///
     /// # Notes
-    /// Position is clamped to buffer length
+    /// Position is snapped to a frame boundary and clamped to the buffer. The play
+    /// state is left unchanged: seeking while playing continues from the new spot,
+    /// and seeking while paused moves the point playback resumes from. A stop that
+    /// is still fading out keeps the new position instead of rewinding. Seeking
+    /// while audible crossfades from the old position to avoid a click.
     pub fn set_position(&mut self, position: f64)
     {
         let mut state = self.state.lock().unwrap();
-        let sample_position = (position * self.sample_rate as f64) as usize * self.channels;
-        state.position = sample_position.min(state.buffer.len());
+        let total_frames = state.buffer.len() / self.channels;
+        let local_time = (position - state.start_time_offset).max(0.0);
+        let frame_position = ((local_time * self.sample_rate as f64) as usize).min(total_frames);
+
+        // crossfade from the old position so the jump does not click
+        if state.is_playing && state.gain > 0.0 && state.seek_fade_frames > 0
+        {
+            state.seek_from = Some(state.position);
+            state.seek_fade_remaining = state.seek_fade_frames;
+        }
+
+        state.position = frame_position as f64;
+        state.stop_after_fade = false;
+        state.finished = false;
+    }
+
+    /// Set the master stereo balance
+    ///
+    /// # Parameters
+    /// * `balance` - -1.0 for full left, 0.0 for centre, 1.0 for full right
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if balance is outside -1.0 to 1.0
+    ///
+    /// # Notes
+    /// Unlike a pan, balance only turns down the side it moves away from, so centre
+    /// leaves both channels at full level. Takes effect immediately. Mono streams
+    /// are unaffected.
+    pub fn set_balance(&mut self, balance: f32) -> Result<(), String>
+    {
+        if !(-1.0..=1.0).contains(&balance)
+        {
+            return Err(format!("Invalid balance: {}", balance));
+        }
+        self.state.lock().unwrap().balance = balance;
+        Ok(())
+    }
+
+    /// Set the quality used to convert buffers to the device rate
+    ///
+    /// # Parameters
+    /// * `quality` - sample rate conversion quality, applied from the next buffer played
+    pub fn set_resample_quality(&mut self, quality: ResampleQuality)
+    {
+        self.resample_quality = quality;
+    }
+
+    /// Set the length of the crossfade applied when seeking during playback
+    ///
+    /// # Parameters
+    /// * `fade_ms` - crossfade length in milliseconds, 0 to jump instantly
+    pub fn set_seek_crossfade(&mut self, fade_ms: f64)
+    {
+        self.state.lock().unwrap().seek_fade_frames = Self::fade_frames(fade_ms, self.sample_rate);
+    }
+
+    /// Set the playback speed
+    ///
+    /// # Parameters
+    /// * `speed` - rate multiplier, where 1.0 is normal speed
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful, Err if speed is not positive
+    ///
+    /// # Notes
+    /// The buffer is resampled on the fly with linear interpolation, so pitch
+    /// changes along with speed
+    pub fn set_playback_speed(&mut self, speed: f64) -> Result<(), String>
+    {
+        if speed <= 0.0 || !speed.is_finite()
+        {
+            return Err(format!("Invalid playback speed: {}", speed));
+        }
+        self.state.lock().unwrap().speed = speed;
+        Ok(())
+    }
+}
+
+/// Create a playback instance with no device behind it, driven by `render_frames`
+#[cfg(test)]
+pub(crate) fn headless(sample_rate: u32, channels: usize) -> AudioPlayback
+{
+    AudioPlayback
+    {
+        state: Arc::new(Mutex::new(PlaybackState::new(sample_rate, channels))),
+        _shutdown: mpsc::channel().0,
+        sample_rate,
+        source_rate: sample_rate,
+        resample_quality: ResampleQuality::default(),
+        channels,
+    }
+}
+
+/// Run the audio callback's rendering for a number of frames
+#[cfg(test)]
+pub(crate) fn render_frames(playback: &AudioPlayback, frames: usize) -> Vec<f32>
+{
+    let mut output = vec![0.0f32; frames * playback.channels];
+    AudioPlayback::render(&mut playback.state.lock().unwrap(), &mut output, playback.channels);
+    output
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn loop_wraps_instead_of_stopping()
+    {
+        let mut playback = headless(1000, 1);
+        playback.play(vec![0.5; 1000], 0.0).unwrap();
+        playback.set_loop(0.2, 0.4);
+
+        // well past both the loop end and the end of the buffer
+        render_frames(&playback, 1500);
+
+        assert!(playback.is_playing());
+        assert!(!playback.has_finished());
+        let position = playback.get_position();
+        assert!((0.2..0.4).contains(&position));
+
+        playback.clear_loop();
+        render_frames(&playback, 1000);
+        assert!(playback.has_finished());
+    }
+
+    #[test]
+    fn double_speed_advances_position_twice_as_fast()
+    {
+        let mut playback = headless(1000, 2);
+        playback.play(vec![0.25; 2 * 1000], 0.0).unwrap();
+        playback.set_playback_speed(2.0).unwrap();
+
+        render_frames(&playback, 200);
+
+        assert!((playback.get_position() - 0.4).abs() < 1e-9);
+        assert!(playback.set_playback_speed(0.0).is_err());
+    }
+
+    #[test]
+    fn list_output_devices_includes_default()
+    {
+        let devices = AudioPlayback::list_output_devices();
+
+        // hosts without audio hardware have nothing to list, which must not be an error
+        if !devices.is_empty()
+        {
+            let default_name = cpal::default_host().default_output_device().and_then(|device| device.name().ok());
+            assert!(default_name.is_some_and(|name| devices.contains(&name)));
+        }
+        assert!(devices.iter().all(|name| !name.is_empty()));
+    }
+
+    #[test]
+    fn buffer_is_resampled_to_device_rate()
+    {
+        // as if the device only ran at 48 kHz when 44.1 kHz was requested
+        let mut playback = headless(48000, 2);
+        playback.source_rate = 44100;
+
+        playback.play(vec![0.1; 2 * 44100], 0.0).unwrap();
+
+        let frames = playback.state.lock().unwrap().buffer.len() / 2;
+        assert!(frames.abs_diff(48000) <= 1);
+        render_frames(&playback, 24000);
+        assert!((playback.get_position() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn i16_output_converts_known_values()
+    {
+        let mut playback = headless(1000, 1);
+        playback.set_fade_length(0.0);
+        // the first frame is rendered before the gain steps up from silence
+        playback.play(vec![0.0, 0.0, 0.5, -0.5, 1.0, -1.0, 0.25], 0.0).unwrap();
+
+        let mut output = vec![1i16; 7];
+        AudioPlayback::render(&mut playback.state.lock().unwrap(), &mut output, 1);
+
+        assert_eq!(&output[1..], &[0, 16384, -16384, i16::MAX, i16::MIN, 8192]);
+    }
+
+    #[test]
+    fn resume_ramps_up_instead_of_jumping()
+    {
+        let mut playback = headless(1000, 1);
+        playback.set_fade_length(10.0);
+        playback.play(vec![0.5; 1000], 0.0).unwrap();
+        render_frames(&playback, 100);
+
+        playback.pause();
+        let fade_out = render_frames(&playback, 50);
+        assert!(fade_out[0] > 0.4 && fade_out[20..].iter().all(|&s| s == 0.0));
+        assert!(!playback.is_playing());
+
+        playback.resume().unwrap();
+        let output = render_frames(&playback, 20);
+
+        assert!(output[0] < 0.1);
+        assert!(output[..10].windows(2).all(|pair| pair[1] > pair[0]));
+        assert!(output[10..].iter().all(|&s| (s - 0.5).abs() < 1e-6));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn pause_before_the_first_callback_holds_position()
+    {
+        let mut playback = headless(1000, 1);
+        playback.play(vec![0.5; 300], 0.0).unwrap();
+        // nothing has faded in yet, so there is no fade out to wait for
+        playback.pause();
+        let output = render_frames(&playback, 400);
+
+        assert!(output.iter().all(|&s| s == 0.0));
+        assert_eq!(playback.get_position(), 0.0);
+        assert!(playback.is_paused() && !playback.has_finished());
+
+        playback.resume().unwrap();
+        render_frames(&playback, 100);
+        assert!((playback.get_position() - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn meter_reads_played_amplitude()
+    {
+        let mut playback = headless(44100, 2);
+        playback.play(crate::generators::sine_tone(440.0, 44100, 0.6, 44100, 2), 0.0).unwrap();
+
+        render_frames(&playback, 4410);
+        let levels = playback.get_output_levels();
+        assert_eq!(levels.len(), 2);
+        assert!(levels.iter().all(|&level| (level - 0.6).abs() < 0.01));
+
+        // the meter follows what reaches the device, so after a hard right balance
+        // the left reading falls away at 20 dB per second
+        playback.set_balance(1.0).unwrap();
+        render_frames(&playback, 44100 / 2);
+        let levels = playback.get_output_levels();
+        assert!((levels[0] - 0.6 * 10f32.powf(-0.5)).abs() < 0.01);
+        assert!((levels[1] - 0.6).abs() < 0.01);
+    }
+
+    #[test]
+    fn seek_while_playing_keeps_playing()
+    {
+        let mut playback = headless(1000, 2);
+        playback.play(vec![0.5; 2 * 1000], 0.0).unwrap();
+        render_frames(&playback, 100);
+
+        playback.set_position(0.5004);
+
+        // snapped to a frame boundary
+        assert_eq!(playback.state.lock().unwrap().position, 500.0);
+        render_frames(&playback, 100);
+        assert!(playback.is_playing());
+        assert!((playback.get_position() - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn seek_while_paused_moves_resume_point()
+    {
+        let mut playback = headless(1000, 2);
+        playback.play(vec![0.5; 2 * 1000], 0.0).unwrap();
+        render_frames(&playback, 100);
+        playback.pause();
+        render_frames(&playback, 50);
+
+        playback.set_position(0.25);
+        render_frames(&playback, 100);
+
+        assert!(playback.is_paused() && !playback.is_playing());
+        assert!((playback.get_position() - 0.25).abs() < 1e-9);
+
+        playback.resume().unwrap();
+        render_frames(&playback, 100);
+        assert!((playback.get_position() - 0.35).abs() < 1e-9);
+    }
+
+    #[test]
+    fn position_probe_follows_playback_from_another_thread()
+    {
+        let mut playback = headless(1000, 1);
+        playback.play(vec![0.5; 1000], 2.0).unwrap();
+        let probe = playback.position_probe();
+
+        render_frames(&playback, 300);
+
+        // the position callback's timer thread polls through a probe like this one
+        let (position, playing) = thread::spawn(move || (probe.position(), probe.is_playing())).join().unwrap();
+        assert!((position - 2.3).abs() < 1e-9);
+        assert!(playing);
+    }
+
+    #[test]
+    fn queue_advances_to_the_next_buffer_without_a_gap()
+    {
+        let mut playback = headless(1000, 1);
+        playback.play_queue(vec![(vec![0.25; 500], 0.0, 0.0), (vec![0.5; 300], 2.0, 0.0)]).unwrap();
+        assert_eq!(playback.get_current_queue_index(), Some(0));
+
+        let first = render_frames(&playback, 400);
+        assert_eq!(playback.get_current_queue_index(), Some(0));
+        assert_eq!(first[399], 0.25);
+
+        // the second buffer picks up on the very next frame, with its own start time
+        let second = render_frames(&playback, 200);
+        assert_eq!(playback.get_current_queue_index(), Some(1));
+        assert_eq!(second[..100], [0.25; 100]);
+        assert_eq!(second[100..], [0.5; 100]);
+        assert!((playback.get_position() - 2.1).abs() < 1e-9);
+
+        render_frames(&playback, 300);
+        assert!(playback.has_finished());
+        assert!(playback.play_queue(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn reverse_playback_moves_towards_the_start()
+    {
+        let mut playback = headless(1000, 1);
+        let ramp: Vec<f32> = (0..1000).map(|i| i as f32 / 1000.0).collect();
+        playback.play_reverse(ramp, 1.0).unwrap();
+
+        let mut previous = playback.get_position();
+        assert!((previous - 1.999).abs() < 1e-9);
+        for block in 0..9
+        {
+            let output = render_frames(&playback, 100);
+            let position = playback.get_position();
+            assert!(position < previous, "{} after {}", position, previous);
+            previous = position;
+            // the ramp is read backwards, once the start's fade-in has passed
+            if block > 0
+            {
+                assert!(output.windows(2).all(|pair| pair[1] < pair[0]));
+            }
+        }
+
+        render_frames(&playback, 200);
+        assert!(playback.has_finished());
+        assert!(playback.get_position() >= 1.0);
+    }
+
+    #[test]
+    fn late_callback_counts_as_underrun()
+    {
+        let mut playback = headless(1000, 2);
+        playback.play(vec![0.5; 2 * 1000], 0.0).unwrap();
+
+        // callbacks of 100 frames arriving on time, then one starved for 250 frames
+        let mut state = playback.state.lock().unwrap();
+        AudioPlayback::detect_underrun(&mut state, None, 100);
+        AudioPlayback::detect_underrun(&mut state, Some(100.0), 100);
+        AudioPlayback::detect_underrun(&mut state, Some(140.0), 100);
+        assert_eq!(state.underrun_count, 0);
+        AudioPlayback::detect_underrun(&mut state, Some(250.0), 100);
+        drop(state);
+
+        assert_eq!(playback.get_underrun_count(), 1);
+        assert_eq!(playback.get_last_underrun(), Some((100, 250)));
+
+        // gaps once playback has stopped are expected, not underruns
+        playback.stop();
+        render_frames(&playback, 100);
+        AudioPlayback::detect_underrun(&mut playback.state.lock().unwrap(), Some(1000.0), 100);
+        assert_eq!(playback.get_underrun_count(), 1);
+    }
+
+    #[test]
+    fn seek_crossfade_avoids_a_jump()
+    {
+        // largest step between consecutive output frames across a seek from 0.1 s to 0.9 s
+        let seek_step = |fade_ms: f64|
+        {
+            let mut playback = headless(1000, 1);
+            playback.play((0..1000).map(|i| i as f32 / 1000.0).collect(), 0.0).unwrap();
+            playback.set_seek_crossfade(fade_ms);
+            let before = render_frames(&playback, 100);
+            playback.set_position(0.9);
+            let after = render_frames(&playback, 50);
+            let seam: Vec<f32> = std::iter::once(before[99]).chain(after).collect();
+            seam.windows(2).map(|pair| (pair[1] - pair[0]).abs()).fold(0.0, f32::max)
+        };
+
+        assert!(seek_step(0.0) > 0.7);
+        // the 0.8 jump is spread over 20 frames
+        assert!(seek_step(20.0) < 0.05, "step {}", seek_step(20.0));
+    }
+
+    #[test]
+    fn finishing_differs_from_stopping()
+    {
+        let mut playback = headless(1000, 1);
+        playback.play(vec![0.5; 300], 0.0).unwrap();
+        render_frames(&playback, 200);
+        assert!(!playback.has_finished());
+
+        render_frames(&playback, 200);
+        assert!(playback.has_finished());
+        assert!(!playback.is_paused() && !playback.is_playing());
+
+        // a stop by the user is not a finish, and playing again clears the flag
+        playback.play(vec![0.5; 300], 0.0).unwrap();
+        assert!(!playback.has_finished());
+        render_frames(&playback, 100);
+        playback.stop();
+        render_frames(&playback, 300);
+        assert!(!playback.has_finished() && !playback.is_playing());
+
+        // pausing on the last frames does not finish either
+        playback.play(vec![0.5; 300], 0.0).unwrap();
+        render_frames(&playback, 290);
+        playback.pause();
+        render_frames(&playback, 100);
+        assert!(playback.is_paused() && !playback.has_finished());
+    }
+
+    #[test]
+    fn full_right_balance_silences_the_left()
+    {
+        let mut playback = headless(1000, 2);
+        playback.play(vec![0.5; 2 * 1000], 0.0).unwrap();
+        playback.set_balance(1.0).unwrap();
+        // past the start's fade-in
+        render_frames(&playback, 50);
+
+        let output = render_frames(&playback, 100);
+        assert!(output.chunks(2).all(|frame| frame == [0.0, 0.5]));
+
+        // half left turns the right down by half and leaves the left alone
+        playback.set_balance(-0.5).unwrap();
+        let output = render_frames(&playback, 100);
+        assert!(output.chunks(2).all(|frame| frame == [0.5, 0.25]));
+        assert!(playback.set_balance(1.5).is_err());
+
+        // mono has no sides to balance
+        let mut mono = headless(1000, 1);
+        mono.play(vec![0.5; 1000], 0.0).unwrap();
+        mono.set_balance(1.0).unwrap();
+        render_frames(&mono, 50);
+        assert!(render_frames(&mono, 100).iter().all(|&s| s == 0.5));
+    }
+}
@@ -0,0 +1,134 @@
+//! Saving and loading editing sessions as JSON project files
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::audio_engine::{AudioTrack, SourceFormat};
+
+/// Project file format version written by `save`
+const PROJECT_VERSION: u32 = 1;
+
+/// Top-level contents of a project file
+#[derive(Serialize, Deserialize)]
+struct ProjectFile
+{
+    version: u32,
+    tracks: Vec<ProjectTrack>,
+}
+
+/// A single track as stored in a project file
+#[derive(Serialize, Deserialize)]
+struct ProjectTrack
+{
+    name: String,
+    sample_rate: u32,
+    channels: usize,
+    start_offset: f64,
+    #[serde(default)]
+    gain_envelope: Vec<(f64, f64)>,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+    #[serde(default)]
+    codec: Option<String>,
+    #[serde(default)]
+    bits_per_sample: Option<u32>,
+    samples: String,  // base64 of little-endian f32 interleaved samples
+}
+
+/// Write tracks to a project file
+///
+/// # Parameters
+/// * `path` - output file path
+/// * `tracks` - tracks to save
+///
+/// # Returns
+/// `Result<(), String>` - Ok if successful
+///
+/// # Notes
+/// Sample data is embedded in the file rather than referenced by source path, since
+/// edits are applied to the in-memory audio and the original file may no longer match
+pub fn save(path: &str, tracks: &[AudioTrack]) -> Result<(), String>
+{
+    let project = ProjectFile
+    {
+        version: PROJECT_VERSION,
+        tracks: tracks.iter().map(|track|
+        {
+            let bytes: Vec<u8> = track.audio_data.iter().flat_map(|s| s.to_le_bytes()).collect();
+            ProjectTrack
+            {
+                name: track.name.clone(),
+                sample_rate: track.sample_rate,
+                channels: track.channels,
+                start_offset: track.start_offset,
+                gain_envelope: track.gain_envelope.clone(),
+                metadata: track.metadata.clone(),
+                codec: track.source_format.codec.clone(),
+                bits_per_sample: track.source_format.bits_per_sample,
+                samples: BASE64.encode(bytes),
+            }
+        }).collect(),
+    };
+
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Failed to create project file: {}", e))?;
+    serde_json::to_writer(std::io::BufWriter::new(file), &project)
+        .map_err(|e| format!("Failed to write project file: {}", e))
+}
+
+/// Read tracks from a project file
+///
+/// # Parameters
+/// * `path` - project file path
+///
+/// # Returns
+/// `Result<Vec<AudioTrack>, String>` - Ok with the saved tracks, Err if the file is unreadable or invalid
+pub fn load(path: &str) -> Result<Vec<AudioTrack>, String>
+{
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open project file: {}", e))?;
+    let project: ProjectFile = serde_json::from_reader(std::io::BufReader::new(file))
+        .map_err(|e| format!("Failed to parse project file: {}", e))?;
+
+    if project.version > PROJECT_VERSION
+    {
+        return Err(format!("Unsupported project version: {}", project.version));
+    }
+
+    project.tracks.into_iter().map(|track|
+    {
+        if track.channels == 0 || track.sample_rate == 0
+        {
+            return Err(format!("Invalid format for track '{}'", track.name));
+        }
+
+        let bytes = BASE64.decode(&track.samples)
+            .map_err(|e| format!("Invalid sample data for track '{}': {}", track.name, e))?;
+        if !bytes.len().is_multiple_of(4 * track.channels)
+        {
+            return Err(format!("Truncated sample data for track '{}'", track.name));
+        }
+
+        let audio_data = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+
+        Ok(AudioTrack
+        {
+            audio_data,
+            sample_rate: track.sample_rate,
+            channels: track.channels,
+            name: track.name,
+            start_offset: track.start_offset,
+            gain_envelope: track.gain_envelope,
+            metadata: track.metadata,
+            source_format: SourceFormat
+            {
+                codec: track.codec,
+                bits_per_sample: track.bits_per_sample,
+            },
+        })
+    }).collect()
+}
@@ -0,0 +1,298 @@
+//! Sample storage for tracks, held in memory or memory-mapped from disk
+
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, OnceLock};
+use crate::waveform::WaveformMipmap;
+
+/// Interleaved f32 samples of a track
+///
+/// # Notes
+/// Mapped storage is read-only and paged in by the OS as ranges are read, so a
+/// long recording costs address space rather than RAM. The first mutable access
+/// copies mapped samples into memory (copy-on-write), so edits work unchanged.
+/// Every mutable access marks the samples it may touch as stale in the cached
+/// waveform mipmap, and only those summaries are rebuilt on the next refresh.
+#[derive(Clone)]
+pub struct SampleStore
+{
+    data: SampleData,
+    mipmap: OnceLock<Arc<(usize, WaveformMipmap)>>,  // channel count the mipmap was built for, and the mipmap
+    stale: Option<StaleMipmap>,  // mipmap from before the latest edits, None once rebuilt or if never built
+}
+
+/// A cached mipmap and the samples edited since it was built
+#[derive(Clone)]
+struct StaleMipmap
+{
+    mipmap: Arc<(usize, WaveformMipmap)>,
+    start: usize,  // first edited sample
+    end: usize,  // sample after the last edited one
+}
+
+#[derive(Clone)]
+enum SampleData
+{
+    Owned(Vec<f32>),
+    Mapped(Arc<MappedSamples>),
+}
+
+/// Samples in an anonymous temporary file mapped into memory
+struct MappedSamples
+{
+    map: Option<Mmap>,  // None when there are no samples, since empty files cannot be mapped
+    len: usize,  // number of f32 samples
+}
+
+impl SampleStore
+{
+    fn new(data: SampleData) -> Self
+    {
+        SampleStore
+        {
+            data,
+            mipmap: OnceLock::new(),
+            stale: None,
+        }
+    }
+
+    /// Borrow the samples as a slice
+    ///
+    /// # Returns
+    /// `&[f32]` - interleaved samples
+    pub fn as_slice(&self) -> &[f32]
+    {
+        match self.data
+        {
+            SampleData::Owned(ref data) => data,
+            SampleData::Mapped(ref mapped) => match mapped.map
+            {
+                // the map is page aligned and was written as native-endian f32 values
+                Some(ref map) => unsafe { std::slice::from_raw_parts(map.as_ptr() as *const f32, mapped.len) },
+                None => &[],
+            },
+        }
+    }
+
+    /// Get the samples for modification, copying mapped samples into memory first
+    ///
+    /// # Returns
+    /// `&mut Vec<f32>` - owned interleaved samples
+    pub fn to_mut(&mut self) -> &mut Vec<f32>
+    {
+        self.edit_from(0)
+    }
+
+    /// Get the samples for an edit that leaves everything before a point untouched
+    ///
+    /// # Parameters
+    /// * `first_sample` - index of the first sample the edit may change, insert before, or remove
+    ///
+    /// # Returns
+    /// `&mut Vec<f32>` - owned interleaved samples
+    pub fn edit_from(&mut self, first_sample: usize) -> &mut Vec<f32>
+    {
+        self.mark_stale(first_sample, usize::MAX);
+        self.owned()
+    }
+
+    /// Get a range of samples for an edit that keeps the length unchanged
+    ///
+    /// # Parameters
+    /// * `start` - index of the first sample
+    /// * `end` - index after the last sample
+    ///
+    /// # Returns
+    /// `&mut [f32]` - the interleaved samples in the range
+    pub fn range_mut(&mut self, start: usize, end: usize) -> &mut [f32]
+    {
+        self.mark_stale(start, end);
+        &mut self.owned()[start..end]
+    }
+
+    /// Rebuild the parts of the waveform mipmap that edits made stale
+    ///
+    /// # Parameters
+    /// * `channels` - number of interleaved channels
+    ///
+    /// # Notes
+    /// Call after an edit so the next zoomed-out draw finds the mipmap ready instead of building it
+    pub fn refresh_mipmap(&mut self, channels: usize)
+    {
+        let _ = self.mipmap(channels);
+        self.stale = None;
+    }
+
+    /// Record that samples in a range may change
+    ///
+    /// # Parameters
+    /// * `start` - index of the first sample
+    /// * `end` - index after the last sample
+    fn mark_stale(&mut self, start: usize, end: usize)
+    {
+        if let Some(mipmap) = self.mipmap.take()
+        {
+            self.stale = Some(StaleMipmap { mipmap, start, end });
+        }
+        else if let Some(ref mut stale) = self.stale
+        {
+            stale.start = stale.start.min(start);
+            stale.end = stale.end.max(end);
+        }
+    }
+
+    /// Copy mapped samples into memory if needed and borrow them
+    ///
+    /// # Returns
+    /// `&mut Vec<f32>` - owned interleaved samples
+    fn owned(&mut self) -> &mut Vec<f32>
+    {
+        if let SampleData::Mapped(_) = self.data
+        {
+            self.data = SampleData::Owned(self.as_slice().to_vec());
+        }
+        match self.data
+        {
+            SampleData::Owned(ref mut data) => data,
+            SampleData::Mapped(_) => unreachable!(),
+        }
+    }
+
+    /// Get the waveform mipmap of the samples, building it on first use
+    ///
+    /// # Parameters
+    /// * `channels` - number of interleaved channels
+    ///
+    /// # Returns
+    /// `Option<&WaveformMipmap>` - the cached mipmap, None if it was built for a different channel count
+    ///
+    /// # Notes
+    /// After an edit only the summaries covering the edited samples are recomputed
+    pub fn mipmap(&self, channels: usize) -> Option<&WaveformMipmap>
+    {
+        let cached = self.mipmap.get_or_init(|| match self.stale
+        {
+            Some(ref stale) if stale.mipmap.0 == channels =>
+            {
+                let mut mipmap = stale.mipmap.1.clone();
+                mipmap.update(self.as_slice(), channels, stale.start / channels, stale.end.div_ceil(channels));
+                Arc::new((channels, mipmap))
+            }
+            _ => Arc::new((channels, WaveformMipmap::build(self.as_slice(), channels))),
+        });
+        if cached.0 == channels
+        {
+            Some(&cached.1)
+        }
+        else
+        {
+            None
+        }
+    }
+}
+
+impl Deref for SampleStore
+{
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32]
+    {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for SampleStore
+{
+    fn deref_mut(&mut self) -> &mut [f32]
+    {
+        self.to_mut()
+    }
+}
+
+impl From<Vec<f32>> for SampleStore
+{
+    fn from(data: Vec<f32>) -> Self
+    {
+        SampleStore::new(SampleData::Owned(data))
+    }
+}
+
+impl FromIterator<f32> for SampleStore
+{
+    fn from_iter<I: IntoIterator<Item = f32>>(iter: I) -> Self
+    {
+        SampleStore::new(SampleData::Owned(iter.into_iter().collect()))
+    }
+}
+
+/// Incrementally writes samples to a temporary file for mapping
+pub struct MappedSampleWriter
+{
+    writer: BufWriter<File>,
+    len: usize,
+}
+
+impl MappedSampleWriter
+{
+    /// Create a writer backed by a new anonymous temporary file
+    ///
+    /// # Returns
+    /// `Result<Self, String>` - Ok with the writer
+    ///
+    /// # Notes
+    /// The file is removed by the OS once the writer and any map of it are closed
+    pub fn new() -> Result<Self, String>
+    {
+        let file = tempfile::tempfile().map_err(|e| format!("Failed to create sample file: {}", e))?;
+        Ok(MappedSampleWriter
+        {
+            writer: BufWriter::new(file),
+            len: 0,
+        })
+    }
+
+    /// Append samples
+    ///
+    /// # Parameters
+    /// * `samples` - interleaved samples
+    ///
+    /// # Returns
+    /// `Result<(), String>` - Ok if successful
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String>
+    {
+        for sample in samples
+        {
+            self.writer
+                .write_all(&sample.to_ne_bytes())
+                .map_err(|e| format!("Failed to write sample file: {}", e))?;
+        }
+        self.len += samples.len();
+        Ok(())
+    }
+
+    /// Finish writing and map the file
+    ///
+    /// # Returns
+    /// `Result<SampleStore, String>` - Ok with mapped storage
+    pub fn finish(self) -> Result<SampleStore, String>
+    {
+        let len = self.len;
+        let file = self.writer
+            .into_inner()
+            .map_err(|e| format!("Failed to write sample file: {}", e))?;
+
+        let map = if len == 0
+        {
+            None
+        }
+        else
+        {
+            // the file is private to this process and never written again
+            Some(unsafe { Mmap::map(&file) }.map_err(|e| format!("Failed to map sample file: {}", e))?)
+        };
+
+        Ok(SampleStore::new(SampleData::Mapped(Arc::new(MappedSamples { map, len }))))
+    }
+}
@@ -0,0 +1,269 @@
+//! Multi-resolution min/max summaries for fast waveform drawing
+
+/// Number of frames summarized by each entry of the finest mip level
+pub const MIPMAP_BASE_FRAMES: usize = 64;
+
+/// Peak summary of a span of frames as (min_l, max_l, min_r, max_r)
+pub type Peaks = (f32, f32, f32, f32);
+
+/// Min/max pyramid over a track's frames
+///
+/// # Notes
+/// Level 0 summarizes blocks of `MIPMAP_BASE_FRAMES` frames and each further level
+/// halves the previous one, so any frame range can be covered by raw frames at its
+/// unaligned ends plus O(log n) summaries. The left value is the first channel and
+/// the right value is the second channel of stereo tracks, or the first otherwise.
+#[derive(Clone)]
+pub struct WaveformMipmap
+{
+    levels: Vec<Vec<Peaks>>,
+    frames: usize,  // frame count of the samples last summarized
+}
+
+impl WaveformMipmap
+{
+    /// Build the pyramid for interleaved samples
+    ///
+    /// # Parameters
+    /// * `data` - interleaved samples
+    /// * `channels` - number of channels
+    ///
+    /// # Returns
+    /// `WaveformMipmap` - summaries of every level down to a single entry
+    pub fn build(data: &[f32], channels: usize) -> Self
+    {
+        let mut mipmap = WaveformMipmap
+        {
+            levels: vec![Vec::new()],
+            frames: 0,
+        };
+        mipmap.update(data, channels, 0, data.len() / channels);
+        mipmap
+    }
+
+    /// Bring the pyramid up to date after a range of frames changed
+    ///
+    /// # Parameters
+    /// * `data` - interleaved samples after the change
+    /// * `channels` - number of channels
+    /// * `start_frame` - first frame that changed
+    /// * `end_frame` - frame after the last frame that changed
+    ///
+    /// # Notes
+    /// Only the summaries covering the range and their parents are recomputed. If the
+    /// frame count changed, every frame from `start_frame` on is treated as changed,
+    /// since an insert or delete shifts everything after it.
+    pub fn update(&mut self, data: &[f32], channels: usize, start_frame: usize, end_frame: usize)
+    {
+        let frames = data.len() / channels;
+        let blocks = frames.div_ceil(MIPMAP_BASE_FRAMES);
+        let end_frame = if frames == self.frames { end_frame.min(frames) } else { frames };
+        self.frames = frames;
+
+        // a shrink also changes the last remaining block, so start no later than it
+        let mut lo = (start_frame / MIPMAP_BASE_FRAMES).min(blocks.saturating_sub(1));
+        let mut hi = end_frame.div_ceil(MIPMAP_BASE_FRAMES);
+
+        let base = &mut self.levels[0];
+        base.resize(blocks, (0.0, 0.0, 0.0, 0.0));
+        for (block, peaks) in base.iter_mut().enumerate().take(hi).skip(lo)
+        {
+            let start = block * MIPMAP_BASE_FRAMES;
+            let end = (start + MIPMAP_BASE_FRAMES).min(frames);
+            let (left, right) = frame_pair(data, channels, start);
+            *peaks = (left, left, right, right);
+            accumulate_frames(peaks, data, channels, start + 1, end);
+        }
+
+        let mut level = 1;
+        while self.levels[level - 1].len() > 1
+        {
+            let len = self.levels[level - 1].len().div_ceil(2);
+            if self.levels.len() == level
+            {
+                // a level the pyramid did not have yet is summarized in full
+                self.levels.push(Vec::new());
+                lo = 0;
+                hi = len * 2;
+            }
+
+            let (below, above) = self.levels.split_at_mut(level);
+            let below = &below[level - 1];
+            let current = &mut above[0];
+            current.resize(len, (0.0, 0.0, 0.0, 0.0));
+
+            lo /= 2;
+            hi = hi.div_ceil(2).min(len);
+            for (index, peaks) in current.iter_mut().enumerate().take(hi).skip(lo)
+            {
+                *peaks = below[index * 2];
+                if let Some(&other) = below.get(index * 2 + 1)
+                {
+                    merge(peaks, other);
+                }
+            }
+            level += 1;
+        }
+        self.levels.truncate(level);
+    }
+
+    /// Get the peaks of a frame range
+    ///
+    /// # Parameters
+    /// * `data` - interleaved samples the pyramid was built from
+    /// * `channels` - number of channels
+    /// * `start_frame` - first frame of the range
+    /// * `end_frame` - frame after the last frame of the range
+    ///
+    /// # Returns
+    /// `Peaks` - the same values `scan_peaks` gives for the range
+    pub fn peaks(&self, data: &[f32], channels: usize, start_frame: usize, end_frame: usize) -> Peaks
+    {
+        let mut lo = start_frame.div_ceil(MIPMAP_BASE_FRAMES);
+        let mut hi = end_frame / MIPMAP_BASE_FRAMES;
+
+        if lo >= hi
+        {
+            return scan_peaks(data, channels, start_frame, end_frame);
+        }
+
+        // raw frames before the first and after the last whole block
+        let mut peaks = scan_peaks(data, channels, start_frame, lo * MIPMAP_BASE_FRAMES);
+        accumulate_frames(&mut peaks, data, channels, hi * MIPMAP_BASE_FRAMES, end_frame);
+
+        // walk up the levels taking whichever end entries are not part of a whole parent
+        for level in &self.levels
+        {
+            if lo >= hi
+            {
+                break;
+            }
+            if lo % 2 == 1
+            {
+                merge(&mut peaks, level[lo]);
+                lo += 1;
+            }
+            if hi % 2 == 1
+            {
+                hi -= 1;
+                merge(&mut peaks, level[hi]);
+            }
+            lo /= 2;
+            hi /= 2;
+        }
+
+        peaks
+    }
+}
+
+/// Scan the peaks of a frame range directly from the samples
+///
+/// # Parameters
+/// * `data` - interleaved samples
+/// * `channels` - number of channels
+/// * `start_frame` - first frame of the range
+/// * `end_frame` - frame after the last frame of the range
+///
+/// # Returns
+/// `Peaks` - minimums and maximums, which always include zero
+pub fn scan_peaks(data: &[f32], channels: usize, start_frame: usize, end_frame: usize) -> Peaks
+{
+    let mut peaks = (0.0, 0.0, 0.0, 0.0);
+    accumulate_frames(&mut peaks, data, channels, start_frame, end_frame);
+    peaks
+}
+
+/// Get the left and right display values of one frame
+///
+/// # Parameters
+/// * `data` - interleaved samples
+/// * `channels` - number of channels
+/// * `frame` - frame index
+///
+/// # Returns
+/// `(f32, f32)` - (left, right), both the first channel unless the track is stereo
+pub fn frame_pair(data: &[f32], channels: usize, frame: usize) -> (f32, f32)
+{
+    let idx = frame * channels;
+    if channels == 2
+    {
+        (data[idx], data[idx + 1])
+    }
+    else
+    {
+        (data[idx], data[idx])
+    }
+}
+
+/// Widen peaks to include a range of frames
+fn accumulate_frames(peaks: &mut Peaks, data: &[f32], channels: usize, start_frame: usize, end_frame: usize)
+{
+    for frame in start_frame..end_frame
+    {
+        let (left, right) = frame_pair(data, channels, frame);
+        merge(peaks, (left, left, right, right));
+    }
+}
+
+/// Widen peaks to include another summary
+fn merge(peaks: &mut Peaks, other: Peaks)
+{
+    peaks.0 = peaks.0.min(other.0);
+    peaks.1 = peaks.1.max(other.1);
+    peaks.2 = peaks.2.min(other.2);
+    peaks.3 = peaks.3.max(other.3);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::generators;
+
+    /// Frame ranges that start and end on and off block boundaries, short and long
+    fn test_ranges(frames: usize) -> Vec<(usize, usize)>
+    {
+        let mut ranges = vec![(0, frames), (0, 1), (frames - 1, frames), (63, 65), (64, 128), (100, 9000), (5000, 5001)];
+        ranges.extend((0..frames).step_by(997).map(|start| (start, (start + 2345).min(frames))));
+        ranges
+    }
+
+    #[test]
+    fn mipmap_peaks_match_direct_scan()
+    {
+        for channels in [1, 2, 4]
+        {
+            let data = generators::white_noise(10007, channels, channels as u64);
+            let mipmap = WaveformMipmap::build(&data, channels);
+
+            for (start, end) in test_ranges(10007)
+            {
+                assert_eq!(mipmap.peaks(&data, channels, start, end), scan_peaks(&data, channels, start, end));
+            }
+        }
+    }
+
+    #[test]
+    fn update_matches_rebuild()
+    {
+        let mut data = generators::white_noise(10007, 2, 1);
+        let mut mipmap = WaveformMipmap::build(&data, 2);
+
+        // an edit in place
+        data[2 * 3000..2 * 3100].fill(1.0);
+        mipmap.update(&data, 2, 3000, 3100);
+        assert!(mipmap.levels == WaveformMipmap::build(&data, 2).levels);
+
+        // an insert shifts every frame after it
+        data.splice(2 * 500..2 * 500, vec![-1.0; 2 * 7000]);
+        mipmap.update(&data, 2, 500, 7500);
+        assert!(mipmap.levels == WaveformMipmap::build(&data, 2).levels);
+
+        // a delete shrinks the pyramid
+        data.drain(2 * 200..2 * 16000);
+        mipmap.update(&data, 2, 200, 200);
+        let rebuilt = WaveformMipmap::build(&data, 2);
+        assert!(mipmap.levels == rebuilt.levels);
+        assert_eq!(mipmap.frames, rebuilt.frames);
+    }
+}